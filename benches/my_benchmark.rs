@@ -8,8 +8,6 @@ extern crate rug;
 extern crate schnorr_edu;
 extern crate secp256k1;
 
-use apint::ApInt;
-use apint::BitWidth;
 use apint::UInt;
 use criterion::AxisScale;
 use criterion::Bencher;
@@ -25,13 +23,20 @@ use rand::Rng;
 use rand::RngCore;
 use rug::Assign;
 use rug::Integer;
+#[cfg(feature = "backend-apint")]
+use schnorr_edu::backend::Apint;
+#[cfg(feature = "backend-num-bigint")]
+use schnorr_edu::backend::NumBigint;
+#[cfg(feature = "backend-rug")]
+use schnorr_edu::backend::Rug;
+use schnorr_edu::backend::native;
+use schnorr_edu::backend::{FieldBackend, Native};
+use schnorr_edu::batch::{verify_many, BatchItem};
 use schnorr_edu::context::*;
 use schnorr_edu::point::*;
 use schnorr_edu::scalar::*;
-use schnorr_edu::util::apint::mixed_point_add_apint;
-use schnorr_edu::util::apint::JacobianPointApInt;
-use schnorr_edu::util::apint::PointApInt;
 use schnorr_edu::util::rug::integer_from_bytes;
+use schnorr_edu::util::multi_scalar_mul;
 use schnorr_edu::util::shamir::shamirs_trick;
 use schnorr_edu::*;
 use secp256k1::key::PublicKey;
@@ -39,6 +44,7 @@ use secp256k1::key::SecretKey;
 use secp256k1::Message;
 use secp256k1::Secp256k1;
 use std::ops::Sub;
+use std::time::Instant;
 use std::ops::{Div, Mul, MulAssign, Rem};
 
 #[derive(Debug)]
@@ -123,6 +129,113 @@ fn benchmark_int_libraries(c: &mut Criterion) {
 
 fn benchmark_rugs(c: &mut Criterion) {}
 
+#[derive(Clone, Debug)]
+struct FieldInputs {
+    rugs: Vec<Integer>,
+    field_elements: Vec<FieldElement>,
+}
+
+// the 5x52 limbs FieldElement behind ScalarP against the previous rug backend
+fn benchmark_field(c: &mut Criterion) {
+    let mut rng = thread_rng();
+    let mut rugs = Vec::new();
+    let mut field_elements = Vec::new();
+    for _ in 0..1000usize {
+        let el: ScalarP = rng.gen();
+        rugs.push(el.to_integer());
+        field_elements.push(el.0);
+    }
+    let inputs = FieldInputs {
+        rugs,
+        field_elements,
+    };
+
+    let fun_rug = Fun::new("rug", |b: &mut Bencher, inputs: &FieldInputs| {
+        b.iter(|| {
+            let a = thread_rng().choose(&inputs.rugs).unwrap();
+            let b = thread_rng().choose(&inputs.rugs).unwrap();
+            let result: Integer = (a * b).into();
+            criterion::black_box(result % &CONTEXT.p);
+        })
+    });
+    let fun_5x52 = Fun::new("5x52", |b: &mut Bencher, inputs: &FieldInputs| {
+        b.iter(|| {
+            let a = thread_rng().choose(&inputs.field_elements).unwrap();
+            let b = thread_rng().choose(&inputs.field_elements).unwrap();
+            criterion::black_box(*a * *b);
+        })
+    });
+    c.bench_functions("field mul", vec![fun_rug, fun_5x52], inputs.clone());
+
+    let fun_rug = Fun::new("rug", |b: &mut Bencher, inputs: &FieldInputs| {
+        b.iter(|| {
+            let a = thread_rng().choose(&inputs.rugs).unwrap();
+            let result = a.to_owned().pow_mod(&CONTEXT.p_sub2.to_integer(), &CONTEXT.p);
+            criterion::black_box(result.unwrap());
+        })
+    });
+    let fun_5x52 = Fun::new("5x52", |b: &mut Bencher, inputs: &FieldInputs| {
+        b.iter(|| {
+            let a = thread_rng().choose(&inputs.field_elements).unwrap();
+            criterion::black_box(a.inv());
+        })
+    });
+    c.bench_functions("field inv", vec![fun_rug, fun_5x52], inputs);
+}
+
+#[derive(Clone, Debug)]
+struct ScalarInputs {
+    rugs: Vec<Integer>,
+    scalar_elements: Vec<ScalarElement>,
+}
+
+// the 4x64 limbs ScalarElement behind ScalarN against the previous rug backend
+fn benchmark_scalar(c: &mut Criterion) {
+    let mut rng = thread_rng();
+    let mut rugs = Vec::new();
+    let mut scalar_elements = Vec::new();
+    for _ in 0..1000usize {
+        let el: ScalarN = rng.gen();
+        rugs.push(el.to_integer());
+        scalar_elements.push(el.0);
+    }
+    let inputs = ScalarInputs {
+        rugs,
+        scalar_elements,
+    };
+
+    let fun_rug = Fun::new("rug", |b: &mut Bencher, inputs: &ScalarInputs| {
+        b.iter(|| {
+            let a = thread_rng().choose(&inputs.rugs).unwrap();
+            let b = thread_rng().choose(&inputs.rugs).unwrap();
+            let result: Integer = (a * b).into();
+            criterion::black_box(result % &CONTEXT.n);
+        })
+    });
+    let fun_4x64 = Fun::new("4x64", |b: &mut Bencher, inputs: &ScalarInputs| {
+        b.iter(|| {
+            let a = thread_rng().choose(&inputs.scalar_elements).unwrap();
+            let b = thread_rng().choose(&inputs.scalar_elements).unwrap();
+            criterion::black_box(*a * *b);
+        })
+    });
+    c.bench_functions("scalar mul", vec![fun_rug, fun_4x64], inputs.clone());
+
+    let fun_rug = Fun::new("rug", |b: &mut Bencher, inputs: &ScalarInputs| {
+        b.iter(|| {
+            let a = thread_rng().choose(&inputs.rugs).unwrap();
+            criterion::black_box(a.to_owned().invert(&CONTEXT.n).unwrap());
+        })
+    });
+    let fun_4x64 = Fun::new("4x64", |b: &mut Bencher, inputs: &ScalarInputs| {
+        b.iter(|| {
+            let a = thread_rng().choose(&inputs.scalar_elements).unwrap();
+            criterion::black_box(a.inv());
+        })
+    });
+    c.bench_functions("scalar inv", vec![fun_rug, fun_4x64], inputs);
+}
+
 fn benchmark_biguint(c: &mut Criterion) {
     let mut rng = thread_rng();
     let total = 1000usize;
@@ -147,9 +260,9 @@ fn benchmark_biguint(c: &mut Criterion) {
     let numbers = numbers_orig.clone();
     c.bench_function("ScalarP modpow one", move |b| {
         b.iter(|| {
-            let a = ScalarP(Integer::from(1));
+            let a = ScalarP::from_u64(1);
             let b = rand::thread_rng().choose(&numbers).unwrap();
-            let result = a.pow(&ScalarP(b.to_owned()));
+            let result = a.pow(&ScalarP::new(b.to_owned()));
             criterion::black_box(result);
         })
     });
@@ -157,9 +270,9 @@ fn benchmark_biguint(c: &mut Criterion) {
     let numbers = numbers_orig.clone();
     c.bench_function("ScalarP mul one", move |b| {
         b.iter(|| {
-            let a = ScalarP(Integer::from(1));
+            let a = ScalarP::from_u64(1);
             let b = rand::thread_rng().choose(&numbers).unwrap();
-            let result = a.mul(&ScalarP(b.to_owned()));
+            let result = a.mul(&ScalarP::new(b.to_owned()));
             criterion::black_box(result);
         })
     });
@@ -260,7 +373,7 @@ fn benchmark_verify(c: &mut Criterion) {
     let precomputed_signatures = 100usize;
     for _ in 0..precomputed_signatures {
         let sec_key = thread_rng().gen();
-        let signature = schnorr_sign(&msg, &sec_key);
+        let signature = schnorr_sign(&msg, &sec_key, &[0u8; 32]);
         let pub_key = point_mul(CONTEXT.G.clone(), sec_key).unwrap();
         signatures_orig.push(signature);
         pub_keys_orig.push(pub_key);
@@ -298,10 +411,10 @@ fn benchmark_batch_verify(c: &mut Criterion) {
     let mut messages_orig = Vec::new();
     let precomputed_signatures = 1000usize;
     for _ in 0..precomputed_signatures {
-        let sec_key = rng.gen();
+        let sec_key: ScalarN = rng.gen();
         rng.fill_bytes(&mut msg);
-        let signature = schnorr_sign(&msg, &sec_key);
-        let pub_key: Point = generator_mul(&sec_key).unwrap().into();
+        let signature = schnorr_sign(&msg, &sec_key, &[0u8; 32]);
+        let pub_key: native::Point = generator_mul(&sec_key).unwrap().into();
         signatures_orig.push(signature);
         pub_keys_orig.push(pub_key);
         messages_orig.push(msg);
@@ -319,29 +432,50 @@ fn benchmark_batch_verify(c: &mut Criterion) {
     } ));
     */
 
-    /*let signatures = signatures_orig.clone();
-    let pub_keys = pub_keys_orig.clone();
+    let signatures = signatures_orig.clone();
+    let pub_keys: Vec<native::NormalizedPoint> = pub_keys_orig.iter().cloned().map(NormalizedPoint::from).collect();
     let messages = messages_orig.clone();
+    let (messages_many, pub_keys_many, signatures_many) = (messages.clone(), pub_keys.clone(), signatures.clone());
     let plot_config = PlotConfiguration::default().summary_scale(AxisScale::Logarithmic);
     let benchmark = ParameterizedBenchmark::new(
         "Schnorr Batch Ver",
         move |b, &&size| {
+            let batch: Vec<BatchItem> = (0..size).map(|i| (&messages[i][..], &pub_keys[i], &signatures[i])).collect();
             b.iter(|| {
-                let result = schnorr_batch_verify(
-                    &messages[..size].to_vec(),
-                    &pub_keys[..size].to_vec(),
-                    &signatures[..size].to_vec(),
-                );
-                criterion::black_box(result);
-                assert!(result);
+                let result = schnorr_batch_verify(&batch);
+                criterion::black_box(result).unwrap();
             })
         },
         &[10, 20, 40, 80, 160, 320, 640],
     )
+    .with_function("verify_many", move |b, &&size| {
+        let batch: Vec<BatchItem> = (0..size).map(|i| (&messages_many[i][..], &pub_keys_many[i], &signatures_many[i])).collect();
+        b.iter(|| criterion::black_box(verify_many(&batch)))
+    })
+    .throughput(|size| Throughput::Elements(**size as u32))
+    .plot_config(plot_config);
+
+    c.bench("Schnorr Batch Ver", benchmark);
+
+    // the crossover of `multi_scalar_mul::PIPPENGER_THRESHOLD`, batch verification has 2u + 1 terms
+    let terms: Vec<(ScalarN, native::JacobianPoint)> = pub_keys_orig
+        .iter()
+        .map(|pub_key| (rng.gen(), JacobianPoint::from(pub_key.clone())))
+        .collect();
+    let terms_pippenger = terms.clone();
+    let plot_config = PlotConfiguration::default().summary_scale(AxisScale::Logarithmic);
+    let benchmark = ParameterizedBenchmark::new(
+        "strauss",
+        move |b, &&size| b.iter(|| criterion::black_box(multi_scalar_mul::strauss(&terms[..size]))),
+        &[8, 16, 32, 64, 128, 256, 512, 1000],
+    )
+    .with_function("pippenger", move |b, &&size| {
+        b.iter(|| criterion::black_box(multi_scalar_mul::pippenger(&terms_pippenger[..size])))
+    })
     .throughput(|size| Throughput::Elements(**size as u32))
     .plot_config(plot_config);
 
-    c.bench("Schnorr Batch Ver", benchmark);*/
+    c.bench("multi scalar mul", benchmark);
 
     /*
     c.bench_function_over_inputs("Schnorr Batch Ver",move |b, &&size| b.iter(|| {
@@ -354,14 +488,102 @@ fn benchmark_batch_verify(c: &mut Criterion) {
     */
 }
 
+#[derive(Clone, Debug)]
+struct BackendInputs {
+    msg: [u8; 32],
+    sec_key: ScalarN,
+    aux_rand: [u8; 32],
+}
+
+fn backend_sign<B: FieldBackend>() -> Fun<BackendInputs> {
+    Fun::new(B::NAME, |b: &mut Bencher, inputs: &BackendInputs| {
+        let sec_key = B::scalar(&inputs.sec_key);
+        b.iter(|| criterion::black_box(schnorr_sign(&inputs.msg, &sec_key, &inputs.aux_rand)))
+    })
+}
+
+fn backend_verify<B: FieldBackend>() -> Fun<BackendInputs> {
+    Fun::new(B::NAME, |b: &mut Bencher, inputs: &BackendInputs| {
+        let sec_key = B::scalar(&inputs.sec_key);
+        let signature = schnorr_sign(&inputs.msg, &sec_key, &inputs.aux_rand);
+        let pub_key = NormalizedPoint::from(Point::from(generator_mul(&sec_key).unwrap()));
+        b.iter(|| criterion::black_box(schnorr_verify(&inputs.msg, &pub_key, &signature)))
+    })
+}
+
+// the same sign and verify code on every backend enabled with the cargo features,
+// `cargo bench --features all-backends -- backend`
+fn benchmark_backends(c: &mut Criterion) {
+    let mut rng = thread_rng();
+    let inputs = BackendInputs {
+        msg: rng.gen(),
+        sec_key: rng.gen(),
+        aux_rand: rng.gen(),
+    };
+
+    // only pushed to when another backend is built
+    #[allow(unused_mut)]
+    let mut sign = vec![backend_sign::<Native>()];
+    #[allow(unused_mut)]
+    let mut verify = vec![backend_verify::<Native>()];
+    #[cfg(feature = "backend-rug")]
+    {
+        sign.push(backend_sign::<Rug>());
+        verify.push(backend_verify::<Rug>());
+    }
+    #[cfg(feature = "backend-num-bigint")]
+    {
+        sign.push(backend_sign::<NumBigint>());
+        verify.push(backend_verify::<NumBigint>());
+    }
+    #[cfg(feature = "backend-apint")]
+    {
+        sign.push(backend_sign::<Apint>());
+        verify.push(backend_verify::<Apint>());
+    }
+    c.bench_functions("backend sign", sign, inputs.clone());
+    c.bench_functions("backend verify", verify, inputs);
+}
+
+// time of `generator_mul` against the memory of the table, `cargo bench -- "G mul table"`
+fn benchmark_generator_tables(c: &mut Criterion) {
+    let mut functions = Vec::new();
+    for (layout, window) in [
+        (Layout::Comb, 4usize),
+        (Layout::Comb, 6),
+        (Layout::Comb, 8),
+        (Layout::Windows, 4),
+        (Layout::Windows, 6),
+        (Layout::Windows, 8),
+        (Layout::Windows, 16),
+    ]
+    .iter()
+    {
+        let start = Instant::now();
+        let table = match layout {
+            Layout::Comb => GeneratorTable::comb(*window),
+            Layout::Windows => GeneratorTable::windows(*window),
+        };
+        let name = format!("{:?} {} ({} points, {} KiB)", layout, window, table.len(), table.size_in_bytes() / 1024);
+        println!("{} built in {:?}", name, start.elapsed());
+        functions.push(Fun::new(&name, move |b: &mut Bencher, _: &()| {
+            b.iter(|| {
+                let sec_key: ScalarN = thread_rng().gen();
+                criterion::black_box(table.mul(&sec_key))
+            })
+        }));
+    }
+    c.bench_functions("G mul table", functions, ());
+}
+
 fn benchmark_sign(c: &mut Criterion) {
     let mut rng = thread_rng();
     let mut msg = [0u8; 32];
     c.bench_function("Schnorr sign", move |b| {
         b.iter(|| {
             rng.fill_bytes(&mut msg);
-            let sec_key = rng.gen();
-            let signature = schnorr_sign(&msg, &sec_key);
+            let sec_key: ScalarN = rng.gen();
+            let signature = schnorr_sign(&msg, &sec_key, &[0u8; 32]);
             criterion::black_box(signature);
         })
     });
@@ -475,33 +697,6 @@ fn benchmark_point(c: &mut Criterion) {
         })
     });
 
-    let mut j_points_apint = Vec::new();
-    let mut a_points_apint = Vec::new();
-
-    let total = 200usize;
-    for _ in 0..total {
-        j_points_apint.push(JacobianPointApInt {
-            x: ApInt::random_with_width(BitWidth::new(256).unwrap()),
-            y: ApInt::random_with_width(BitWidth::new(256).unwrap()),
-            z: ApInt::random_with_width(BitWidth::new(256).unwrap()),
-        });
-
-        a_points_apint.push(PointApInt {
-            x: ApInt::random_with_width(BitWidth::new(256).unwrap()),
-            y: ApInt::random_with_width(BitWidth::new(256).unwrap()),
-        });
-    }
-
-    /*
-    c.bench_function("EC mixed Point2 adding",move |b|
-        b.iter(|| {
-            let a = thread_rng().choose(&a_points_apint).unwrap();
-            let b = thread_rng().choose(&j_points_apint).unwrap();
-            let point = mixed_point_add_apint(Some(b),Some(a));
-            criterion::black_box(point);
-        }));
-    */
-
     let points = points_orig.clone();
     c.bench_function("EC Jac Point add", move |b| {
         b.iter(|| {
@@ -532,8 +727,9 @@ fn benchmark_point(c: &mut Criterion) {
     });
 
     let points = points_orig.clone();
+    let points_glv = points_orig.clone();
     let benchmark = ParameterizedBenchmark::new(
-        "EC Jac P mul wnaf",
+        "wnaf",
         move |b, &&size| {
             b.iter(|| {
                 let sec_key: ScalarN = thread_rng().gen();
@@ -543,9 +739,17 @@ fn benchmark_point(c: &mut Criterion) {
             })
         },
         &[2i8, 3, 4, 5, 6, 7],
-    );
+    )
+    .with_function("glv", move |b, &&size| {
+        b.iter(|| {
+            let sec_key: ScalarN = thread_rng().gen();
+            let a = thread_rng().choose(&points_glv).unwrap();
+            let point = jacobian_point_mul_glv(a, &sec_key, size);
+            criterion::black_box(point);
+        })
+    });
 
-    c.bench("EC Jac P mul wnaf", benchmark);
+    c.bench("EC Jac P mul wnaf vs glv", benchmark);
 
     c.bench_function("G JPoint mul", move |b| {
         b.iter(|| {
@@ -589,7 +793,7 @@ criterion_group! {
     name = benches;
     config = Criterion::default().sample_size(10);
     //config = Criterion::default().sample_size(2).without_plots();
-    targets = benchmark_biguint, benchmark_point, benchmark_verify, benchmark_batch_verify, benchmark_sign, benchmark_int_libraries
+    targets = benchmark_biguint, benchmark_field, benchmark_scalar, benchmark_backends, benchmark_point, benchmark_generator_tables, benchmark_verify, benchmark_batch_verify, benchmark_sign, benchmark_int_libraries
 }
 
 criterion_main!(benches);
@@ -1,21 +1,163 @@
 // build.rs
 
+// Generates the table of `generator_mul` into OUT_DIR, which `context.rs` embeds with
+// `include_bytes!`. For every window of G_MUL_WINDOW bits of the scalar (8 by default, from 1 to
+// 8 with the environment variable SCHNORR_EDU_G_MUL_WINDOW) there are the 2^G_MUL_WINDOW
+// multiples digit * 2^(window * row) * G as 64 bytes x || y, the digit 0 holds G. The build
+// script can't use the crate, so the arithmetic is redone here with num-bigint, in jacobian
+// coordinates with a single inversion at the end. With the cargo feature `lazy-g-mul-table`
+// nothing is generated and the crate computes the table in memory at the first use.
+
+extern crate num_bigint;
+
+use num_bigint::BigUint;
 use std::env;
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
 
+const P_HEX: &[u8] = b"FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F";
+const GX_HEX: &[u8] = b"79BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798";
+const GY_HEX: &[u8] = b"483ADA7726A3C4655DA4FBFC0E1108A8FD17B448A68554199C47D08FFB10D4B8";
+
+#[derive(Clone)]
+struct JacobianPoint {
+    x: BigUint,
+    y: BigUint,
+    z: BigUint,
+}
+
+struct Field {
+    p: BigUint,
+}
+
+impl Field {
+    fn mul(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        (a * b) % &self.p
+    }
+
+    fn add(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        (a + b) % &self.p
+    }
+
+    fn sub(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        (a + &self.p - b) % &self.p
+    }
+
+    fn double(&self, a: &JacobianPoint) -> JacobianPoint {
+        let y2 = self.mul(&a.y, &a.y);
+        let s = self.mul(&BigUint::from(4u8), &self.mul(&a.x, &y2));
+        let m = self.mul(&BigUint::from(3u8), &self.mul(&a.x, &a.x));
+        let x = self.sub(&self.mul(&m, &m), &self.add(&s, &s));
+        let y4_8 = self.mul(&BigUint::from(8u8), &self.mul(&y2, &y2));
+        let y = self.sub(&self.mul(&m, &self.sub(&s, &x)), &y4_8);
+        let z = self.mul(&BigUint::from(2u8), &self.mul(&a.y, &a.z));
+        JacobianPoint { x, y, z }
+    }
+
+    // a + b for different points
+    fn add_points(&self, a: &JacobianPoint, b: &JacobianPoint) -> JacobianPoint {
+        let a_z2 = self.mul(&a.z, &a.z);
+        let b_z2 = self.mul(&b.z, &b.z);
+        let u1 = self.mul(&a.x, &b_z2);
+        let u2 = self.mul(&b.x, &a_z2);
+        let s1 = self.mul(&a.y, &self.mul(&b_z2, &b.z));
+        let s2 = self.mul(&b.y, &self.mul(&a_z2, &a.z));
+        let h = self.sub(&u2, &u1);
+        let r = self.sub(&s2, &s1);
+        let h2 = self.mul(&h, &h);
+        let h3 = self.mul(&h2, &h);
+        let u1_h2 = self.mul(&u1, &h2);
+        let x = self.sub(&self.sub(&self.mul(&r, &r), &h3), &self.add(&u1_h2, &u1_h2));
+        let y = self.sub(&self.mul(&r, &self.sub(&u1_h2, &x)), &self.mul(&s1, &h3));
+        let z = self.mul(&h, &self.mul(&a.z, &b.z));
+        JacobianPoint { x, y, z }
+    }
+
+    // (x / z^2, y / z^3) of every point, with Montgomery's trick: one inversion for all the z
+    fn to_affine(&self, points: &[JacobianPoint]) -> Vec<(BigUint, BigUint)> {
+        let mut prefix = Vec::with_capacity(points.len());
+        let mut acc = BigUint::from(1u8);
+        for point in points.iter() {
+            prefix.push(acc.clone());
+            acc = self.mul(&acc, &point.z);
+        }
+        let mut inv = acc.modpow(&(&self.p - BigUint::from(2u8)), &self.p);
+        let mut result = vec![(BigUint::from(0u8), BigUint::from(0u8)); points.len()];
+        for i in (0..points.len()).rev() {
+            let z_inv = self.mul(&inv, &prefix[i]);
+            inv = self.mul(&inv, &points[i].z);
+            let z_inv2 = self.mul(&z_inv, &z_inv);
+            let z_inv3 = self.mul(&z_inv2, &z_inv);
+            result[i] = (self.mul(&points[i].x, &z_inv2), self.mul(&points[i].y, &z_inv3));
+        }
+        result
+    }
+}
+
+fn to_32_bytes(val: &BigUint) -> Vec<u8> {
+    let bytes = val.to_bytes_be();
+    let mut result = vec![0u8; 32 - bytes.len()];
+    result.extend_from_slice(&bytes);
+    result
+}
+
+fn g_mul_table(window: usize) -> Vec<u8> {
+    let field = Field {
+        p: BigUint::parse_bytes(P_HEX, 16).unwrap(),
+    };
+    let g = JacobianPoint {
+        x: BigUint::parse_bytes(GX_HEX, 16).unwrap(),
+        y: BigUint::parse_bytes(GY_HEX, 16).unwrap(),
+        z: BigUint::from(1u8),
+    };
+    let rows = 256usize.div_ceil(window);
+    let digits = 1usize << window;
+
+    let mut points = Vec::with_capacity(rows * digits);
+    let mut base = g.clone();
+    for _ in 0..rows {
+        points.push(g.clone());
+        let mut current = base.clone();
+        for digit in 1..digits {
+            points.push(current.clone());
+            current = if digit == 1 {
+                field.double(&base)
+            } else {
+                field.add_points(&current, &base)
+            };
+        }
+        for _ in 0..window {
+            base = field.double(&base);
+        }
+    }
+
+    let mut table = Vec::with_capacity(points.len() * 64);
+    for (x, y) in field.to_affine(&points).iter() {
+        table.extend(to_32_bytes(x));
+        table.extend(to_32_bytes(y));
+    }
+    table
+}
+
 fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=SCHNORR_EDU_G_MUL_WINDOW");
     let out_dir = env::var("OUT_DIR").unwrap();
-    let dest_path = Path::new(&out_dir).join("hello.rs");
-    let mut f = File::create(&dest_path).unwrap();
 
-    f.write_all(
-        b"
-        pub fn message() -> &'static str {
-            \"Hello, World!\"
-        }
-    ",
-    )
-    .unwrap();
+    let window: usize = match env::var("SCHNORR_EDU_G_MUL_WINDOW") {
+        Ok(val) => val.parse().expect("SCHNORR_EDU_G_MUL_WINDOW must be a number"),
+        Err(_) => 8,
+    };
+    assert!((1..=8).contains(&window), "SCHNORR_EDU_G_MUL_WINDOW must be between 1 and 8");
+    let mut f = File::create(Path::new(&out_dir).join("g_mul_window.rs")).unwrap();
+    writeln!(f, "pub const G_MUL_WINDOW: usize = {};", window).unwrap();
+
+    let table = if env::var("CARGO_FEATURE_LAZY_G_MUL_TABLE").is_ok() {
+        Vec::new()
+    } else {
+        g_mul_table(window)
+    };
+    let mut f = File::create(Path::new(&out_dir).join("g_mul_table.bin")).unwrap();
+    f.write_all(&table).unwrap();
 }
@@ -0,0 +1,108 @@
+extern crate rand;
+extern crate schnorr_edu;
+
+use rand::thread_rng;
+use rand::Rng;
+use schnorr_edu::constant_time;
+use schnorr_edu::point::generator_mul;
+use schnorr_edu::scalar::ScalarN;
+use std::env;
+use std::time::Instant;
+
+// Timing leakage test in the style of dudect (https://eprint.iacr.org/2016/1123): the function
+// runs on two classes of secret inputs, a fixed one and random ones, interleaved at random, and
+// Welch's t-test tells if the two timing distributions differ. |t| above 4.5 is a leak, below it
+// nothing was detected, which is not a proof. Run it in release mode on an idle machine,
+// `cargo run --release --example dudect [measurements]`.
+
+const THRESHOLD: f64 = 4.5;
+
+// mean and variance with Welford's online algorithm
+#[derive(Default)]
+struct Stats {
+    n: f64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Stats {
+    fn push(&mut self, x: f64) {
+        self.n += 1.0;
+        let delta = x - self.mean;
+        self.mean += delta / self.n;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    fn variance(&self) -> f64 {
+        self.m2 / (self.n - 1.0)
+    }
+}
+
+fn welch_t(a: &Stats, b: &Stats) -> f64 {
+    (a.mean - b.mean) / (a.variance() / a.n + b.variance() / b.n).sqrt()
+}
+
+fn measure<F: Fn(&ScalarN)>(name: &str, measurements: usize, f: F) {
+    let mut rng = thread_rng();
+    // a secret made of zero bytes but one is the worst case for the byte table lookup
+    let fixed = ScalarN::from_u64(1);
+    // the inputs are prepared before measuring, so that generating them does not disturb the caches
+    let inputs: Vec<(bool, ScalarN)> = (0..measurements)
+        .map(|_| {
+            let class = rng.gen::<bool>();
+            (class, if class { rng.gen() } else { fixed.clone() })
+        })
+        .collect();
+    let mut samples = Vec::with_capacity(measurements);
+    for (class, input) in inputs.iter() {
+        let start = Instant::now();
+        f(input);
+        let elapsed = start.elapsed();
+        samples.push((*class, elapsed.as_secs() as f64 * 1e9 + f64::from(elapsed.subsec_nanos())));
+    }
+
+    // like dudect, the slowest measurements are mostly noise of the machine and are cropped
+    let mut times: Vec<f64> = samples.iter().map(|(_, t)| *t).collect();
+    times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let crop = times[times.len() * 9 / 10];
+
+    let (mut fixed_stats, mut random_stats) = (Stats::default(), Stats::default());
+    for (class, time) in samples.iter().filter(|(_, t)| *t <= crop) {
+        if *class {
+            random_stats.push(*time);
+        } else {
+            fixed_stats.push(*time);
+        }
+    }
+    let t = welch_t(&fixed_stats, &random_stats);
+    println!(
+        "{:<32} fixed {:>10.0}ns random {:>10.0}ns t {:>8.2} {}",
+        name,
+        fixed_stats.mean,
+        random_stats.mean,
+        t,
+        if t.abs() > THRESHOLD { "LEAK" } else { "no leak detected" }
+    );
+}
+
+fn main() {
+    let measurements = env::args()
+        .nth(1)
+        .map(|arg| arg.parse().expect("the number of measurements"))
+        .unwrap_or(20_000usize);
+    let msg = [0u8; 32];
+    let aux_rand = [0u8; 32];
+
+    measure("point::generator_mul", measurements, |n| {
+        generator_mul(n);
+    });
+    measure("constant_time::generator_mul", measurements, |n| {
+        constant_time::generator_mul(n);
+    });
+    measure("schnorr_sign", measurements, |n| {
+        schnorr_edu::schnorr_sign(&msg, n, &aux_rand);
+    });
+    measure("constant_time::schnorr_sign", measurements, |n| {
+        constant_time::schnorr_sign(&msg, n, &aux_rand);
+    });
+}
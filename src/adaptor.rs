@@ -0,0 +1,158 @@
+use backend::native::{JacobianPoint, NormalizedPoint, Point, Signature};
+use error::{check_length, Error};
+use point::{generator_mul, jacobian_point_add, jacobian_point_mul_wnaf};
+use scalar::{concat_and_hash_BIP0340_challenge, sha256_BIP0340_aux, tagged_hash};
+use scalar::ScalarN;
+use std::ops::{Add, Mul, Sub};
+
+// Schnorr adaptor signatures: a pre-signature for the adaptor point T = t*G becomes a valid BIP340
+// signature once adapted with t, and whoever sees both learns t. Used for atomic swaps and DLCs.
+// With R = k*G + T the BIP340 nonce is R or -R depending on the parity of R, the pre-signature
+// keeps R with its parity so that adapt and extract know whether to add or subtract t.
+
+/// Pre-signature (R, s') with s'*G = k*G + e*P when R has even y, -k*G + e*P otherwise
+#[allow(non_snake_case)]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AdaptorSignature {
+    pub R: Point,
+    pub s: ScalarN,
+}
+
+impl AdaptorSignature {
+    /// 33 bytes compressed R followed by 32 bytes s'
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        check_length(bytes, 65)?;
+        Ok(AdaptorSignature {
+            R: Point::from_compressed_bytes(&bytes[..33])?,
+            s: ScalarN::try_from_bytes(&bytes[33..])?,
+        })
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut vec = Vec::with_capacity(65);
+        vec.extend(&self.R.as_compressed_bytes()[..]);
+        vec.extend(&self.s.to_32_bytes()[..]);
+        vec
+    }
+}
+
+/// Pre-signs `msg` for the adaptor point `T`, the nonce is derived like in `schnorr_sign`
+#[allow(non_snake_case)]
+pub fn adaptor_presign(msg: &[u8], sec_key: &ScalarN, T: &Point, aux_rand: &[u8; 32]) -> AdaptorSignature {
+    let P = Point::from(generator_mul(sec_key).unwrap());
+    let sec_key_even = if P.has_even_y() {
+        sec_key.clone()
+    } else {
+        sec_key.negate()
+    };
+
+    let mut input = sec_key_even.to_32_bytes().to_vec();
+    for (t_i, aux_i) in input.iter_mut().zip(sha256_BIP0340_aux(aux_rand).iter()) {
+        *t_i ^= aux_i;
+    }
+    input.extend(&P.as_bytes()[..]);
+    input.extend(&T.as_compressed_bytes()[..]);
+    input.extend(msg);
+    let k0 = ScalarN::from_bytes(&tagged_hash(b"SchnorrAdaptor/nonce", &input));
+
+    let kG = generator_mul(&k0).unwrap();
+    let R = Point::from(jacobian_point_add(Some(&kG), Some(&JacobianPoint::from(T.clone()))).unwrap());
+    let k = if R.has_even_y() {
+        k0
+    } else {
+        k0.negate()
+    };
+
+    let e: ScalarN = concat_and_hash_BIP0340_challenge(&R.as_bytes(), &P.as_bytes(), msg);
+    AdaptorSignature {
+        R,
+        s: k.add(e.mul(&sec_key_even)),
+    }
+}
+
+/// Checks that adapting `pre_signature` with the discrete log of `T` gives a valid signature
+#[allow(non_snake_case)]
+pub fn adaptor_preverify(msg: &[u8], pub_key: &NormalizedPoint, T: &Point, pre_signature: &AdaptorSignature) -> bool {
+    let P = match Point::lift_x(&pub_key.x) {
+        Some(P) => P,
+        None => return false,
+    };
+    let e: ScalarN = concat_and_hash_BIP0340_challenge(&pre_signature.R.as_bytes(), &P.as_bytes(), msg);
+
+    // k*G = s'*G - e*P, negated when R has odd y
+    let a = generator_mul(&pre_signature.s);
+    let b = jacobian_point_mul_wnaf(&JacobianPoint::from(P), &e.negate(), 5i8);
+    let kG = jacobian_point_add(a.as_ref(), b.as_ref());
+    let kG = if pre_signature.R.has_even_y() {
+        kG
+    } else {
+        kG.map(JacobianPoint::negate)
+    };
+    let R = jacobian_point_add(kG.as_ref(), Some(&JacobianPoint::from(T.clone())));
+    R.map(Point::from) == Some(pre_signature.R.clone())
+}
+
+/// Completes the pre-signature with the adaptor secret `t`
+pub fn adapt(pre_signature: &AdaptorSignature, t: &ScalarN) -> Signature {
+    let s = if pre_signature.R.has_even_y() {
+        pre_signature.s.clone().add(t.clone())
+    } else {
+        pre_signature.s.clone().sub(t)
+    };
+    Signature::new(pre_signature.R.x.clone(), s)
+}
+
+/// Recovers the adaptor secret `t` from the pre-signature and the published signature,
+/// `None` if the signature does not come from this pre-signature
+pub fn extract_secret(pre_signature: &AdaptorSignature, signature: &Signature) -> Option<ScalarN> {
+    if signature.Rx != pre_signature.R.x {
+        return None;
+    }
+    if pre_signature.R.has_even_y() {
+        Some(signature.s.clone().sub(&pre_signature.s))
+    } else {
+        Some(pre_signature.s.clone().sub(&signature.s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use context::CONTEXT;
+    use rand::prelude::*;
+    use schnorr_verify;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_adaptor() {
+        let mut rng = thread_rng();
+        let mut parities = [false, false];
+        // loop until both parities of R are covered
+        for _ in 0..64 {
+            let sec_key = rng.gen::<ScalarN>();
+            let pub_key = NormalizedPoint::from(Point::from(generator_mul(&sec_key).unwrap()));
+            let t = rng.gen::<ScalarN>();
+            let T = Point::from(generator_mul(&t).unwrap());
+            let msg = rng.gen::<[u8; 32]>();
+
+            let pre_signature = adaptor_presign(&msg, &sec_key, &T, &rng.gen());
+            parities[pre_signature.R.has_even_y() as usize] = true;
+            assert!(adaptor_preverify(&msg, &pub_key, &T, &pre_signature));
+            assert!(!adaptor_preverify(&[0u8; 32], &pub_key, &T, &pre_signature));
+            assert!(!adaptor_preverify(&msg, &pub_key, &CONTEXT.G, &pre_signature));
+            assert_eq!(Ok(pre_signature.clone()), AdaptorSignature::from_bytes(&pre_signature.as_bytes()));
+
+            let signature = adapt(&pre_signature, &t);
+            assert!(schnorr_verify(&msg, &pub_key, &signature));
+            assert!(!schnorr_verify(&msg, &pub_key, &adapt(&pre_signature, &rng.gen())));
+            assert_eq!(Some(t), extract_secret(&pre_signature, &signature));
+
+            let other = adaptor_presign(&msg, &sec_key, &CONTEXT.G, &[0u8; 32]);
+            assert_eq!(None, extract_secret(&other, &signature));
+            if parities[0] && parities[1] {
+                return;
+            }
+        }
+        panic!("R always had the same parity");
+    }
+}
@@ -0,0 +1,89 @@
+use apint::ApInt;
+use backend::{BigNum, FieldBackend, ModElement, ModN, ModP, Modulus};
+use context::G_MUL_CACHE;
+use point::Point;
+
+// apint 0.2 implements multiplication, division and remainder only for values fitting a single
+// 64 bits digit (the others are `unimplemented!()`), so the modular product is computed by
+// doubling and adding, with only the working add, sub, shift and compare. Values use 320 bits,
+// enough for the sum of two values lower than the modulus.
+
+/// Pure rust apint with interleaved modular multiplication, by far the slowest
+pub struct Apint;
+
+impl FieldBackend for Apint {
+    type Field = ModElement<ApInt, ModP>;
+    type Scalar = ModElement<ApInt, ModN>;
+
+    const NAME: &'static str = "apint";
+}
+
+lazy_static! {
+    static ref P: ApInt = ApInt::from_32_bytes(&ModP::BYTES);
+    static ref N: ApInt = ApInt::from_32_bytes(&ModN::BYTES);
+    static ref APINT_G_MUL_CACHE: Vec<Point<ModElement<ApInt, ModP>>> =
+        G_MUL_CACHE.iter().map(Apint::point).collect();
+}
+
+fn reduce_once(mut value: ApInt, m: &ApInt) -> ApInt {
+    if value.checked_uge(m).unwrap() {
+        value.checked_sub_assign(m).unwrap();
+    }
+    value
+}
+
+impl BigNum for ApInt {
+    fn from_32_bytes(bytes: &[u8; 32]) -> Self {
+        let mut limbs = [0u64; 5];
+        for (i, limb) in limbs[1..].iter_mut().enumerate() {
+            for byte in bytes[8 * i..8 * i + 8].iter() {
+                *limb = (*limb << 8) | u64::from(*byte);
+            }
+        }
+        // the most significant limb first
+        ApInt::from(limbs)
+    }
+    fn to_32_bytes(&self) -> [u8; 32] {
+        let mut result = [0u8; 32];
+        for i in 0..4 {
+            let limb = self.clone().into_checked_lshr(64 * i).unwrap().resize_to_u64();
+            result[24 - 8 * i..32 - 8 * i].copy_from_slice(&limb.to_be_bytes());
+        }
+        result
+    }
+    fn add_mod(&self, other: &Self, m: &Self) -> Self {
+        reduce_once(self.clone().into_checked_add(other).unwrap(), m)
+    }
+    fn sub_mod(&self, other: &Self, m: &Self) -> Self {
+        if self.checked_uge(other).unwrap() {
+            self.clone().into_checked_sub(other).unwrap()
+        } else {
+            self.clone().into_checked_add(m).unwrap().into_checked_sub(other).unwrap()
+        }
+    }
+    fn mul_mod(&self, other: &Self, m: &Self) -> Self {
+        let mut result = ApInt::from([0u64; 5]);
+        for i in (0..256).rev() {
+            result = reduce_once(result.into_checked_shl(1).unwrap(), m);
+            if other.get_bit_at(i).unwrap().to_bool() {
+                result = reduce_once(result.into_checked_add(self).unwrap(), m);
+            }
+        }
+        result
+    }
+    fn is_zero(&self) -> bool {
+        self.is_zero()
+    }
+    fn is_even(&self) -> bool {
+        self.is_even()
+    }
+    fn p() -> &'static Self {
+        &P
+    }
+    fn n() -> &'static Self {
+        &N
+    }
+    fn g_mul_cache() -> &'static [Point<ModElement<Self, ModP>>] {
+        &APINT_G_MUL_CACHE
+    }
+}
@@ -0,0 +1,213 @@
+use backend::{FieldOps, ScalarOps};
+use point::Point;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::ops::{Add, Mul, Sub};
+
+// The backends on a general purpose bignum library share `ModElement`, which keeps the value
+// lower than the modulus and leaves to the library only the modular add, sub and mul. Inversion
+// is Fermat on top of them, so every library runs the same algorithm.
+
+/// The operations a bignum library must provide, inputs are always lower than the modulus
+pub trait BigNum: Clone + PartialEq + Eq + Hash + Debug + 'static {
+    /// Big endian bytes, lower than the modulus
+    fn from_32_bytes(bytes: &[u8; 32]) -> Self;
+    fn to_32_bytes(&self) -> [u8; 32];
+    fn add_mod(&self, other: &Self, m: &Self) -> Self;
+    fn sub_mod(&self, other: &Self, m: &Self) -> Self;
+    fn mul_mod(&self, other: &Self, m: &Self) -> Self;
+    fn is_zero(&self) -> bool;
+    fn is_even(&self) -> bool;
+    /// The field order p, built once
+    fn p() -> &'static Self;
+    /// The group order n, built once
+    fn n() -> &'static Self;
+    /// The table of `generator_mul` converted from the native one
+    fn g_mul_cache() -> &'static [Point<ModElement<Self, ModP>>];
+}
+
+pub trait Modulus: Clone + PartialEq + Eq + Hash + Debug {
+    const BYTES: [u8; 32];
+    /// The modulus minus 2, the exponent of the inversion
+    const SUB2: [u8; 32];
+    fn get<L: BigNum>() -> &'static L;
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ModP;
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ModN;
+
+impl Modulus for ModP {
+    const BYTES: [u8; 32] = [
+        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE, 0xFF, 0xFF,
+        0xFC, 0x2F,
+    ];
+    const SUB2: [u8; 32] = [
+        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE, 0xFF, 0xFF,
+        0xFC, 0x2D,
+    ];
+    fn get<L: BigNum>() -> &'static L {
+        L::p()
+    }
+}
+
+impl Modulus for ModN {
+    const BYTES: [u8; 32] = [
+        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        0xFE, 0xBA, 0xAE, 0xDC, 0xE6, 0xAF, 0x48, 0xA0, 0x3B, 0xBF, 0xD2, 0x5E, 0x8C, 0xD0, 0x36,
+        0x41, 0x41,
+    ];
+    const SUB2: [u8; 32] = [
+        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        0xFE, 0xBA, 0xAE, 0xDC, 0xE6, 0xAF, 0x48, 0xA0, 0x3B, 0xBF, 0xD2, 0x5E, 0x8C, 0xD0, 0x36,
+        0x41, 0x3F,
+    ];
+    fn get<L: BigNum>() -> &'static L {
+        L::n()
+    }
+}
+
+/// Element mod p (`ModP`) or mod n (`ModN`) backed by the bignum `L`
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ModElement<L, M> {
+    pub value: L,
+    modulus: PhantomData<M>,
+}
+
+impl<L: BigNum, M: Modulus> ModElement<L, M> {
+    fn new(value: L) -> Self {
+        ModElement {
+            value,
+            modulus: PhantomData,
+        }
+    }
+
+    fn from_u64(val: u64) -> Self {
+        let mut bytes = [0u8; 32];
+        bytes[24..].copy_from_slice(&val.to_be_bytes());
+        Self::from_32_bytes(&bytes)
+    }
+
+    fn from_32_bytes(bytes: &[u8; 32]) -> Self {
+        if bytes[..] < M::BYTES[..] {
+            return Self::new(L::from_32_bytes(bytes));
+        }
+        // 2^256 is lower than twice both moduli, one subtraction is enough
+        let mut reduced = [0u8; 32];
+        let mut borrow = 0i16;
+        for i in (0..32).rev() {
+            let v = i16::from(bytes[i]) - i16::from(M::BYTES[i]) - borrow;
+            borrow = (v < 0) as i16;
+            reduced[i] = (v + 256 * borrow) as u8;
+        }
+        Self::new(L::from_32_bytes(&reduced))
+    }
+
+    fn negate(&self) -> Self {
+        let zero = L::from_32_bytes(&[0u8; 32]);
+        Self::new(zero.sub_mod(&self.value, M::get::<L>()))
+    }
+
+    fn pow_bytes(&self, exponent: &[u8; 32]) -> Self {
+        let mut result = Self::from_u64(1);
+        for byte in exponent.iter() {
+            for i in (0..8).rev() {
+                result = result.clone() * &result;
+                if (byte >> i) & 1 == 1 {
+                    result = result * self;
+                }
+            }
+        }
+        result
+    }
+}
+
+impl<L: BigNum, M: Modulus> Add for ModElement<L, M> {
+    type Output = ModElement<L, M>;
+
+    fn add(self, other: ModElement<L, M>) -> ModElement<L, M> {
+        self + &other
+    }
+}
+
+impl<L: BigNum, M: Modulus> Add<&ModElement<L, M>> for ModElement<L, M> {
+    type Output = ModElement<L, M>;
+
+    fn add(self, other: &ModElement<L, M>) -> ModElement<L, M> {
+        Self::new(self.value.add_mod(&other.value, M::get::<L>()))
+    }
+}
+
+impl<L: BigNum, M: Modulus> Sub<&ModElement<L, M>> for ModElement<L, M> {
+    type Output = ModElement<L, M>;
+
+    fn sub(self, other: &ModElement<L, M>) -> ModElement<L, M> {
+        Self::new(self.value.sub_mod(&other.value, M::get::<L>()))
+    }
+}
+
+impl<L: BigNum, M: Modulus> Mul<&ModElement<L, M>> for ModElement<L, M> {
+    type Output = ModElement<L, M>;
+
+    fn mul(self, other: &ModElement<L, M>) -> ModElement<L, M> {
+        Self::new(self.value.mul_mod(&other.value, M::get::<L>()))
+    }
+}
+
+impl<L: BigNum> FieldOps for ModElement<L, ModP> {
+    fn from_u64(val: u64) -> Self {
+        ModElement::from_u64(val)
+    }
+    fn from_32_bytes(bytes: &[u8; 32]) -> Self {
+        ModElement::from_32_bytes(bytes)
+    }
+    fn to_32_bytes(&self) -> [u8; 32] {
+        self.value.to_32_bytes()
+    }
+    fn negate(&self) -> Self {
+        ModElement::negate(self)
+    }
+    fn inv(&self) -> Self {
+        self.pow_bytes(&ModP::SUB2)
+    }
+    fn pow_bytes(&self, exponent: &[u8; 32]) -> Self {
+        ModElement::pow_bytes(self, exponent)
+    }
+    fn is_zero(&self) -> bool {
+        self.value.is_zero()
+    }
+    fn is_even(&self) -> bool {
+        self.value.is_even()
+    }
+    fn g_mul_cache() -> &'static [Point<Self>] {
+        L::g_mul_cache()
+    }
+}
+
+impl<L: BigNum> ScalarOps for ModElement<L, ModN> {
+    type Field = ModElement<L, ModP>;
+
+    fn from_u64(val: u64) -> Self {
+        ModElement::from_u64(val)
+    }
+    fn from_32_bytes(bytes: &[u8; 32]) -> Self {
+        ModElement::from_32_bytes(bytes)
+    }
+    fn to_32_bytes(&self) -> [u8; 32] {
+        self.value.to_32_bytes()
+    }
+    fn negate(&self) -> Self {
+        ModElement::negate(self)
+    }
+    fn inv(&self) -> Self {
+        self.pow_bytes(&ModN::SUB2)
+    }
+    fn is_zero(&self) -> bool {
+        self.value.is_zero()
+    }
+}
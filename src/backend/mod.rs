@@ -0,0 +1,223 @@
+use point::Point;
+use scalar::{ScalarElement, ScalarN, ScalarP};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::{Add, Mul, Sub};
+
+pub use self::bignum::{BigNum, ModElement, ModN, ModP, Modulus};
+pub use self::native::Native;
+
+pub mod bignum;
+pub mod native;
+
+#[cfg(feature = "backend-apint")]
+pub use self::apint::Apint;
+#[cfg(feature = "backend-num-bigint")]
+pub use self::num_bigint::NumBigint;
+#[cfg(feature = "backend-rug")]
+pub use self::rug::Rug;
+
+#[cfg(feature = "backend-apint")]
+pub mod apint;
+#[cfg(feature = "backend-num-bigint")]
+pub mod num_bigint;
+#[cfg(feature = "backend-rug")]
+pub mod rug;
+
+// The curve arithmetic (`Point`, `JacobianPoint`, `generator_mul`, `schnorr_sign`,
+// `schnorr_verify`) is written once against `FieldOps` and `ScalarOps`. The native backend is
+// `ScalarP`/`ScalarN`, the others wrap a bignum library in `ModElement` and are compiled with the
+// cargo features `backend-rug`, `backend-num-bigint` and `backend-apint`, so the same algorithm
+// can be benchmarked across libraries. The default type parameter of the points and signatures is
+// `DefaultBackend`, native unless one of `default-backend-rug`, `default-backend-num-bigint` or
+// `default-backend-apint` selects another.
+
+/// Element of the field of the curve coordinates, mod p
+pub trait FieldOps:
+    'static
+    + Clone
+    + PartialEq
+    + Eq
+    + Hash
+    + Debug
+    + for<'a> Add<&'a Self, Output = Self>
+    + for<'a> Sub<&'a Self, Output = Self>
+    + for<'a> Mul<&'a Self, Output = Self>
+{
+    fn from_u64(val: u64) -> Self;
+    /// Big endian bytes reduced mod p
+    fn from_32_bytes(bytes: &[u8; 32]) -> Self;
+    fn to_32_bytes(&self) -> [u8; 32];
+    /// p - self
+    fn negate(&self) -> Self;
+    /// Multiplicative inverse, zero for zero
+    fn inv(&self) -> Self;
+    /// self^exponent with the exponent as big endian bytes
+    fn pow_bytes(&self, exponent: &[u8; 32]) -> Self;
+    fn is_zero(&self) -> bool;
+    fn is_even(&self) -> bool;
+    /// The table of `generator_mul`: `G_MUL_ROWS` rows of 2^`G_MUL_WINDOW` points, the entry
+    /// `(i << G_MUL_WINDOW) + d` is d * 2^(i * G_MUL_WINDOW) * G (G for the unused d = 0)
+    fn g_mul_cache() -> &'static [Point<Self>];
+}
+
+/// Scalar mod the group order n
+pub trait ScalarOps:
+    Clone
+    + PartialEq
+    + Eq
+    + Hash
+    + Debug
+    + Add<Output = Self>
+    + for<'a> Sub<&'a Self, Output = Self>
+    + for<'a> Mul<&'a Self, Output = Self>
+{
+    /// The coordinates of the points multiplied by this scalar
+    type Field: FieldOps;
+
+    fn from_u64(val: u64) -> Self;
+    /// Big endian bytes reduced mod n
+    fn from_32_bytes(bytes: &[u8; 32]) -> Self;
+    fn to_32_bytes(&self) -> [u8; 32];
+    /// n - self
+    fn negate(&self) -> Self;
+    /// Multiplicative inverse, zero for zero
+    fn inv(&self) -> Self;
+    fn is_zero(&self) -> bool;
+    /// Width w non adjacent form, most significant digit first, see `ScalarElement::wnaf`
+    fn wnaf(&self, w: i8) -> Vec<i8> {
+        ScalarElement::from_bytes(&self.to_32_bytes()).wnaf(w)
+    }
+}
+
+/// A bignum library providing the field and the scalar types
+pub trait FieldBackend {
+    type Field: FieldOps;
+    type Scalar: ScalarOps<Field = Self::Field>;
+
+    /// Name in the benchmark reports
+    const NAME: &'static str;
+
+    fn field(x: &ScalarP) -> Self::Field {
+        Self::Field::from_32_bytes(&x.to_32_bytes())
+    }
+
+    fn scalar(n: &ScalarN) -> Self::Scalar {
+        Self::Scalar::from_32_bytes(&n.to_32_bytes())
+    }
+
+    /// Converts a point of the native backend
+    fn point(p: &Point<ScalarP>) -> Point<Self::Field> {
+        Point {
+            x: Self::field(&p.x),
+            y: Self::field(&p.y),
+        }
+    }
+}
+
+/// The backend selected with a `default-backend-*` cargo feature, `Native` without one
+#[cfg(not(any(
+    feature = "default-backend-rug",
+    feature = "default-backend-num-bigint",
+    feature = "default-backend-apint"
+)))]
+pub type DefaultBackend = Native;
+#[cfg(feature = "default-backend-rug")]
+pub type DefaultBackend = Rug;
+#[cfg(feature = "default-backend-num-bigint")]
+pub type DefaultBackend = NumBigint;
+#[cfg(feature = "default-backend-apint")]
+pub type DefaultBackend = Apint;
+
+#[cfg(any(
+    all(feature = "default-backend-rug", feature = "default-backend-num-bigint"),
+    all(feature = "default-backend-rug", feature = "default-backend-apint"),
+    all(feature = "default-backend-num-bigint", feature = "default-backend-apint")
+))]
+compile_error!("at most one default-backend-* feature can be selected");
+
+/// The default coordinates of `Point`, `JacobianPoint` and `Signature`
+pub type DefaultField = <DefaultBackend as FieldBackend>::Field;
+/// The default scalar of `Signature`
+pub type DefaultScalar = <DefaultBackend as FieldBackend>::Scalar;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use point::{generator_mul, NormalizedPoint};
+    use rand::prelude::*;
+    use util::signature::Signature;
+    use {schnorr_sign, schnorr_verify};
+
+    // every backend must give the same results as the native one
+    fn check_backend<B: FieldBackend>() {
+        let mut rng = thread_rng();
+        let a: ScalarP = rng.gen();
+        let b: ScalarP = rng.gen();
+        let (x, y) = (B::field(&a), B::field(&b));
+        assert_eq!((x.clone() + &y).to_32_bytes(), (a.clone() + &b).to_32_bytes());
+        assert_eq!((x.clone() - &y).to_32_bytes(), (a.clone() - &b).to_32_bytes());
+        assert_eq!((y.clone() - &x).to_32_bytes(), (b.clone() - &a).to_32_bytes());
+        assert_eq!((x.clone() * &y).to_32_bytes(), (a.clone() * &b).to_32_bytes());
+        assert_eq!(x.negate().to_32_bytes(), a.negate().to_32_bytes());
+        assert_eq!(x.inv().to_32_bytes(), a.inv().to_32_bytes());
+        assert_eq!(x.is_even(), a.is_even());
+        assert_eq!(B::Field::from_32_bytes(&[0xFF; 32]).to_32_bytes(), ScalarP::from_bytes(&[0xFF; 32]).to_32_bytes());
+
+        let sec_key: ScalarN = rng.gen();
+        let k: ScalarN = rng.gen();
+        let (s, l) = (B::scalar(&sec_key), B::scalar(&k));
+        assert_eq!((s.clone() + l.clone()).to_32_bytes(), (sec_key.clone() + k.clone()).to_32_bytes());
+        assert_eq!((s.clone() - &l).to_32_bytes(), (sec_key.clone() - &k).to_32_bytes());
+        assert_eq!((s.clone() * &l).to_32_bytes(), (sec_key.clone() * &k).to_32_bytes());
+        assert_eq!(s.inv().to_32_bytes(), sec_key.inv().to_32_bytes());
+        assert_eq!(s.wnaf(5), sec_key.clone().to_wnaf(5));
+
+        let mut msg = [0u8; 32];
+        let mut aux_rand = [0u8; 32];
+        rng.fill_bytes(&mut msg);
+        rng.fill_bytes(&mut aux_rand);
+        let expected = schnorr_sign(&msg, &sec_key, &aux_rand);
+        let signature = schnorr_sign(&msg, &s, &aux_rand);
+        assert_eq!(expected.as_bytes(), signature.as_bytes());
+
+        let pub_key = NormalizedPoint::from(B::point(&Point::from(generator_mul(&sec_key).unwrap())));
+        assert_eq!(Point::from(generator_mul(&s).unwrap()).as_bytes(), pub_key.as_bytes());
+        assert!(schnorr_verify(&msg, &pub_key, &signature));
+        msg[0] ^= 1;
+        assert!(!schnorr_verify(&msg, &pub_key, &signature));
+    }
+
+    #[test]
+    fn test_native() {
+        check_backend::<Native>();
+    }
+
+    #[cfg(feature = "backend-rug")]
+    #[test]
+    fn test_rug() {
+        check_backend::<Rug>();
+    }
+
+    #[cfg(feature = "backend-num-bigint")]
+    #[test]
+    fn test_num_bigint() {
+        check_backend::<NumBigint>();
+    }
+
+    #[cfg(feature = "backend-apint")]
+    #[test]
+    fn test_apint() {
+        check_backend::<Apint>();
+    }
+
+    // the types without parameters are the ones of the backend selected by the features
+    #[test]
+    fn test_default_backend() {
+        check_backend::<DefaultBackend>();
+        let sec_key = DefaultBackend::scalar(&thread_rng().gen());
+        let pub_key: NormalizedPoint = Point::from(generator_mul(&sec_key).unwrap()).into();
+        let signature: Signature = schnorr_sign(&[0u8; 32], &sec_key, &[0u8; 32]);
+        assert!(schnorr_verify(&[0u8; 32], &pub_key, &signature));
+    }
+}
@@ -0,0 +1,80 @@
+use backend::{FieldBackend, FieldOps, ScalarOps};
+use context::G_MUL_CACHE;
+use scalar::{FieldElement, ScalarElement, ScalarN, ScalarP};
+
+// The protocols of the crate (musig, frost, taproot...) are written only for `ScalarP` and
+// `ScalarN`, they name their points and signatures with the aliases below so they don't change
+// with the default backend.
+
+/// The stack allocated `FieldElement` and `ScalarElement` behind `ScalarP` and `ScalarN`
+pub struct Native;
+
+// the types of the native backend, whatever the default one
+pub type Point = ::point::Point<ScalarP>;
+pub type NormalizedPoint = ::point::NormalizedPoint<ScalarP>;
+pub type JacobianPoint = ::point::JacobianPoint<ScalarP>;
+pub type Signature = ::util::signature::Signature<ScalarP, ScalarN>;
+
+impl FieldBackend for Native {
+    type Field = ScalarP;
+    type Scalar = ScalarN;
+
+    const NAME: &'static str = "native";
+}
+
+// inherent methods have the precedence, these calls don't recurse
+impl FieldOps for ScalarP {
+    fn from_u64(val: u64) -> Self {
+        ScalarP::from_u64(val)
+    }
+    fn from_32_bytes(bytes: &[u8; 32]) -> Self {
+        ScalarP(FieldElement::from_bytes(bytes))
+    }
+    fn to_32_bytes(&self) -> [u8; 32] {
+        self.to_32_bytes()
+    }
+    fn negate(&self) -> Self {
+        self.negate()
+    }
+    fn inv(&self) -> Self {
+        self.inv()
+    }
+    fn pow_bytes(&self, exponent: &[u8; 32]) -> Self {
+        ScalarP(self.0.pow(exponent))
+    }
+    fn is_zero(&self) -> bool {
+        self.is_zero()
+    }
+    fn is_even(&self) -> bool {
+        self.is_even()
+    }
+    fn g_mul_cache() -> &'static [Point] {
+        &G_MUL_CACHE
+    }
+}
+
+impl ScalarOps for ScalarN {
+    type Field = ScalarP;
+
+    fn from_u64(val: u64) -> Self {
+        ScalarN::from_u64(val)
+    }
+    fn from_32_bytes(bytes: &[u8; 32]) -> Self {
+        ScalarN(ScalarElement::from_bytes(bytes))
+    }
+    fn to_32_bytes(&self) -> [u8; 32] {
+        self.to_32_bytes()
+    }
+    fn negate(&self) -> Self {
+        self.negate()
+    }
+    fn inv(&self) -> Self {
+        self.inv()
+    }
+    fn is_zero(&self) -> bool {
+        self.is_zero()
+    }
+    fn wnaf(&self, w: i8) -> Vec<i8> {
+        self.0.wnaf(w)
+    }
+}
@@ -0,0 +1,69 @@
+use backend::{BigNum, FieldBackend, ModElement, ModN, ModP, Modulus};
+use context::G_MUL_CACHE;
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::Zero;
+use point::Point;
+
+/// Pure rust num-bigint, allocates on every operation
+pub struct NumBigint;
+
+impl FieldBackend for NumBigint {
+    type Field = ModElement<BigUint, ModP>;
+    type Scalar = ModElement<BigUint, ModN>;
+
+    const NAME: &'static str = "num-bigint";
+}
+
+lazy_static! {
+    static ref P: BigUint = BigUint::from_bytes_be(&ModP::BYTES);
+    static ref N: BigUint = BigUint::from_bytes_be(&ModN::BYTES);
+    static ref BIGUINT_G_MUL_CACHE: Vec<Point<ModElement<BigUint, ModP>>> =
+        G_MUL_CACHE.iter().map(NumBigint::point).collect();
+}
+
+impl BigNum for BigUint {
+    fn from_32_bytes(bytes: &[u8; 32]) -> Self {
+        BigUint::from_bytes_be(bytes)
+    }
+    fn to_32_bytes(&self) -> [u8; 32] {
+        let bytes = self.to_bytes_be();
+        let mut result = [0u8; 32];
+        // zero is encoded as [0]
+        result[32 - bytes.len()..].copy_from_slice(&bytes);
+        result
+    }
+    fn add_mod(&self, other: &Self, m: &Self) -> Self {
+        let result = self + other;
+        if result >= *m {
+            result - m
+        } else {
+            result
+        }
+    }
+    fn sub_mod(&self, other: &Self, m: &Self) -> Self {
+        if self >= other {
+            self - other
+        } else {
+            m - other + self
+        }
+    }
+    fn mul_mod(&self, other: &Self, m: &Self) -> Self {
+        (self * other) % m
+    }
+    fn is_zero(&self) -> bool {
+        Zero::is_zero(self)
+    }
+    fn is_even(&self) -> bool {
+        Integer::is_even(self)
+    }
+    fn p() -> &'static Self {
+        &P
+    }
+    fn n() -> &'static Self {
+        &N
+    }
+    fn g_mul_cache() -> &'static [Point<ModElement<Self, ModP>>] {
+        &BIGUINT_G_MUL_CACHE
+    }
+}
@@ -0,0 +1,66 @@
+use backend::{BigNum, FieldBackend, ModElement, ModN, ModP, Modulus};
+use context::G_MUL_CACHE;
+use point::Point;
+use rug::integer::Order;
+use rug::Integer;
+
+/// GMP through rug, allocates on every operation
+pub struct Rug;
+
+impl FieldBackend for Rug {
+    type Field = ModElement<Integer, ModP>;
+    type Scalar = ModElement<Integer, ModN>;
+
+    const NAME: &'static str = "rug";
+}
+
+lazy_static! {
+    static ref P: Integer = Integer::from_digits(&ModP::BYTES, Order::MsfBe);
+    static ref N: Integer = Integer::from_digits(&ModN::BYTES, Order::MsfBe);
+    static ref RUG_G_MUL_CACHE: Vec<Point<ModElement<Integer, ModP>>> =
+        G_MUL_CACHE.iter().map(Rug::point).collect();
+}
+
+impl BigNum for Integer {
+    fn from_32_bytes(bytes: &[u8; 32]) -> Self {
+        Integer::from_digits(bytes, Order::MsfBe)
+    }
+    fn to_32_bytes(&self) -> [u8; 32] {
+        let digits = self.to_digits::<u8>(Order::MsfBe);
+        let mut result = [0u8; 32];
+        result[32 - digits.len()..].copy_from_slice(&digits);
+        result
+    }
+    fn add_mod(&self, other: &Self, m: &Self) -> Self {
+        let mut result = Integer::from(self + other);
+        if result >= *m {
+            result -= m;
+        }
+        result
+    }
+    fn sub_mod(&self, other: &Self, m: &Self) -> Self {
+        let mut result = Integer::from(self - other);
+        if result < 0 {
+            result += m;
+        }
+        result
+    }
+    fn mul_mod(&self, other: &Self, m: &Self) -> Self {
+        Integer::from(self * other) % m
+    }
+    fn is_zero(&self) -> bool {
+        *self == 0
+    }
+    fn is_even(&self) -> bool {
+        self.is_even()
+    }
+    fn p() -> &'static Self {
+        &P
+    }
+    fn n() -> &'static Self {
+        &N
+    }
+    fn g_mul_cache() -> &'static [Point<ModElement<Self, ModP>>] {
+        &RUG_G_MUL_CACHE
+    }
+}
@@ -0,0 +1,420 @@
+use backend::native::{JacobianPoint, NormalizedPoint, Point, Signature};
+use check_signature;
+use context::CONTEXT;
+use error::{BatchError, Error};
+#[cfg(feature = "parallel")]
+use rand::prng::ChaChaRng;
+use rand::rngs::ThreadRng;
+use rand::thread_rng;
+#[cfg(feature = "parallel")]
+use rand::SeedableRng;
+use rand::{CryptoRng, Rng, RngCore};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use scalar::{concat_and_hash_BIP0340_challenge, tagged_hash};
+use scalar::ScalarN;
+use util::multi_scalar_mul::multi_scalar_mul;
+
+// https://www.deadalnix.me/2017/02/17/schnorr-signatures-for-not-so-dummies/
+// Batch verification checks the sum of the BIP340 equations of all the signatures, every one
+// multiplied by a random coefficient so that invalid signatures can't cancel each other.
+// When the sum does not hold the batch is cut in halves and every half failing its own sum is cut
+// again, down to the single signatures, so k invalid signatures out of u cost about 2k log(u)
+// smaller batches. A batch of one signature with coefficient 1 is the BIP340 verification.
+// The coefficients come from a random generator or, as in libsecp256k1, from a hash of the whole
+// batch: an attacker can't choose the signatures after the coefficients, since changing any of
+// them changes all the coefficients, and the result of a batch is reproducible.
+// With the feature `parallel` the challenges are hashed and the points lifted on the rayon thread
+// pool, then the batch is cut in chunks of `PARALLEL_CHUNK` checked on the pool, each one with its
+// own coefficients: a generator seeded by the caller one or the hash of the chunk.
+
+/// Signatures checked by a thread with the feature `parallel`, fixed so that the deterministic
+/// coefficients don't depend on the number of threads
+#[cfg(feature = "parallel")]
+pub const PARALLEL_CHUNK: usize = 256;
+
+/// Message, public key and signature of a batch
+pub type BatchItem<'a> = (&'a [u8], &'a NormalizedPoint, &'a Signature);
+
+// the points and scalars of the equation sG = R + eP of the item at index
+#[allow(non_snake_case)]
+struct Prepared {
+    index: usize,
+    R: JacobianPoint,
+    P: JacobianPoint,
+    e: ScalarN,
+    s: ScalarN,
+}
+
+// `None` if the public key or R are not on the curve
+#[allow(non_snake_case)]
+fn prepare(index: usize, (msg, pub_key, signature): &BatchItem) -> Option<Prepared> {
+    let P = Point::lift_x(&pub_key.x)?;
+    let R = Point::lift_x(&signature.Rx)?;
+    let e = concat_and_hash_BIP0340_challenge(&signature.Rx.to_32_bytes(), &pub_key.as_bytes(), msg);
+    Some(Prepared {
+        index,
+        R: JacobianPoint::from(R),
+        P: JacobianPoint::from(P),
+        e,
+        s: signature.s.clone(),
+    })
+}
+
+// where the coefficients a2, ..., au of the batch equation come from
+enum Coefficients<'a> {
+    Random(&'a mut dyn RngCore),
+    Deterministic,
+}
+
+impl<'a> Coefficients<'a> {
+    // the seed of the generator of an independent chunk, `None` if the coefficients are hashes
+    #[cfg(feature = "parallel")]
+    fn seed(&mut self) -> Option<[u8; 32]> {
+        match self {
+            Coefficients::Random(rng) => Some(rng.gen()),
+            Coefficients::Deterministic => None,
+        }
+    }
+
+    fn generate(&mut self, items: &[Prepared]) -> Vec<ScalarN> {
+        match self {
+            Coefficients::Random(rng) => (1..items.len()).map(|_| rng.gen::<ScalarN>()).collect(),
+            Coefficients::Deterministic => {
+                // e commits to R, P and the message, s completes the signature
+                let mut input = Vec::with_capacity(items.len() * 64);
+                for item in items.iter() {
+                    input.extend(&item.e.to_32_bytes()[..]);
+                    input.extend(&item.s.to_32_bytes()[..]);
+                }
+                let seed = tagged_hash(b"BIP0340/batch", &input);
+                (1..items.len() as u64)
+                    .map(|i| {
+                        let mut input = seed.to_vec();
+                        input.extend(&i.to_be_bytes()[..]);
+                        ScalarN::from_bytes(&tagged_hash(b"BIP0340/batch", &input))
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+// (s1 + a2s2 + ... + ausu)G = R1 + a2R2 + ... + auRu + e1P1 + (a2e2)P2 + ... + (aueu)Pu
+fn equation_holds(items: &[Prepared], coefficients: &mut Coefficients) -> bool {
+    let mut a_vec = vec![ScalarN::from_u64(1)];
+    a_vec.extend(coefficients.generate(items));
+    let mut coeff = ScalarN::from_u64(0);
+    let mut terms = Vec::with_capacity(2 * items.len() + 1);
+    for (item, a) in items.iter().zip(a_vec) {
+        coeff = coeff + a.clone() * &item.s;
+        terms.push((a.clone() * &item.e, item.P.clone()));
+        terms.push((a, item.R.clone()));
+    }
+    terms.push((coeff.negate(), CONTEXT.G_jacobian.clone())); // -sG
+
+    multi_scalar_mul(&terms).is_none()
+}
+
+// the indexes of the invalid items of a batch whose equation does not hold
+fn bisect(items: &[Prepared], coefficients: &mut Coefficients, invalid: &mut Vec<usize>) {
+    if items.len() == 1 {
+        invalid.push(items[0].index);
+        return;
+    }
+    let (left, right) = items.split_at(items.len() / 2);
+    for half in [left, right].iter() {
+        if !equation_holds(half, coefficients) {
+            bisect(half, coefficients, invalid);
+        }
+    }
+}
+
+/// Verifies all the signatures of the batch at once, on failure the error has the indexes of the
+/// invalid ones in increasing order. The coefficients come from `thread_rng`
+pub fn schnorr_batch_verify(batch: &[BatchItem]) -> Result<(), BatchError> {
+    schnorr_batch_verify_with_rng(&mut thread_rng(), batch)
+}
+
+/// `schnorr_batch_verify` with the coefficients from `rng`
+pub fn schnorr_batch_verify_with_rng<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    batch: &[BatchItem],
+) -> Result<(), BatchError> {
+    verify(batch, Coefficients::Random(rng))
+}
+
+/// `schnorr_batch_verify` with the coefficients derived from a hash of the batch, the same batch
+/// always takes the same path
+pub fn schnorr_batch_verify_deterministic(batch: &[BatchItem]) -> Result<(), BatchError> {
+    verify(batch, Coefficients::Deterministic)
+}
+
+// the indexes of the invalid items
+fn check(items: &[Prepared], coefficients: &mut Coefficients) -> Vec<usize> {
+    let mut invalid = Vec::new();
+    if !items.is_empty() && !equation_holds(items, coefficients) {
+        bisect(items, coefficients, &mut invalid);
+    }
+    invalid
+}
+
+#[cfg(feature = "parallel")]
+fn check_chunks(items: &[Prepared], coefficients: &mut Coefficients, chunk: usize) -> Vec<usize> {
+    let seeds: Vec<Option<[u8; 32]>> = items.chunks(chunk).map(|_| coefficients.seed()).collect();
+    items
+        .par_chunks(chunk)
+        .zip(seeds)
+        .flat_map(|(items, seed)| match seed {
+            Some(seed) => check(items, &mut Coefficients::Random(&mut ChaChaRng::from_seed(seed))),
+            None => check(items, &mut Coefficients::Deterministic),
+        })
+        .collect()
+}
+
+// `check`, in chunks on the thread pool with the feature `parallel`
+fn check_all(items: &[Prepared], coefficients: &mut Coefficients) -> Vec<usize> {
+    #[cfg(feature = "parallel")]
+    return check_chunks(items, coefficients, PARALLEL_CHUNK);
+    #[cfg(not(feature = "parallel"))]
+    check(items, coefficients)
+}
+
+fn verify(batch: &[BatchItem], mut coefficients: Coefficients) -> Result<(), BatchError> {
+    #[cfg(feature = "parallel")]
+    let items = batch.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let items = batch.iter();
+    let prepared: Vec<Option<Prepared>> = items.enumerate().map(|(index, item)| prepare(index, item)).collect();
+
+    let mut invalid: Vec<usize> = (0..batch.len()).filter(|i| prepared[*i].is_none()).collect();
+    let prepared: Vec<Prepared> = prepared.into_iter().flatten().collect();
+    invalid.extend(check_all(&prepared, &mut coefficients));
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        invalid.sort();
+        Err(BatchError { invalid })
+    }
+}
+
+/// Batch verification of signatures fed one at a time, for example while parsing blocks: every
+/// `threshold` signatures are checked as a batch and dropped, only their challenge and points are
+/// kept until then, and `finalize` tells the invalid ones among all of them
+pub struct BatchVerifier<R = ThreadRng> {
+    // `None` for the deterministic coefficients
+    rng: Option<R>,
+    threshold: usize,
+    pending: Vec<Prepared>,
+    queued: usize,
+    invalid: Vec<usize>,
+}
+
+impl BatchVerifier {
+    /// Coefficients from `thread_rng`
+    pub fn new(threshold: usize) -> Self {
+        BatchVerifier::with_rng(thread_rng(), threshold)
+    }
+
+    /// Coefficients derived from a hash of every sub-batch, like `schnorr_batch_verify_deterministic`
+    pub fn deterministic(threshold: usize) -> Self {
+        assert!(threshold > 0);
+        BatchVerifier {
+            rng: None,
+            threshold,
+            pending: Vec::with_capacity(threshold),
+            queued: 0,
+            invalid: Vec::new(),
+        }
+    }
+}
+
+impl<R: RngCore + CryptoRng> BatchVerifier<R> {
+    /// Coefficients from `rng`
+    pub fn with_rng(rng: R, threshold: usize) -> Self {
+        assert!(threshold > 0);
+        BatchVerifier {
+            rng: Some(rng),
+            threshold,
+            pending: Vec::with_capacity(threshold),
+            queued: 0,
+            invalid: Vec::new(),
+        }
+    }
+
+    /// Adds a signature, its index is the number of signatures queued before it
+    pub fn queue(&mut self, msg: &[u8], pub_key: &NormalizedPoint, signature: &Signature) {
+        let index = self.queued;
+        self.queued += 1;
+        match prepare(index, &(msg, pub_key, signature)) {
+            Some(item) => self.pending.push(item),
+            None => self.invalid.push(index),
+        }
+        if self.pending.len() >= self.threshold {
+            self.check_pending();
+        }
+    }
+
+    /// Number of signatures queued so far
+    pub fn len(&self) -> usize {
+        self.queued
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queued == 0
+    }
+
+    fn check_pending(&mut self) {
+        let mut coefficients = match self.rng {
+            Some(ref mut rng) => Coefficients::Random(rng),
+            None => Coefficients::Deterministic,
+        };
+        self.invalid.extend(check_all(&self.pending, &mut coefficients));
+        self.pending.clear();
+    }
+
+    /// Checks the last sub-batch, on failure the error has the indexes of all the invalid
+    /// signatures in increasing order
+    pub fn finalize(mut self) -> Result<(), BatchError> {
+        self.check_pending();
+        if self.invalid.is_empty() {
+            Ok(())
+        } else {
+            self.invalid.sort();
+            Err(BatchError { invalid: self.invalid })
+        }
+    }
+}
+
+/// `schnorr_verify` of every item with the reason of the failures, on all the cores with the
+/// feature `parallel`
+pub fn verify_many(batch: &[BatchItem]) -> Vec<Result<(), Error>> {
+    #[cfg(feature = "parallel")]
+    let items = batch.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let items = batch.iter();
+    items
+        .map(|(msg, pub_key, signature)| check_signature(msg, pub_key, signature))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use point::generator_mul;
+    use rand::prng::ChaChaRng;
+    use rand::SeedableRng;
+    use scalar::ScalarP;
+    use schnorr_sign;
+
+    fn random_batch(size: usize) -> Vec<([u8; 32], NormalizedPoint, Signature)> {
+        let mut rng = thread_rng();
+        (0..size)
+            .map(|_| {
+                let sec_key: ScalarN = rng.gen();
+                let msg: [u8; 32] = rng.gen();
+                let pub_key = NormalizedPoint::from(Point::from(generator_mul(&sec_key).unwrap()));
+                (msg, pub_key, schnorr_sign(&msg, &sec_key, &rng.gen()))
+            })
+            .collect()
+    }
+
+    fn items(batch: &[([u8; 32], NormalizedPoint, Signature)]) -> Vec<BatchItem<'_>> {
+        batch.iter().map(|(msg, pub_key, signature)| (&msg[..], pub_key, signature)).collect()
+    }
+
+    #[test]
+    fn test_batch_verify() {
+        assert_eq!(Ok(()), schnorr_batch_verify(&[]));
+        let mut batch = random_batch(20);
+        assert_eq!(Ok(()), schnorr_batch_verify(&items(&batch)));
+
+        // a wrong message, a signature of another key and an R which is not on the curve
+        batch[3].0[0] ^= 1;
+        batch[11].2 = batch[12].2.clone();
+        let mut x = 0u64;
+        while Point::lift_x(&ScalarP::from_u64(x)).is_some() {
+            x += 1;
+        }
+        batch[19].2.Rx = ScalarP::from_u64(x);
+        assert_eq!(
+            Err(BatchError { invalid: vec![3, 11, 19] }),
+            schnorr_batch_verify(&items(&batch))
+        );
+        assert_eq!(
+            Err(BatchError { invalid: vec![0] }),
+            schnorr_batch_verify(&items(&batch[3..4]))
+        );
+    }
+
+    #[test]
+    fn test_batch_verify_reproducible() {
+        let mut batch = random_batch(10);
+        batch[7].0[0] ^= 1;
+        let expected = Err(BatchError { invalid: vec![7] });
+        assert_eq!(expected, schnorr_batch_verify_deterministic(&items(&batch)));
+        assert_eq!(expected, schnorr_batch_verify_deterministic(&items(&batch)));
+        let mut rng = ChaChaRng::from_seed([7u8; 32]);
+        assert_eq!(expected, schnorr_batch_verify_with_rng(&mut rng, &items(&batch)));
+        assert_eq!(Ok(()), schnorr_batch_verify_deterministic(&items(&batch[..7])));
+
+        // the coefficients change with every item of the batch
+        let mut coefficients = Coefficients::Deterministic;
+        let batch = items(&batch);
+        let prepared: Vec<Prepared> = batch.iter().enumerate().filter_map(|(i, item)| prepare(i, item)).collect();
+        let a = coefficients.generate(&prepared[..5]);
+        assert_eq!(a, coefficients.generate(&prepared[..5]));
+        assert_eq!(4, a.len());
+        assert_ne!(a[..3], coefficients.generate(&prepared[..4])[..]);
+    }
+
+    #[test]
+    fn test_verify_many() {
+        let mut batch = random_batch(6);
+        batch[2].0[0] ^= 1;
+        batch[4].2.s = batch[4].2.s.negate();
+        let results = verify_many(&items(&batch));
+        assert_eq!(6, results.len());
+        for (i, result) in results.iter().enumerate() {
+            assert_eq!(i != 2 && i != 4, result.is_ok());
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_check_chunks() {
+        let mut batch = random_batch(10);
+        batch[1].0[0] ^= 1;
+        batch[8].0[0] ^= 1;
+        let batch = items(&batch);
+        let prepared: Vec<Prepared> = batch.iter().enumerate().filter_map(|(i, item)| prepare(i, item)).collect();
+        for chunk in [1usize, 3, 4, 10].iter() {
+            let mut rng = thread_rng();
+            assert_eq!(vec![1, 8], check_chunks(&prepared, &mut Coefficients::Random(&mut rng), *chunk));
+            assert_eq!(vec![1, 8], check_chunks(&prepared, &mut Coefficients::Deterministic, *chunk));
+        }
+    }
+
+    #[test]
+    fn test_batch_verifier() {
+        let mut batch = random_batch(10);
+        batch[2].0[0] ^= 1;
+        batch[9].2.s = batch[9].2.s.negate();
+        batch[5].2.Rx = batch[5].2.Rx.clone() + &ScalarP::from_u64(1);
+        let expected = Err(BatchError { invalid: vec![2, 5, 9] });
+        assert_eq!(expected, schnorr_batch_verify(&items(&batch)));
+        for threshold in [1usize, 3, 4, 100].iter() {
+            let mut verifier = BatchVerifier::new(*threshold);
+            let mut deterministic = BatchVerifier::deterministic(*threshold);
+            for (msg, pub_key, signature) in batch.iter() {
+                verifier.queue(msg, pub_key, signature);
+                deterministic.queue(msg, pub_key, signature);
+                assert!(verifier.pending.len() < *threshold);
+            }
+            assert_eq!(10, verifier.len());
+            assert_eq!(expected, verifier.finalize());
+            assert_eq!(expected, deterministic.finalize());
+        }
+        assert_eq!(Ok(()), BatchVerifier::new(5).finalize());
+    }
+}
@@ -0,0 +1,149 @@
+use backend::native::{JacobianPoint, Point};
+use context::CONTEXT;
+use error::{check_length, Error};
+use point::{generator_mul, jacobian_point_add, jacobian_point_mul_glv};
+use scalar::sha256;
+use scalar::{ScalarN, ScalarP};
+use std::ops::{Add, Sub};
+
+// Pedersen commitments C = vG + rH to a value v with a blinding factor r. They are hiding, C says
+// nothing about v without r, and binding as long as nobody knows the discrete log of H in base G,
+// which is why H comes from a hash: the x of H is the SHA256 of the uncompressed G, incremented
+// until it is on the curve. Commitments add up like their values and blinding factors, so a sum
+// of inputs minus a sum of outputs committing to 0 proves the amounts balance.
+// The point at infinity is a valid commitment, to 0 with blinding factor 0 or as the difference
+// of equal commitments.
+
+lazy_static! {
+    /// The second generator, the H of the context and of the unspendable key of BIP341
+    pub static ref H: Point = CONTEXT.H.clone();
+    static ref H_JACOBIAN: JacobianPoint = JacobianPoint::from(H.clone());
+}
+
+/// The point with even y whose x is the first of SHA256(input), SHA256(input) + 1, ... on the
+/// curve (try-and-increment), about half of the x are
+pub fn hash_to_curve(input: &[u8]) -> Point {
+    let mut x = ScalarP::from_bytes(&sha256(input));
+    loop {
+        if let Some(point) = Point::lift_x(&x) {
+            return point;
+        }
+        x = x + &ScalarP::from_u64(1);
+    }
+}
+
+/// vG + rH, `None` is the point at infinity
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Commitment(pub Option<JacobianPoint>);
+
+impl Commitment {
+    /// 33 bytes, the compressed point or 33 zero bytes for the point at infinity
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        check_length(bytes, 33)?;
+        if bytes.iter().all(|b| *b == 0) {
+            return Ok(Commitment(None));
+        }
+        Point::from_compressed_bytes(bytes).map(|point| Commitment(Some(JacobianPoint::from(point))))
+    }
+
+    pub fn as_bytes(&self) -> [u8; 33] {
+        match self.0 {
+            Some(ref point) => Point::from(point.clone()).as_compressed_bytes(),
+            None => [0u8; 33],
+        }
+    }
+}
+
+impl Add for Commitment {
+    type Output = Commitment;
+
+    fn add(self, other: Commitment) -> Commitment {
+        Commitment(jacobian_point_add(self.0.as_ref(), other.0.as_ref()))
+    }
+}
+
+impl Sub for Commitment {
+    type Output = Commitment;
+
+    fn sub(self, other: Commitment) -> Commitment {
+        let other = other.0.map(JacobianPoint::negate);
+        Commitment(jacobian_point_add(self.0.as_ref(), other.as_ref()))
+    }
+}
+
+/// Commits to `value` with the secret `blinding` factor
+pub fn commit(value: &ScalarN, blinding: &ScalarN) -> Commitment {
+    let v_g = generator_mul(value);
+    let r_h = jacobian_point_mul_glv(&H_JACOBIAN, blinding, 5i8);
+    Commitment(jacobian_point_add(v_g.as_ref(), r_h.as_ref()))
+}
+
+/// Whether `commitment` commits to `value` with `blinding`
+pub fn open(commitment: &Commitment, value: &ScalarN, blinding: &ScalarN) -> bool {
+    *commitment == commit(value, blinding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_encoding::HEXLOWER;
+    use rand::prelude::*;
+
+    // SEC1 uncompressed encoding of G, 0x04 followed by x and y
+    fn uncompressed_g() -> Vec<u8> {
+        let mut bytes = vec![4u8];
+        bytes.extend(&CONTEXT.G.as_uncompressed_bytes()[..]);
+        bytes
+    }
+
+    #[test]
+    fn test_h() {
+        assert_eq!(
+            "50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96d547bfee9ace803ac0",
+            HEXLOWER.encode(&H.as_bytes())
+        );
+        assert!(H.on_curve() && H.has_even_y());
+        assert_eq!(hash_to_curve(&uncompressed_g()), *H);
+        for input in [&b""[..], b"a", b"b", b"c"].iter() {
+            let point = hash_to_curve(input);
+            assert!(point.on_curve() && point.has_even_y());
+        }
+    }
+
+    #[test]
+    fn test_commit() {
+        let mut rng = thread_rng();
+        let v1 = ScalarN::from_u64(u64::from(rng.gen::<u32>()));
+        let v2 = ScalarN::from_u64(u64::from(rng.gen::<u32>()));
+        let (r1, r2): (ScalarN, ScalarN) = (rng.gen(), rng.gen());
+        let c1 = commit(&v1, &r1);
+        let c2 = commit(&v2, &r2);
+        assert!(open(&c1, &v1, &r1));
+        assert!(!open(&c1, &v2, &r1));
+        assert!(!open(&c1, &v1, &r2));
+        assert_ne!(c1, commit(&v1, &r2));
+
+        assert!(open(&(c1.clone() + c2.clone()), &(v1.clone() + v2.clone()), &(r1.clone() + r2.clone())));
+        assert!(open(&(c1.clone() - c2.clone()), &(v1.clone() - &v2), &(r1.clone() - &r2)));
+        assert_eq!(Commitment(None), c1.clone() - c1.clone());
+        assert_eq!(Commitment(None), commit(&ScalarN::from_u64(0), &ScalarN::from_u64(0)));
+        assert_eq!(c2.clone(), Commitment(None) + c2.clone());
+        let g = commit(&ScalarN::from_u64(1), &ScalarN::from_u64(0));
+        assert_eq!(Commitment(Some(CONTEXT.G_jacobian.clone())), g);
+    }
+
+    #[test]
+    fn test_serialization() {
+        let mut rng = thread_rng();
+        for commitment in [commit(&rng.gen(), &rng.gen()), Commitment(None)].iter() {
+            assert_eq!(*commitment, Commitment::from_bytes(&commitment.as_bytes()).unwrap());
+        }
+        let mut bytes = commit(&rng.gen(), &rng.gen()).as_bytes();
+        bytes[0] = 4;
+        assert_eq!(Err(Error::InvalidPrefix { found: 4 }), Commitment::from_bytes(&bytes));
+        assert_eq!(
+            Err(Error::InvalidLength { expected: 33, found: 32 }),
+            Commitment::from_bytes(&bytes[..32])
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use backend::native::{NormalizedPoint, Point, Signature};
+use data_encoding::HEXUPPER;
+use point::generator_mul;
+use scalar::vec_to_32_bytes;
+use scalar::ScalarN;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+use util::rug::integer_from_bytes;
+use {schnorr_sign_variant, schnorr_verify_variant, Variant};
+
+// Runs test vectors in the format of
+// https://github.com/bitcoin/bips/blob/master/bip-0340/test-vectors.csv
+// Columns are found by header name, a missing "aux_rand" column (like in the 2019 draft file)
+// means 32 zero bytes.
+
+/// Outcome of a single test vector
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct VectorReport {
+    pub index: usize,
+    /// The "verification result" column
+    pub expected: bool,
+    /// Verification result, false if the public key, message or signature could not be parsed.
+    /// When the row has a secret key it is also false if the signature we produce differs
+    pub actual: bool,
+    /// `None` if the row has no secret key, otherwise whether our signature matches the expected
+    pub signed: Option<bool>,
+    pub comment: String,
+}
+
+impl VectorReport {
+    pub fn passed(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+struct Columns {
+    index: usize,
+    secret_key: usize,
+    public_key: usize,
+    aux_rand: Option<usize>,
+    message: usize,
+    signature: usize,
+    result: usize,
+    comment: usize,
+}
+
+impl Columns {
+    fn from_header(header: &str) -> Option<Self> {
+        let names: Vec<&str> = header.trim().split(',').collect();
+        let find = |name: &str| names.iter().position(|el| *el == name);
+        Some(Columns {
+            index: find("index")?,
+            secret_key: find("secret key")?,
+            public_key: find("public key")?,
+            aux_rand: find("aux_rand"),
+            message: find("message")?,
+            signature: find("signature")?,
+            result: find("verification result")?,
+            comment: find("comment")?,
+        })
+    }
+}
+
+/// Runs every row of `csv`, returns `None` if the header misses a required column
+pub fn run_csv(variant: Variant, csv: &str) -> Option<Vec<VectorReport>> {
+    let mut lines = csv.lines().filter(|line| !line.trim().is_empty());
+    let columns = Columns::from_header(lines.next()?)?;
+    Some(
+        lines
+            .enumerate()
+            .map(|(row, line)| run_row(variant, &columns, row, line))
+            .collect(),
+    )
+}
+
+pub fn run_csv_file<P: AsRef<Path>>(variant: Variant, path: P) -> io::Result<Vec<VectorReport>> {
+    let mut csv = String::new();
+    File::open(path)?.read_to_string(&mut csv)?;
+    run_csv(variant, &csv)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing csv column"))
+}
+
+fn run_row(variant: Variant, columns: &Columns, row: usize, line: &str) -> VectorReport {
+    let cols: Vec<&str> = line.split(',').collect();
+    let col = |i: usize| cols.get(i).map(|el| el.trim()).unwrap_or("");
+
+    let signed = if col(columns.secret_key).is_empty() {
+        None
+    } else {
+        let aux_rand = columns.aux_rand.map(col).unwrap_or("");
+        Some(
+            check_sign(
+                variant,
+                col(columns.secret_key),
+                col(columns.public_key),
+                aux_rand,
+                col(columns.message),
+                col(columns.signature),
+            )
+            .unwrap_or(false),
+        )
+    };
+    let verified = check_verify(
+        variant,
+        col(columns.public_key),
+        col(columns.message),
+        col(columns.signature),
+    )
+    .unwrap_or(false);
+
+    VectorReport {
+        index: col(columns.index).parse().unwrap_or(row),
+        expected: col(columns.result) == "TRUE",
+        actual: verified && signed.unwrap_or(true),
+        signed,
+        // the comment is the last column and may contain commas
+        comment: cols.get(columns.comment..).map(|el| el.join(",")).unwrap_or_default(),
+    }
+}
+
+fn decode(hex: &str) -> Option<Vec<u8>> {
+    HEXUPPER.decode(hex.to_uppercase().as_bytes()).ok()
+}
+
+fn decode_32(hex: &str) -> Option<[u8; 32]> {
+    match decode(hex) {
+        Some(ref bytes) if bytes.len() == 32 => Some(vec_to_32_bytes(bytes)),
+        _ => None,
+    }
+}
+
+fn check_sign(
+    variant: Variant,
+    secret_key: &str,
+    public_key: &str,
+    aux_rand: &str,
+    message: &str,
+    signature: &str,
+) -> Option<bool> {
+    let sec_key = ScalarN::new(integer_from_bytes(&decode_32(secret_key)?));
+    let pub_key: Point = generator_mul(&sec_key)?.into();
+    let aux_rand = if aux_rand.is_empty() {
+        [0u8; 32]
+    } else {
+        decode_32(aux_rand)?
+    };
+    let message = decode(message)?;
+    let signature_bytes = decode(signature)?;
+    let signature = schnorr_sign_variant(variant, &message, &sec_key, &aux_rand).ok()?;
+    Some(pub_key.as_bytes()[..] == decode(public_key)?[..] && signature.as_bytes() == signature_bytes)
+}
+
+fn check_verify(variant: Variant, public_key: &str, message: &str, signature: &str) -> Option<bool> {
+    let pub_key = NormalizedPoint::from_bytes(&decode(public_key)?).ok()?;
+    let message = decode(message)?;
+    let signature_bytes = decode(signature)?;
+    let signature = Signature::from_bytes(&signature_bytes).ok()?;
+    Some(schnorr_verify_variant(variant, &message, &pub_key, &signature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bip340_csv() {
+        let reports = run_csv(Variant::Bip340, include_str!("../test-vectors-bip340.csv")).unwrap();
+        assert_eq!(reports.len(), 19);
+        for (i, report) in reports.iter().enumerate() {
+            assert_eq!(report.index, i);
+            assert!(report.passed(), "{:?}", report);
+        }
+        assert_eq!(reports[0].signed, Some(true));
+        assert_eq!(reports[4].signed, None);
+        assert_eq!(reports[5].comment, "public key not on the curve");
+        assert_eq!(reports[15].comment, "message of size 0 (added 2022-12)");
+    }
+
+    #[test]
+    fn test_draft_csv() {
+        let reports = run_csv(Variant::Draft2019, include_str!("../test-vectors.csv")).unwrap();
+        assert_eq!(reports.len(), 14);
+        assert!(reports.iter().all(VectorReport::passed));
+    }
+
+    #[test]
+    fn test_wrong_variant() {
+        let reports = run_csv(Variant::Draft2019, include_str!("../test-vectors-bip340.csv")).unwrap();
+        assert!(!reports[0].passed());
+        assert_eq!(reports[0].signed, Some(false));
+    }
+
+    #[test]
+    fn test_bad_rows() {
+        let csv = "index,secret key,public key,aux_rand,message,signature,verification result,comment
+0,,DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659,,243F6A88,6CFF5C3B,FALSE,short
+1,,ZZ,,,,FALSE,not hex, with comma";
+        let reports = run_csv(Variant::Bip340, csv).unwrap();
+        assert_eq!(reports.len(), 2);
+        assert!(reports.iter().all(VectorReport::passed));
+        assert_eq!(reports[1].comment, "not hex, with comma");
+
+        assert!(run_csv(Variant::Bip340, "index,public key").is_none());
+    }
+
+    #[test]
+    fn test_csv_file() {
+        let reports = run_csv_file(Variant::Bip340, "test-vectors-bip340.csv").unwrap();
+        assert!(reports.iter().all(VectorReport::passed));
+        assert!(run_csv_file(Variant::Bip340, "missing.csv").is_err());
+    }
+}
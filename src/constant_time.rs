@@ -0,0 +1,135 @@
+use backend::native::{JacobianPoint, Point, Signature};
+use context::{CONTEXT, G_MUL_CACHE, G_MUL_ROWS, G_MUL_WINDOW};
+use point::jacobian_point::window_digit;
+use point::mixed_point_add;
+use scalar::{concat_and_hash_BIP0340_challenge, concat_and_tagged_hash, sha256_BIP0340_aux, FieldElement, ScalarN, ScalarP};
+
+// Opt-in constant time signing: `schnorr_sign` here gives the same signatures as the crate one
+// but the time does not depend on the secret key or the nonce.
+// `generator_mul` is a fixed window on `G_MUL_CACHE`, every window reads all the entries of its
+// row and keeps the one of the secret digit with a mask, then always adds it and keeps the sum
+// only if the digit is not zero, again with a mask. The accumulator starts from a point nobody
+// knows the discrete log of, the `H` of the context, so the addition never meets the exceptional
+// cases (equal points, infinity) and can skip their branches. Field and scalar arithmetic have no
+// branches on the values. This is as much as can be done without looking at the generated
+// assembly, measure it with `cargo run --release --example dudect`.
+
+lazy_static! {
+    static ref NEG_OFFSET: Point = CONTEXT.H.clone().negate();
+}
+
+fn select_field(a: &ScalarP, b: &ScalarP, choice: bool) -> ScalarP {
+    ScalarP(FieldElement::conditional_select(&a.0, &b.0, choice))
+}
+
+fn select_point(a: &Point, b: &Point, choice: bool) -> Point {
+    Point {
+        x: select_field(&a.x, &b.x, choice),
+        y: select_field(&a.y, &b.y, choice),
+    }
+}
+
+fn select_jacobian(a: &JacobianPoint, b: &JacobianPoint, choice: bool) -> JacobianPoint {
+    JacobianPoint {
+        x: select_field(&a.x, &b.x, choice),
+        y: select_field(&a.y, &b.y, choice),
+        z: select_field(&a.z, &b.z, choice),
+    }
+}
+
+// the entry at index, after reading all of them
+fn scan(table: &[Point], index: usize) -> Point {
+    let mut result = table[0].clone();
+    for (i, point) in table.iter().enumerate() {
+        result = select_point(&result, point, i == index);
+    }
+    result
+}
+
+// `mixed_point_add` without the branches for equal points and infinity
+fn mixed_add(p1: &JacobianPoint, p2: &Point) -> JacobianPoint {
+    let z_pow2 = p1.z.clone() * &p1.z;
+    let u2 = p2.x.clone() * &z_pow2;
+    let s2 = p2.y.clone() * &z_pow2 * &p1.z;
+    let h = u2 - &p1.x;
+    let r = s2 - &p1.y;
+    let h_pow2 = h.clone() * &h;
+    let h_pow3 = h_pow2.clone() * &h;
+    let u1_h_pow2 = p1.x.clone() * &h_pow2;
+    let x = r.clone() * &r - &h_pow3 - &(u1_h_pow2.clone() + &u1_h_pow2);
+    let y = r * &(u1_h_pow2 - &x) - &(p1.y.clone() * &h_pow3);
+    let z = p1.z.clone() * &h;
+    JacobianPoint { x, y, z }
+}
+
+/// n * G in constant time, `None` for zero
+pub fn generator_mul(n: &ScalarN) -> Option<JacobianPoint> {
+    let bytes = n.to_32_bytes();
+    let mut acc = JacobianPoint::from(CONTEXT.H.clone());
+    for row in 0..G_MUL_ROWS {
+        let digit = window_digit(&bytes, row, G_MUL_WINDOW);
+        let point = scan(&G_MUL_CACHE[row << G_MUL_WINDOW..(row + 1) << G_MUL_WINDOW], digit);
+        let sum = mixed_add(&acc, &point);
+        acc = select_jacobian(&acc, &sum, digit != 0);
+    }
+    mixed_point_add(Some(&acc), Some(&NEG_OFFSET))
+}
+
+/// BIP340 signature like `schnorr_sign`, with the constant time `generator_mul` and the secret
+/// key and nonce negated with `conditional_negate`
+#[allow(non_snake_case)]
+pub fn schnorr_sign(msg: &[u8], sec_key: &ScalarN, aux_rand: &[u8; 32]) -> Signature {
+    let P = Point::from(generator_mul(sec_key).unwrap());
+    let sec_key_even = sec_key.conditional_negate(!P.has_even_y());
+
+    let mut t = sec_key_even.to_32_bytes();
+    for (t_i, aux_i) in t.iter_mut().zip(sha256_BIP0340_aux(aux_rand).iter()) {
+        *t_i ^= aux_i;
+    }
+
+    let k0: ScalarN = concat_and_tagged_hash(b"BIP0340/nonce", &t, &P.as_bytes(), msg);
+    let R = Point::from(generator_mul(&k0).unwrap());
+    let k = k0.conditional_negate(!R.has_even_y());
+
+    let e: ScalarN = concat_and_hash_BIP0340_challenge(&R.as_bytes(), &P.as_bytes(), msg);
+
+    Signature::new(R.x, k + e * &sec_key_even)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use point::generator_mul as generator_mul_vartime;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_generator_mul() {
+        let mut rng = thread_rng();
+        let mut values = vec![
+            ScalarN::from_u64(1),
+            ScalarN::from_u64(256),
+            ScalarN::from_u64(1).negate(),
+        ];
+        for _ in 0..20 {
+            values.push(rng.gen());
+        }
+        for n in values.iter() {
+            assert_eq!(generator_mul_vartime(n), generator_mul(n));
+        }
+        assert_eq!(None, generator_mul(&ScalarN::from_u64(0)));
+    }
+
+    #[test]
+    fn test_schnorr_sign() {
+        let mut rng = thread_rng();
+        for _ in 0..10 {
+            let sec_key: ScalarN = rng.gen();
+            let msg: [u8; 32] = rng.gen();
+            let aux_rand: [u8; 32] = rng.gen();
+            assert_eq!(
+                ::schnorr_sign(&msg, &sec_key, &aux_rand).as_bytes()[..],
+                schnorr_sign(&msg, &sec_key, &aux_rand).as_bytes()[..]
+            );
+        }
+    }
+}
@@ -1,11 +1,8 @@
-use point::JacobianPoint;
-use point::Point;
+use backend::native::{JacobianPoint, Point};
+use point::{batch_normalize, jacobian_point_add};
 use rug::Integer;
-use scalar::ScalarN;
 use scalar::ScalarP;
 use std::borrow::Borrow;
-use std::fs::File;
-use std::io::Read;
 use std::ops::Add;
 use std::ops::Div;
 use std::ops::Sub;
@@ -16,7 +13,8 @@ lazy_static! {
 
 #[allow(non_snake_case)]
 pub struct Context {
-    pub p: ScalarP,
+    /// p as an integer, as a field element it would be 0
+    pub p: Integer,
     pub p_sub2: ScalarP,
     pub p_sub1_div2: ScalarP,
     pub p_add1_div4: ScalarP,
@@ -25,9 +23,13 @@ pub struct Context {
     pub four: ScalarP,
     pub seven: ScalarP,
     pub eight: ScalarP,
-    pub n: ScalarN,
+    pub n: Integer,
+    /// Cube root of unity mod p, (beta * x, y) is lambda times the point (x, y)
+    pub beta: ScalarP,
     pub G: Point,
     pub G_jacobian: JacobianPoint,
+    /// The "nothing up my sleeve" H of BIP341, nobody knows its discrete log
+    pub H: Point,
 }
 
 impl Default for Context {
@@ -47,14 +49,14 @@ impl Default for Context {
         let p_sub1: Integer = p.borrow().sub(&one).into();
         let p_add1: Integer = p.clone().add(&one).into();
         let g = Point {
-            x: ScalarP(
+            x: ScalarP::new(
                 Integer::from_str_radix(
                     "79BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
                     16,
                 )
                 .unwrap(),
             ),
-            y: ScalarP(
+            y: ScalarP::new(
                 Integer::from_str_radix(
                     "483ADA7726A3C4655DA4FBFC0E1108A8FD17B448A68554199C47D08FFB10D4B8",
                     16,
@@ -64,41 +66,85 @@ impl Default for Context {
         };
 
         Context {
-            p: ScalarP(p.clone()),
-            p_sub2: ScalarP(p.clone().sub(&two)),
-            p_sub1_div2: ScalarP(p_sub1.clone().div(&two)),
-            p_add1_div4: ScalarP(p_add1.clone().div(&four)),
-            two: ScalarP(two),
-            three: ScalarP(three),
-            four: ScalarP(four),
-            seven: ScalarP(seven),
-            eight: ScalarP(eight),
-            n: ScalarN(
+            p: p.clone(),
+            p_sub2: ScalarP::new(p.clone().sub(&two)),
+            p_sub1_div2: ScalarP::new(p_sub1.clone().div(&two)),
+            p_add1_div4: ScalarP::new(p_add1.clone().div(&four)),
+            two: ScalarP::new(two),
+            three: ScalarP::new(three),
+            four: ScalarP::new(four),
+            seven: ScalarP::new(seven),
+            eight: ScalarP::new(eight),
+            n: Integer::from_str_radix(
+                "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141",
+                16,
+            )
+            .unwrap(),
+            beta: ScalarP::new(
                 Integer::from_str_radix(
-                    "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141",
+                    "7AE96A2B657C07106E64479EAC3434E99CF0497512F58995C1396C28719501EE",
                     16,
                 )
                 .unwrap(),
             ),
             G: g.clone(),
             G_jacobian: JacobianPoint::from(g),
+            H: Point::lift_x(&ScalarP::new(
+                Integer::from_str_radix(
+                    "50929B74C1A04954B78B4B6035E97A5E078A5A0F28EC96D547BFEE9ACE803AC0",
+                    16,
+                )
+                .unwrap(),
+            ))
+            .unwrap(),
         }
     }
 }
 
+// the window of `G_MUL_CACHE`, chosen at build time, see build.rs
+include!(concat!(env!("OUT_DIR"), "/g_mul_window.rs"));
+
+/// Rows of `G_MUL_CACHE`, one per window of the scalar
+pub const G_MUL_ROWS: usize = 256usize.div_ceil(G_MUL_WINDOW);
+
+#[cfg(not(feature = "lazy-g-mul-table"))]
+static G_MUL_TABLE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/g_mul_table.bin"));
+
 lazy_static! {
-    pub static ref G_MUL_CACHE: Vec<Point> = {
-        let total_elements = 8192usize;
-        let mut vec = Vec::with_capacity(total_elements);
-        let mut f = File::open("res/g_mul_cache.dat").unwrap();
-        let mut buffer = [0; 64];
-        for _ in 0..total_elements {
-            f.read(&mut buffer).unwrap();
-            let option = Point::from_uncompressed_bytes(&buffer);
-            vec.push(option.unwrap());
+    /// digit * 2^(G_MUL_WINDOW * row) * G at index row * 2^G_MUL_WINDOW + digit, the digit 0 holds G
+    pub static ref G_MUL_CACHE: Vec<Point> = g_mul_cache();
+}
+
+#[cfg(not(feature = "lazy-g-mul-table"))]
+fn g_mul_cache() -> Vec<Point> {
+    G_MUL_TABLE
+        .chunks(64)
+        .map(|bytes| Point::from_uncompressed_bytes(bytes).unwrap())
+        .collect()
+}
+
+#[cfg(feature = "lazy-g-mul-table")]
+fn g_mul_cache() -> Vec<Point> {
+    compute_g_mul_table(G_MUL_WINDOW)
+}
+
+/// The table of `G_MUL_CACHE` for any window, computed in memory
+pub fn compute_g_mul_table(window: usize) -> Vec<Point> {
+    let rows = 256usize.div_ceil(window);
+    let mut table = Vec::with_capacity(rows << window);
+    let mut base = CONTEXT.G_jacobian.clone();
+    for _ in 0..rows {
+        table.push(CONTEXT.G_jacobian.clone());
+        let mut current = Some(base.clone());
+        for _ in 1..1usize << window {
+            table.push(current.clone().unwrap());
+            current = jacobian_point_add(current.as_ref(), Some(&base));
+        }
+        for _ in 0..window {
+            base = base.double().unwrap();
         }
-        vec
-    };
+    }
+    batch_normalize(&table)
 }
 
 #[cfg(test)]
@@ -115,5 +161,12 @@ mod tests {
     fn test_load_big() {
         let option = G_MUL_CACHE.get(0).unwrap();
         assert_eq!(CONTEXT.G, option.to_owned());
+        assert_eq!(G_MUL_ROWS << G_MUL_WINDOW, G_MUL_CACHE.len());
+    }
+
+    // build.rs has its own arithmetic
+    #[test]
+    fn test_build_table() {
+        assert_eq!(*G_MUL_CACHE, compute_g_mul_table(G_MUL_WINDOW));
     }
 }
@@ -0,0 +1,68 @@
+use backend::native::{JacobianPoint, NormalizedPoint, Point, Signature};
+use error::{Error, Reason};
+use point::{generator_mul, jacobian_point_add, jacobian_point_mul_wnaf};
+use scalar::concat_and_hash_BIPSchnorr;
+use scalar::concat_and_hash_BIPSchnorrDerive;
+use scalar::ScalarN;
+use std::ops::{Add, Mul};
+use Msg;
+
+// The October 2019 draft of the Schnorr BIP, superseded by BIP340 and kept to compare the two.
+// Public key and R have a square y and hashes are tagged "BIPSchnorr" and "BIPSchnorrDerive"
+// https://github.com/sipa/bips/blob/bip-schnorr/bip-schnorr.mediawiki
+
+#[allow(non_snake_case)]
+pub fn schnorr_sign(msg: &Msg, sec_key: &ScalarN) -> Signature {
+    let P_jacobian = generator_mul(sec_key).unwrap();
+    let P = Point::from(P_jacobian);
+
+    let sec_key_sq = if P.y.is_square() {
+        sec_key.clone()
+    } else {
+        sec_key.negate()
+    };
+
+    let k0 = concat_and_hash_BIPSchnorrDerive(&sec_key_sq.to_32_bytes(), msg, &[]);
+    let R_jacobian = generator_mul(&k0).unwrap();
+
+    let R = Point::from(R_jacobian);
+    let k = if R.y.is_square() {
+        k0
+    } else {
+        k0.negate()
+    };
+
+    let e = concat_and_hash_BIPSchnorr(&R.as_bytes(), &P.as_bytes(), msg);
+
+    let s = k.add(e.mul(&sec_key_sq));
+
+    Signature::new(R.x, s)
+}
+
+pub fn schnorr_verify(msg: &Msg, pub_key: &NormalizedPoint, signature: &Signature) -> bool {
+    check_signature(msg, pub_key, signature).is_ok()
+}
+
+#[allow(non_snake_case)]
+pub fn check_signature(msg: &Msg, pub_key: &NormalizedPoint, signature: &Signature) -> Result<(), Error> {
+    // the draft lifts x to the point with square y
+    let pub_key = Point::from_bytes(&pub_key.as_bytes())?;
+
+    // r < p and s < n are enforced when the signature is built
+    let e = concat_and_hash_BIPSchnorr(&signature.Rx.to_32_bytes(), &pub_key.as_bytes()[..], msg);
+
+    let a = generator_mul(&signature.s);
+    let b = jacobian_point_mul_wnaf(&JacobianPoint::from(pub_key), &e.negate(), 5i8);
+    let R = match jacobian_point_add(a.as_ref(), b.as_ref()) {
+        Some(R) => Point::from(R),
+        None => return Err(Error::InvalidSignature { reason: Reason::RAtInfinity }),
+    };
+
+    if !R.y.is_square() {
+        return Err(Error::InvalidSignature { reason: Reason::RNotSquareY });
+    }
+    if R.x != signature.Rx {
+        return Err(Error::InvalidSignature { reason: Reason::RxMismatch });
+    }
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use backend::native::{JacobianPoint, Point};
+use error::Error;
+use point::jacobian_point_mul_wnaf;
+use scalar::sha256;
+use scalar::ScalarN;
+
+// Elliptic curve Diffie-Hellman: both parties compute the point a*B = b*A and hash it into a
+// shared secret. The peer point comes from the other party, so it is checked to be on the curve
+// before the multiplication, otherwise the result could leak the secret key (invalid curve attack).
+
+/// The shared point sec_key * peer, the input for the hashes below
+pub fn ecdh_point(sec_key: &ScalarN, peer: &Point) -> Result<Point, Error> {
+    // the point at infinity has no affine coordinates, the default (0, 0) stands for it
+    if peer.x.is_zero() && peer.y.is_zero() {
+        return Err(Error::PointAtInfinity);
+    }
+    if !peer.on_curve() {
+        return Err(Error::NotOnCurve);
+    }
+    jacobian_point_mul_wnaf(&JacobianPoint::from(peer.clone()), sec_key, 5i8)
+        .map(Point::from)
+        .ok_or(Error::PointAtInfinity)
+}
+
+/// The libsecp256k1 default: SHA256 of the compressed shared point
+pub fn ecdh(sec_key: &ScalarN, peer: &Point) -> Result<[u8; 32], Error> {
+    ecdh_with_hash(sec_key, peer, |point| sha256(&point.as_compressed_bytes()))
+}
+
+/// The x coordinate of the shared point, unhashed, as with x-only keys
+pub fn ecdh_x_only(sec_key: &ScalarN, peer: &Point) -> Result<[u8; 32], Error> {
+    ecdh_with_hash(sec_key, peer, Point::as_bytes)
+}
+
+/// Derives the secret from the shared point with a caller supplied function, like the hash
+/// function argument of `secp256k1_ecdh`
+pub fn ecdh_with_hash<F, T>(sec_key: &ScalarN, peer: &Point, hash: F) -> Result<T, Error>
+where
+    F: Fn(&Point) -> T,
+{
+    ecdh_point(sec_key, peer).map(|point| hash(&point))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use context::CONTEXT;
+    use point::generator_mul;
+    use rand::prelude::*;
+    use scalar::ScalarP;
+    use secp256k1::ecdh::SharedSecret;
+    use secp256k1::key::{PublicKey, SecretKey};
+    use secp256k1::Secp256k1;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn test_ecdh() {
+        let secp = Secp256k1::new();
+        let mut rng = thread_rng();
+        for _ in 0..16 {
+            let a = rng.gen::<ScalarN>();
+            let b = rng.gen::<ScalarN>();
+            let A = Point::from(generator_mul(&a).unwrap());
+            let B = Point::from(generator_mul(&b).unwrap());
+            assert_eq!(ecdh_point(&a, &B), ecdh_point(&b, &A));
+            assert_eq!(ecdh_x_only(&a, &B), ecdh_x_only(&b, &A));
+            assert_eq!(ecdh_x_only(&a, &B).unwrap(), ecdh_point(&a, &B).unwrap().as_bytes());
+
+            let shared = ecdh(&a, &B).unwrap();
+            assert_eq!(Ok(shared), ecdh(&b, &A));
+            let sk = SecretKey::from_slice(&secp, &a.to_32_bytes()).unwrap();
+            let pk = PublicKey::from_slice(&secp, &B.as_compressed_bytes()).unwrap();
+            assert_eq!(&SharedSecret::new(&secp, &pk, &sk)[..], &shared[..]);
+
+            let uncompressed = ecdh_with_hash(&a, &B, Point::as_uncompressed_bytes).unwrap();
+            assert_eq!(&uncompressed[..32], &ecdh_x_only(&b, &A).unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn test_invalid_peer() {
+        let a = thread_rng().gen::<ScalarN>();
+        let mut off_curve = CONTEXT.G.clone();
+        off_curve.y = off_curve.y + &ScalarP::from_u64(1);
+        assert_eq!(Err(Error::NotOnCurve), ecdh(&a, &off_curve));
+        assert_eq!(Err(Error::PointAtInfinity), ecdh(&a, &Point::default()));
+        assert_eq!(Err(Error::PointAtInfinity), ecdh(&ScalarN::from_bytes(&[0u8]), &CONTEXT.G));
+    }
+}
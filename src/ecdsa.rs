@@ -0,0 +1,349 @@
+use backend::native::{JacobianPoint, Point};
+use context::CONTEXT;
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::sha2::Sha256;
+use error::{check_length, Error};
+use point::{generator_mul, jacobian_point_add, jacobian_point_mul_wnaf};
+use scalar::{ScalarN, ScalarP};
+use std::ops::{Add, Mul};
+use util::rug::integer_from_bytes;
+
+// ECDSA over secp256k1 for comparison with Schnorr, signing a 32 bytes message hash like libsecp256k1:
+// k from RFC6979 with HMAC-SHA256, r = x(k*G) mod n, s = (e + r*d)/k normalized to s <= n/2.
+// Unlike Schnorr the verification needs an inversion and there is no linearity to aggregate keys
+// or signatures, the recovery id lets the verifier compute the public key from the signature.
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct EcdsaSignature {
+    pub r: ScalarN,
+    pub s: ScalarN,
+}
+
+fn hmac_sha256(key: &[u8], data: &[&[u8]]) -> [u8; 32] {
+    let mut hmac = Hmac::new(Sha256::new(), key);
+    for el in data.iter() {
+        hmac.input(el);
+    }
+    let mut result = [0u8; 32];
+    result.copy_from_slice(hmac.result().code());
+    result
+}
+
+/// RFC6979 deterministic nonces for `sec_key` and `msg_hash`, the first is used unless it
+/// gives r = 0 or s = 0
+pub struct Rfc6979 {
+    k: [u8; 32],
+    v: [u8; 32],
+}
+
+impl Rfc6979 {
+    pub fn new(sec_key: &ScalarN, msg_hash: &[u8; 32]) -> Self {
+        let x = sec_key.to_32_bytes();
+        // bits2octets, the hash reduced mod n
+        let h1 = ScalarN::from_bytes(msg_hash).to_32_bytes();
+        let v = [1u8; 32];
+        let k = hmac_sha256(&[0u8; 32], &[&v, &[0u8], &x, &h1]);
+        let v = hmac_sha256(&k, &[&v]);
+        let k = hmac_sha256(&k, &[&v, &[1u8], &x, &h1]);
+        let v = hmac_sha256(&k, &[&v]);
+        Rfc6979 { k, v }
+    }
+}
+
+impl Iterator for Rfc6979 {
+    type Item = ScalarN;
+
+    fn next(&mut self) -> Option<ScalarN> {
+        loop {
+            self.v = hmac_sha256(&self.k, &[&self.v]);
+            let candidate = ScalarN::try_from_bytes(&self.v);
+            // the state is updated also after a valid candidate, for the next retry
+            self.k = hmac_sha256(&self.k, &[&self.v, &[0u8]]);
+            self.v = hmac_sha256(&self.k, &[&self.v]);
+            match candidate {
+                Ok(k) if !k.is_zero() => return Some(k),
+                _ => continue,
+            }
+        }
+    }
+}
+
+/// Signs the 32 bytes `msg_hash`, returns the low-S signature and the recovery id
+#[allow(non_snake_case)]
+pub fn ecdsa_sign_recoverable(msg_hash: &[u8; 32], sec_key: &ScalarN) -> (EcdsaSignature, u8) {
+    let e = ScalarN::from_bytes(msg_hash);
+    for k in Rfc6979::new(sec_key, msg_hash) {
+        let R = Point::from(generator_mul(&k).unwrap());
+        let r = ScalarN::new(R.x.to_integer());
+        if r.is_zero() {
+            continue;
+        }
+        let s = k.inv().mul(&e.clone().add(r.clone().mul(sec_key)));
+        if s.is_zero() {
+            continue;
+        }
+        // bit 0 is the parity of y(R), bit 1 is set when x(R) was reduced
+        let mut recovery_id = (!R.has_even_y() as u8) | (((R.x.to_integer() != r.to_integer()) as u8) << 1);
+        let s = if s.is_high() {
+            recovery_id ^= 1;
+            s.negate()
+        } else {
+            s
+        };
+        return (EcdsaSignature { r, s }, recovery_id);
+    }
+    unreachable!()
+}
+
+pub fn ecdsa_sign(msg_hash: &[u8; 32], sec_key: &ScalarN) -> EcdsaSignature {
+    ecdsa_sign_recoverable(msg_hash, sec_key).0
+}
+
+// s^-1*e*G + s^-1*r*P
+fn public_nonce(msg_hash: &[u8; 32], pub_key: &Point, signature: &EcdsaSignature) -> Option<JacobianPoint> {
+    let e = ScalarN::from_bytes(msg_hash);
+    let w = signature.s.inv();
+    let u1 = e.mul(&w);
+    let u2 = signature.r.clone().mul(&w);
+    let a = generator_mul(&u1);
+    let b = jacobian_point_mul_wnaf(&JacobianPoint::from(pub_key.clone()), &u2, 5i8);
+    jacobian_point_add(a.as_ref(), b.as_ref())
+}
+
+/// Like libsecp256k1 only low-S signatures are valid, see `EcdsaSignature::normalize_s`
+pub fn ecdsa_verify(msg_hash: &[u8; 32], pub_key: &Point, signature: &EcdsaSignature) -> bool {
+    if signature.r.is_zero() || signature.s.is_zero() || signature.s.is_high() {
+        return false;
+    }
+    match public_nonce(msg_hash, pub_key, signature) {
+        Some(point) => ScalarN::new(Point::from(point).x.to_integer()) == signature.r,
+        None => false,
+    }
+}
+
+/// The public key that makes `signature` valid for `msg_hash`, Q = r^-1 (s*R - e*G)
+#[allow(non_snake_case)]
+pub fn ecdsa_recover(msg_hash: &[u8; 32], signature: &EcdsaSignature, recovery_id: u8) -> Result<Point, Error> {
+    if recovery_id > 3 {
+        return Err(Error::InvalidRecoveryId { found: recovery_id });
+    }
+    if signature.r.is_zero() || signature.s.is_zero() {
+        return Err(Error::ScalarOutOfRange);
+    }
+    let mut x = signature.r.to_integer();
+    if recovery_id & 2 != 0 {
+        x += &CONTEXT.n;
+        if x >= CONTEXT.p {
+            return Err(Error::FieldElementOutOfRange);
+        }
+    }
+    let R = Point::lift_x(&ScalarP::new(x)).ok_or(Error::NotOnCurve)?;
+    let R = if (recovery_id & 1 == 1) == R.has_even_y() {
+        R.negate()
+    } else {
+        R
+    };
+    let r_inv = signature.r.inv();
+    let e = ScalarN::from_bytes(msg_hash);
+    let a = generator_mul(&e.negate().mul(&r_inv));
+    let b = jacobian_point_mul_wnaf(&JacobianPoint::from(R), &signature.s.clone().mul(&r_inv), 5i8);
+    jacobian_point_add(a.as_ref(), b.as_ref())
+        .map(Point::from)
+        .ok_or(Error::PointAtInfinity)
+}
+
+// minimal big endian encoding of a positive integer, with a leading zero if the high bit is set
+fn der_integer(value: &ScalarN) -> Vec<u8> {
+    let bytes = value.to_32_bytes();
+    let start = bytes.iter().take_while(|el| **el == 0).count().min(31);
+    let mut vec = Vec::with_capacity(35);
+    vec.push(0x02);
+    if bytes[start] & 0x80 != 0 {
+        vec.push((33 - start) as u8);
+        vec.push(0);
+    } else {
+        vec.push((32 - start) as u8);
+    }
+    vec.extend(&bytes[start..]);
+    vec
+}
+
+// parses a strict DER integer in 1..n, returns it with the remaining bytes
+fn parse_der_integer(bytes: &[u8]) -> Result<(ScalarN, &[u8]), Error> {
+    if bytes.len() < 3 || bytes[0] != 0x02 {
+        return Err(Error::InvalidDer);
+    }
+    let len = bytes[1] as usize;
+    if len == 0 || len > 33 || bytes.len() < 2 + len {
+        return Err(Error::InvalidDer);
+    }
+    let value = &bytes[2..2 + len];
+    // negative or not minimal
+    if value[0] & 0x80 != 0 || (len > 1 && value[0] == 0 && value[1] & 0x80 == 0) {
+        return Err(Error::InvalidDer);
+    }
+    let value = integer_from_bytes(value);
+    if value == 0 || value >= CONTEXT.n {
+        return Err(Error::ScalarOutOfRange);
+    }
+    Ok((ScalarN::new(value), &bytes[2 + len..]))
+}
+
+impl EcdsaSignature {
+    /// 64 bytes r || s
+    pub fn from_compact(bytes: &[u8]) -> Result<Self, Error> {
+        check_length(bytes, 64)?;
+        Ok(EcdsaSignature {
+            r: ScalarN::try_from_bytes(&bytes[..32])?,
+            s: ScalarN::try_from_bytes(&bytes[32..])?,
+        })
+    }
+
+    pub fn as_compact(&self) -> [u8; 64] {
+        let mut result = [0u8; 64];
+        result[..32].copy_from_slice(&self.r.to_32_bytes());
+        result[32..].copy_from_slice(&self.s.to_32_bytes());
+        result
+    }
+
+    /// 0x30 len 0x02 len(r) r 0x02 len(s) s as enforced by BIP66
+    pub fn from_der(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < 8 || bytes[0] != 0x30 || bytes[1] as usize != bytes.len() - 2 {
+            return Err(Error::InvalidDer);
+        }
+        let (r, rest) = parse_der_integer(&bytes[2..])?;
+        let (s, rest) = parse_der_integer(rest)?;
+        if !rest.is_empty() {
+            return Err(Error::InvalidDer);
+        }
+        Ok(EcdsaSignature { r, s })
+    }
+
+    pub fn to_der(&self) -> Vec<u8> {
+        let r = der_integer(&self.r);
+        let s = der_integer(&self.s);
+        let mut vec = Vec::with_capacity(72);
+        vec.push(0x30);
+        vec.push((r.len() + s.len()) as u8);
+        vec.extend(r);
+        vec.extend(s);
+        vec
+    }
+
+    /// Replaces s with n - s if s is greater than n/2, both are valid for the same key and message
+    pub fn normalize_s(&mut self) {
+        if self.s.is_high() {
+            self.s = self.s.negate();
+        }
+    }
+
+    pub fn is_low_s(&self) -> bool {
+        !self.s.is_high()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_encoding::HEXLOWER;
+    use rand::prelude::*;
+    use secp256k1::key::{PublicKey, SecretKey};
+    use secp256k1::{Message, Secp256k1, Signature};
+
+    #[test]
+    fn test_against_libsecp() {
+        let secp = Secp256k1::new();
+        let mut rng = thread_rng();
+        for _ in 0..32 {
+            let sec_key = rng.gen::<ScalarN>();
+            let msg_hash = rng.gen::<[u8; 32]>();
+            let pub_key = Point::from(generator_mul(&sec_key).unwrap());
+            let sk = SecretKey::from_slice(&secp, &sec_key.to_32_bytes()).unwrap();
+            let pk = PublicKey::from_secret_key(&secp, &sk).unwrap();
+            let message = Message::from_slice(&msg_hash).unwrap();
+            assert_eq!(Ok(pk), PublicKey::from_slice(&secp, &pub_key.as_compressed_bytes()));
+
+            // same deterministic nonce, same normalization and same recovery id
+            let (signature, recovery_id) = ecdsa_sign_recoverable(&msg_hash, &sec_key);
+            let (expected_id, expected) = secp.sign_recoverable(&message, &sk).unwrap().serialize_compact(&secp);
+            assert_eq!(&signature.as_compact()[..], &expected[..]);
+            assert_eq!(i32::from(recovery_id), expected_id.to_i32());
+            let expected_der = secp.sign(&message, &sk).unwrap().serialize_der(&secp);
+            assert_eq!(signature.to_der(), expected_der);
+            assert_eq!(Ok(signature.clone()), EcdsaSignature::from_der(&expected_der));
+
+            assert!(ecdsa_verify(&msg_hash, &pub_key, &signature));
+            assert!(!ecdsa_verify(&rng.gen(), &pub_key, &signature));
+            assert_eq!(Ok(pub_key), ecdsa_recover(&msg_hash, &signature, recovery_id));
+
+            // a signature of libsecp for another message
+            let msg_hash = rng.gen::<[u8; 32]>();
+            let message = Message::from_slice(&msg_hash).unwrap();
+            let der = secp.sign(&message, &sk).unwrap().serialize_der(&secp);
+            let signature = EcdsaSignature::from_der(&der).unwrap();
+            assert!(ecdsa_verify(&msg_hash, &Point::from(generator_mul(&sec_key).unwrap()), &signature));
+            // and ours verifies with libsecp
+            let signature = ecdsa_sign(&msg_hash, &sec_key);
+            let signature = Signature::from_der(&secp, &signature.to_der()).unwrap();
+            assert!(secp.verify(&message, &signature, &pk).is_ok());
+        }
+    }
+
+    // widely used secp256k1 RFC6979 vector (python-ecdsa, bitcoinjs): private key 1, message SHA256("Satoshi Nakamoto")
+    #[test]
+    fn test_rfc6979_vector() {
+        let sec_key = ScalarN::from_bytes(&[1u8]);
+        let msg_hash = ::scalar::sha256(b"Satoshi Nakamoto");
+        let k = Rfc6979::new(&sec_key, &msg_hash).next().unwrap();
+        assert_eq!(
+            HEXLOWER.encode(&k.to_32_bytes()),
+            "8f8a276c19f4149656b280621e358cce24f5f52542772691ee69063b74f15d15"
+        );
+        assert_eq!(
+            HEXLOWER.encode(&ecdsa_sign(&msg_hash, &sec_key).as_compact()),
+            "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d82442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5"
+        );
+    }
+
+    #[test]
+    fn test_low_s_and_der() {
+        let mut rng = thread_rng();
+        let sec_key = rng.gen::<ScalarN>();
+        let pub_key = Point::from(generator_mul(&sec_key).unwrap());
+        let msg_hash = rng.gen::<[u8; 32]>();
+        let signature = ecdsa_sign(&msg_hash, &sec_key);
+        assert!(signature.is_low_s());
+        let mut high_s = signature.clone();
+        high_s.s = signature.s.negate();
+        assert!(!ecdsa_verify(&msg_hash, &pub_key, &high_s));
+        high_s.normalize_s();
+        assert_eq!(signature, high_s);
+        assert_eq!(Ok(signature.clone()), EcdsaSignature::from_compact(&signature.as_compact()));
+        assert_eq!(Err(Error::InvalidRecoveryId { found: 4 }), ecdsa_recover(&msg_hash, &signature, 4));
+
+        // small values are encoded on a single byte, a high bit needs a leading zero
+        let small = EcdsaSignature {
+            r: ScalarN::from_bytes(&[1u8]),
+            s: ScalarN::from_bytes(&[0x80u8]),
+        };
+        assert_eq!(small.to_der(), vec![0x30, 0x07, 0x02, 0x01, 0x01, 0x02, 0x02, 0x00, 0x80]);
+        assert_eq!(Ok(small.clone()), EcdsaSignature::from_der(&small.to_der()));
+        let invalid: [&[u8]; 7] = [
+            &[0x30, 0x07, 0x02, 0x01, 0x01, 0x02, 0x02, 0x00, 0x80, 0x00], // trailing byte
+            &[0x30, 0x08, 0x02, 0x01, 0x01, 0x02, 0x02, 0x00, 0x80],       // wrong total length
+            &[0x31, 0x07, 0x02, 0x01, 0x01, 0x02, 0x02, 0x00, 0x80],       // not a sequence
+            &[0x30, 0x07, 0x02, 0x01, 0x01, 0x03, 0x02, 0x00, 0x80],       // not an integer
+            &[0x30, 0x07, 0x02, 0x01, 0x01, 0x02, 0x02, 0x00, 0x7f],       // leading zero not needed
+            &[0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x80],             // negative
+            &[0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x02, 0x00],             // s longer than the sequence
+        ];
+        for bytes in invalid.iter() {
+            assert_eq!(Err(Error::InvalidDer), EcdsaSignature::from_der(bytes), "{:?}", bytes);
+        }
+        assert_eq!(
+            Err(Error::ScalarOutOfRange),
+            EcdsaSignature::from_der(&[0x30, 0x06, 0x02, 0x01, 0x00, 0x02, 0x01, 0x01])
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::error;
+use std::fmt;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Error {
+    /// A byte slice has not the expected length
+    InvalidLength { expected: usize, found: usize },
+    /// A scalar is not lower than the curve order n
+    ScalarOutOfRange,
+    /// A field element is not lower than the field size p
+    FieldElementOutOfRange,
+    /// There is no point on the curve with the given coordinates
+    NotOnCurve,
+    /// The point at infinity has no serialization and can't be a key
+    PointAtInfinity,
+    /// The first byte of a compressed point is neither 0x02 nor 0x03
+    InvalidPrefix { found: u8 },
+    /// Taproot leaf versions are even, the lowest bit of the control block byte is the parity
+    InvalidLeafVersion { found: u8 },
+    /// A script tree needs at least one leaf
+    EmptyTree,
+    /// The public key is not among the ones of the multisignature
+    UnknownSigner,
+    /// A multisignature needs at least one public key
+    NoPublicKeys,
+    /// The secret nonce was generated for a different public key
+    NonceKeyMismatch,
+    /// The secret share dealt by participant `index` does not match its commitment
+    InvalidShare { index: u32 },
+    /// Participant `index` did not prove knowledge of its secret
+    InvalidProofOfKnowledge { index: u32 },
+    /// Participants are numbered from 1, the share of index 0 would be the secret itself
+    InvalidIndex,
+    /// Two participants have the same index
+    DuplicateIndex { index: u32 },
+    /// The threshold is 0 or above the number of participants
+    InvalidThreshold { threshold: usize },
+    /// The polynomial of participant `index` has `found` coefficients instead of the threshold
+    ThresholdMismatch { index: u32, expected: usize, found: usize },
+    /// The signature is well formed but the verification equation does not hold
+    InvalidSignature { reason: Reason },
+    /// A string contains a character which is not in the base58 alphabet
+    InvalidBase58,
+    /// The last 4 bytes are not the double SHA256 of the payload
+    InvalidChecksum,
+    /// An extended key has an unknown version or a master key has a parent
+    InvalidExtendedKey,
+    /// A derivation path is not like m/0'/1/2h
+    InvalidPath,
+    /// Hardened children can't be derived from an extended public key
+    HardenedFromPublic,
+    /// An ECDSA signature is not strict DER (BIP66)
+    InvalidDer,
+    /// An ECDSA recovery id is not in 0..4
+    InvalidRecoveryId { found: u8 },
+}
+
+/// Which check of the verification equation failed, R is the point sG - eP
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Reason {
+    /// R is the point at infinity
+    RAtInfinity,
+    /// R has an odd y (BIP340)
+    ROddY,
+    /// R has a y which is not a square (2019 draft)
+    RNotSquareY,
+    /// x(R) differs from the r encoded in the signature
+    RxMismatch,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match *self {
+            Reason::RAtInfinity => "R is the point at infinity",
+            Reason::ROddY => "R has an odd y",
+            Reason::RNotSquareY => "R has a y which is not a square",
+            Reason::RxMismatch => "x(R) is not equal to r",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::InvalidLength { expected, found } => {
+                write!(f, "invalid length, expected {} bytes found {}", expected, found)
+            }
+            Error::ScalarOutOfRange => write!(f, "scalar is not lower than the curve order"),
+            Error::FieldElementOutOfRange => write!(f, "field element is not lower than the field size"),
+            Error::NotOnCurve => write!(f, "point is not on the curve"),
+            Error::PointAtInfinity => write!(f, "point at infinity"),
+            Error::InvalidPrefix { found } => write!(f, "invalid compressed point prefix {:#04x}", found),
+            Error::InvalidLeafVersion { found } => write!(f, "odd leaf version {:#04x}", found),
+            Error::EmptyTree => write!(f, "script tree without leaves"),
+            Error::UnknownSigner => write!(f, "public key is not among the signers"),
+            Error::NoPublicKeys => write!(f, "no public keys to aggregate"),
+            Error::NonceKeyMismatch => write!(f, "secret nonce belongs to another public key"),
+            Error::InvalidShare { index } => write!(f, "invalid secret share from participant {}", index),
+            Error::InvalidProofOfKnowledge { index } => {
+                write!(f, "invalid proof of knowledge from participant {}", index)
+            }
+            Error::InvalidIndex => write!(f, "participant index 0"),
+            Error::DuplicateIndex { index } => write!(f, "duplicate participant index {}", index),
+            Error::InvalidThreshold { threshold } => write!(f, "invalid threshold {}", threshold),
+            Error::ThresholdMismatch { index, expected, found } => write!(
+                f,
+                "participant {} has a threshold of {} instead of {}",
+                index, found, expected
+            ),
+            Error::InvalidSignature { reason } => write!(f, "invalid signature: {}", reason),
+            Error::InvalidBase58 => write!(f, "invalid base58 character"),
+            Error::InvalidChecksum => write!(f, "invalid checksum"),
+            Error::InvalidExtendedKey => write!(f, "invalid extended key"),
+            Error::InvalidPath => write!(f, "invalid derivation path"),
+            Error::HardenedFromPublic => write!(f, "can't derive a hardened child from a public key"),
+            Error::InvalidDer => write!(f, "signature is not strict DER"),
+            Error::InvalidRecoveryId { found } => write!(f, "invalid recovery id {}", found),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// The indexes of the invalid signatures of a batch, in increasing order
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BatchError {
+    pub invalid: Vec<usize>,
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} invalid signatures in the batch at {:?}", self.invalid.len(), self.invalid)
+    }
+}
+
+impl error::Error for BatchError {}
+
+pub fn check_length(bytes: &[u8], expected: usize) -> Result<(), Error> {
+    if bytes.len() != expected {
+        return Err(Error::InvalidLength {
+            expected,
+            found: bytes.len(),
+        });
+    }
+    Ok(())
+}
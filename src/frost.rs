@@ -0,0 +1,631 @@
+use backend::native::{JacobianPoint, NormalizedPoint, Point, Signature};
+use context::CONTEXT;
+use error::Error;
+use point::{generator_mul, jacobian_point_add, jacobian_point_mul_wnaf};
+use rand::{CryptoRng, Rng, RngCore};
+use rug::Integer;
+use scalar::{concat_and_hash_BIP0340_challenge, tagged_hash};
+use scalar::ScalarN;
+use std::ops::{Add, Mul, Sub};
+
+// FROST: Flexible Round-Optimized Schnorr Threshold signatures
+// https://eprint.iacr.org/2020/852.pdf and https://www.rfc-editor.org/rfc/rfc9591
+// Any t of the n participants produce a BIP340 signature for the x-only group key.
+// The secret is shared with a polynomial of degree t-1 over ScalarN, participant i holds f(i),
+// either given by a trusted dealer or built with a Pedersen DKG where every participant deals a
+// polynomial and the shares are summed. Feldman commitments to the coefficients let everybody
+// check their share. Like BIP340, an odd y of the group key or of R is handled by negating the
+// secret shares or the nonces when signing.
+
+/// Feldman commitment, the coefficients of the sharing polynomial times G
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Commitment(pub Vec<Point>);
+
+/// What participant `index` needs to sign, `pub_share` is `sec_share*G` and is public
+#[derive(Clone, Debug)]
+pub struct KeyShare {
+    pub index: u32,
+    pub sec_share: ScalarN,
+    pub pub_share: Point,
+    pub group_pub_key: Point,
+}
+
+/// Schnorr proof of knowledge of the constant term, prevents rogue key attacks in the DKG
+#[allow(non_snake_case)]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ProofOfKnowledge {
+    pub R: Point,
+    pub mu: ScalarN,
+}
+
+/// The first round DKG message a participant broadcasts to the others
+#[derive(Clone, Debug)]
+pub struct DkgPackage {
+    pub index: u32,
+    pub commitment: Commitment,
+    pub proof: ProofOfKnowledge,
+}
+
+/// The secret polynomial a participant keeps during the DKG
+#[derive(Debug)]
+pub struct DkgSecret {
+    index: u32,
+    coefficients: Vec<ScalarN>,
+}
+
+/// The two secret nonces of a signer, not `Clone` so that `sign` consumes it once
+#[derive(Debug)]
+pub struct SigningNonces {
+    d: ScalarN,
+    e: ScalarN,
+}
+
+#[allow(non_snake_case)]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct NonceCommitment {
+    pub index: u32,
+    pub D: Point,
+    pub E: Point,
+}
+
+/// Values shared by the signers once the commitments of all of them and the message are known
+#[allow(non_snake_case)]
+#[derive(Clone, Debug)]
+pub struct Session {
+    group_pub_key: Point,
+    commitments: Vec<NonceCommitment>,
+    binding_factors: Vec<ScalarN>,
+    R: Point,
+    c: ScalarN,
+}
+
+fn scalar(index: u32) -> ScalarN {
+    ScalarN::new(Integer::from(index))
+}
+
+fn negate(scalar: ScalarN) -> ScalarN {
+    scalar.negate()
+}
+
+fn point_mul(point: &Point, scalar: &ScalarN) -> Option<JacobianPoint> {
+    jacobian_point_mul_wnaf(&JacobianPoint::from(point.clone()), scalar, 5i8)
+}
+
+fn random_coefficients<R: RngCore + CryptoRng>(rng: &mut R, first: ScalarN, threshold: usize) -> Vec<ScalarN> {
+    assert!(threshold > 0);
+    let mut coefficients = vec![first];
+    for _ in 1..threshold {
+        coefficients.push(rng.gen());
+    }
+    coefficients
+}
+
+// Horner evaluation of the polynomial in x
+fn evaluate(coefficients: &[ScalarN], index: u32) -> ScalarN {
+    let x = scalar(index);
+    let mut acc = ScalarN::new(Integer::new());
+    for coefficient in coefficients.iter().rev() {
+        acc = acc.mul(&x).add(coefficient.clone());
+    }
+    acc
+}
+
+fn commit_coefficients(coefficients: &[ScalarN]) -> Commitment {
+    // a zero coefficient has negligible probability
+    Commitment(
+        coefficients
+            .iter()
+            .map(|coefficient| Point::from(generator_mul(coefficient).unwrap()))
+            .collect(),
+    )
+}
+
+// participant indexes start from 1 and are all different
+fn check_indices(indices: &[u32]) -> Result<(), Error> {
+    let mut sorted = indices.to_vec();
+    sorted.sort();
+    if sorted.first() == Some(&0) {
+        return Err(Error::InvalidIndex);
+    }
+    match sorted.windows(2).find(|pair| pair[0] == pair[1]) {
+        Some(pair) => Err(Error::DuplicateIndex { index: pair[0] }),
+        None => Ok(()),
+    }
+}
+
+/// Lagrange coefficient at 0 of `index` for the interpolation over the points of `indices`
+pub fn lagrange_coefficient(index: u32, indices: &[u32]) -> Result<ScalarN, Error> {
+    check_indices(indices)?;
+    if index == 0 {
+        return Err(Error::InvalidIndex);
+    }
+    let x_i = scalar(index);
+    let mut num = scalar(1);
+    let mut den = scalar(1);
+    for j in indices.iter().filter(|j| **j != index) {
+        let x_j = scalar(*j);
+        num = num.mul(&x_j);
+        den = den.mul(&x_j.sub(&x_i));
+    }
+    Ok(num.mul(&den.inv()))
+}
+
+impl Commitment {
+    /// The public share of participant `index`, `f(index)*G` computed from the coefficients
+    pub fn pub_share(&self, index: u32) -> Option<Point> {
+        let x = scalar(index);
+        let mut acc: Option<JacobianPoint> = None;
+        for point in self.0.iter().rev() {
+            acc = acc.and_then(|acc| point_mul(&Point::from(acc), &x));
+            acc = jacobian_point_add(acc.as_ref(), Some(&JacobianPoint::from(point.clone())));
+        }
+        acc.map(Point::from)
+    }
+
+    /// Feldman check of a secret share received from the dealer
+    pub fn verify_share(&self, index: u32, sec_share: &ScalarN) -> bool {
+        let expected = generator_mul(sec_share).map(Point::from);
+        expected.is_some() && expected == self.pub_share(index)
+    }
+
+    pub fn group_pub_key(&self) -> &Point {
+        &self.0[0]
+    }
+
+    pub fn threshold(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// A trusted dealer splits `sec_key` in `participants` shares, any `threshold` of them can sign.
+/// Participants are numbered from 1
+pub fn trusted_dealer_keygen<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    sec_key: &ScalarN,
+    threshold: usize,
+    participants: u32,
+) -> Result<(Commitment, Vec<KeyShare>), Error> {
+    if threshold == 0 || threshold as u64 > u64::from(participants) {
+        return Err(Error::InvalidThreshold { threshold });
+    }
+    let coefficients = random_coefficients(rng, sec_key.clone(), threshold);
+    let commitment = commit_coefficients(&coefficients);
+    let key_shares = (1..=participants)
+        .map(|index| {
+            let sec_share = evaluate(&coefficients, index);
+            KeyShare {
+                index,
+                pub_share: Point::from(generator_mul(&sec_share).unwrap()),
+                sec_share,
+                group_pub_key: commitment.group_pub_key().clone(),
+            }
+        })
+        .collect();
+    Ok((commitment, key_shares))
+}
+
+fn dkg_challenge(index: u32, commitment: &Commitment, r: &Point) -> ScalarN {
+    let mut input = scalar(index).to_32_bytes().to_vec();
+    input.extend(&commitment.group_pub_key().as_compressed_bytes()[..]);
+    input.extend(&r.as_compressed_bytes()[..]);
+    ScalarN::from_bytes(&tagged_hash(b"FROST/dkg", &input))
+}
+
+/// DKG first round, the package is broadcast to every other participant
+#[allow(non_snake_case)]
+pub fn dkg_round1<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    index: u32,
+    threshold: usize,
+) -> Result<(DkgSecret, DkgPackage), Error> {
+    if index == 0 {
+        return Err(Error::InvalidIndex);
+    }
+    if threshold == 0 {
+        return Err(Error::InvalidThreshold { threshold });
+    }
+    let constant_term = rng.gen();
+    let coefficients = random_coefficients(rng, constant_term, threshold);
+    let commitment = commit_coefficients(&coefficients);
+
+    let k = rng.gen::<ScalarN>();
+    let R = Point::from(generator_mul(&k).unwrap());
+    let c = dkg_challenge(index, &commitment, &R);
+    let mu = k.add(coefficients[0].clone().mul(&c));
+
+    let secret = DkgSecret { index, coefficients };
+    let package = DkgPackage {
+        index,
+        commitment,
+        proof: ProofOfKnowledge { R, mu },
+    };
+    Ok((secret, package))
+}
+
+impl DkgSecret {
+    /// DKG second round, the share sent privately to participant `receiver`
+    pub fn share_for(&self, receiver: u32) -> Result<ScalarN, Error> {
+        if receiver == 0 {
+            return Err(Error::InvalidIndex);
+        }
+        Ok(evaluate(&self.coefficients, receiver))
+    }
+
+    /// Checks the packages and the shares received from the others, returns the key share.
+    /// `packages` and `shares` are indexed alike and include neither our package nor our share
+    pub fn finalize(&self, packages: &[DkgPackage], shares: &[ScalarN]) -> Result<KeyShare, Error> {
+        if packages.len() != shares.len() {
+            return Err(Error::InvalidLength {
+                expected: packages.len(),
+                found: shares.len(),
+            });
+        }
+        let mut indices = vec![self.index];
+        indices.extend(packages.iter().map(|package| package.index));
+        check_indices(&indices)?;
+
+        let mut sec_share = evaluate(&self.coefficients, self.index);
+        let mut group_pub_key = generator_mul(&self.coefficients[0]);
+        for (package, share) in packages.iter().zip(shares.iter()) {
+            if package.commitment.threshold() != self.coefficients.len() {
+                return Err(Error::ThresholdMismatch {
+                    index: package.index,
+                    expected: self.coefficients.len(),
+                    found: package.commitment.threshold(),
+                });
+            }
+            if !package.verify_proof() {
+                return Err(Error::InvalidProofOfKnowledge { index: package.index });
+            }
+            if !package.commitment.verify_share(self.index, share) {
+                return Err(Error::InvalidShare { index: package.index });
+            }
+            sec_share = sec_share.add(share.clone());
+            let constant_term = JacobianPoint::from(package.commitment.group_pub_key().clone());
+            group_pub_key = jacobian_point_add(group_pub_key.as_ref(), Some(&constant_term));
+        }
+
+        Ok(KeyShare {
+            index: self.index,
+            pub_share: Point::from(generator_mul(&sec_share).ok_or(Error::PointAtInfinity)?),
+            sec_share,
+            group_pub_key: Point::from(group_pub_key.ok_or(Error::PointAtInfinity)?),
+        })
+    }
+}
+
+impl DkgPackage {
+    /// Checks mu*G = R + c*A0 where A0 commits to the constant term
+    pub fn verify_proof(&self) -> bool {
+        let c = dkg_challenge(self.index, &self.commitment, &self.proof.R);
+        let left = generator_mul(&self.proof.mu).map(Point::from);
+        let c_a0 = point_mul(self.commitment.group_pub_key(), &c);
+        let right = jacobian_point_add(Some(&JacobianPoint::from(self.proof.R.clone())), c_a0.as_ref());
+        left.is_some() && left == right.map(Point::from)
+    }
+}
+
+/// The public share of participant `index` after a DKG, from the commitments of every participant
+pub fn dkg_pub_share(packages: &[DkgPackage], index: u32) -> Option<Point> {
+    let mut acc: Option<JacobianPoint> = None;
+    for package in packages.iter() {
+        let pub_share = package.commitment.pub_share(index).map(JacobianPoint::from);
+        acc = jacobian_point_add(acc.as_ref(), pub_share.as_ref());
+    }
+    acc.map(Point::from)
+}
+
+/// First round of signing: the nonces stay with the signer, the commitment goes to the others
+#[allow(non_snake_case)]
+pub fn commit<R: RngCore + CryptoRng>(rng: &mut R, key_share: &KeyShare) -> (SigningNonces, NonceCommitment) {
+    // hedge the randomness with the secret share, as RFC9591 does
+    let mut nonce = || {
+        let mut input = rng.gen::<[u8; 32]>().to_vec();
+        input.extend(&key_share.sec_share.to_32_bytes()[..]);
+        ScalarN::from_bytes(&tagged_hash(b"FROST/nonce", &input))
+    };
+    let d = nonce();
+    let e = nonce();
+    let D = Point::from(generator_mul(&d).unwrap());
+    let E = Point::from(generator_mul(&e).unwrap());
+    (
+        SigningNonces { d, e },
+        NonceCommitment {
+            index: key_share.index,
+            D,
+            E,
+        },
+    )
+}
+
+impl Session {
+    /// The commitments of the signers (at least the threshold), in any order
+    #[allow(non_snake_case)]
+    pub fn new(group_pub_key: &Point, commitments: &[NonceCommitment], msg: &[u8]) -> Result<Self, Error> {
+        let indices: Vec<u32> = commitments.iter().map(|el| el.index).collect();
+        check_indices(&indices)?;
+        let mut commitments = commitments.to_vec();
+        commitments.sort_by_key(|el| el.index);
+        let Y_bytes = group_pub_key.as_bytes();
+
+        let mut encoded = Vec::with_capacity(commitments.len() * 98);
+        for commitment in commitments.iter() {
+            encoded.extend(&scalar(commitment.index).to_32_bytes()[..]);
+            encoded.extend(&commitment.D.as_compressed_bytes()[..]);
+            encoded.extend(&commitment.E.as_compressed_bytes()[..]);
+        }
+        let mut prefix = Y_bytes.to_vec();
+        prefix.extend(&tagged_hash(b"FROST/msg", msg)[..]);
+        prefix.extend(&tagged_hash(b"FROST/com", &encoded)[..]);
+        let binding_factors: Vec<ScalarN> = commitments
+            .iter()
+            .map(|commitment| {
+                let mut input = prefix.clone();
+                input.extend(&scalar(commitment.index).to_32_bytes()[..]);
+                ScalarN::from_bytes(&tagged_hash(b"FROST/rho", &input))
+            })
+            .collect();
+
+        let mut R: Option<JacobianPoint> = None;
+        for (commitment, rho) in commitments.iter().zip(binding_factors.iter()) {
+            R = jacobian_point_add(R.as_ref(), Some(&JacobianPoint::from(commitment.D.clone())));
+            R = jacobian_point_add(R.as_ref(), point_mul(&commitment.E, rho).as_ref());
+        }
+        // R at infinity happens with negligible probability unless the signers collude
+        let R = R.map(Point::from).unwrap_or_else(|| CONTEXT.G.clone());
+        let c = concat_and_hash_BIP0340_challenge(&R.as_bytes(), &Y_bytes, msg);
+
+        Ok(Session {
+            group_pub_key: group_pub_key.clone(),
+            commitments,
+            binding_factors,
+            R,
+            c,
+        })
+    }
+
+    fn position(&self, index: u32) -> Option<usize> {
+        self.commitments.iter().position(|el| el.index == index)
+    }
+
+    // the indexes were checked by `new`
+    fn lambda(&self, index: u32) -> ScalarN {
+        let indices: Vec<u32> = self.commitments.iter().map(|el| el.index).collect();
+        lagrange_coefficient(index, &indices).unwrap()
+    }
+
+    /// Second round: z = d + e*rho + lambda*c*s with the signs fixed for even y of R and the key
+    pub fn sign(&self, nonces: SigningNonces, key_share: &KeyShare) -> Result<ScalarN, Error> {
+        let position = self.position(key_share.index).ok_or(Error::UnknownSigner)?;
+        if key_share.group_pub_key != self.group_pub_key {
+            return Err(Error::UnknownSigner);
+        }
+        let (d, e) = if self.R.has_even_y() {
+            (nonces.d, nonces.e)
+        } else {
+            (negate(nonces.d), negate(nonces.e))
+        };
+        let sec_share = if self.group_pub_key.has_even_y() {
+            key_share.sec_share.clone()
+        } else {
+            negate(key_share.sec_share.clone())
+        };
+        let rho = &self.binding_factors[position];
+        let lambda = self.lambda(key_share.index);
+        Ok(d.add(e.mul(rho)).add(lambda.mul(&self.c).mul(&sec_share)))
+    }
+
+    /// Checks the signature share z of participant `index` against its public share
+    #[allow(non_snake_case)]
+    pub fn verify_share(&self, index: u32, z: &ScalarN, pub_share: &Point) -> bool {
+        let position = match self.position(index) {
+            Some(position) => position,
+            None => return false,
+        };
+        let commitment = &self.commitments[position];
+        let eE = point_mul(&commitment.E, &self.binding_factors[position]);
+        let R_i = jacobian_point_add(Some(&JacobianPoint::from(commitment.D.clone())), eE.as_ref());
+        let R_i = if self.R.has_even_y() { R_i } else { R_i.map(JacobianPoint::negate) };
+        let Y_i = if self.group_pub_key.has_even_y() {
+            pub_share.clone()
+        } else {
+            pub_share.clone().negate()
+        };
+        let cY = point_mul(&Y_i, &self.lambda(index).mul(&self.c));
+
+        let left = generator_mul(z).map(Point::from);
+        let right = jacobian_point_add(R_i.as_ref(), cY.as_ref()).map(Point::from);
+        left == right
+    }
+
+    /// Sums the signature shares into a BIP340 signature for `x_only_pub_key`
+    pub fn aggregate(&self, shares: &[ScalarN]) -> Signature {
+        let mut s = ScalarN::new(Integer::new());
+        for share in shares.iter() {
+            s = s.add(share.clone());
+        }
+        Signature::new(self.R.x.clone(), s)
+    }
+
+    pub fn x_only_pub_key(&self) -> NormalizedPoint {
+        NormalizedPoint::from(&self.group_pub_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+    use schnorr_verify;
+
+    fn sign_with(key_shares: &[&KeyShare], msg: &[u8]) -> Signature {
+        let mut rng = thread_rng();
+        let (nonces, commitments): (Vec<SigningNonces>, Vec<NonceCommitment>) =
+            key_shares.iter().map(|key_share| commit(&mut rng, key_share)).unzip();
+        let session = Session::new(&key_shares[0].group_pub_key, &commitments, msg).unwrap();
+        let mut shares = Vec::new();
+        for (key_share, nonces) in key_shares.iter().zip(nonces) {
+            let z = session.sign(nonces, key_share).unwrap();
+            assert!(session.verify_share(key_share.index, &z, &key_share.pub_share));
+            assert!(!session.verify_share(key_share.index, &z.clone().add(scalar(1)), &key_share.pub_share));
+            shares.push(z);
+        }
+        session.aggregate(&shares)
+    }
+
+    fn check_subsets(key_shares: &[KeyShare], subsets: &[&[usize]]) {
+        let x_only_pub_key = NormalizedPoint::from(&key_shares[0].group_pub_key);
+        for subset in subsets.iter() {
+            let signers: Vec<&KeyShare> = subset.iter().map(|i| &key_shares[*i]).collect();
+            let msg = thread_rng().gen::<[u8; 32]>();
+            let signature = sign_with(&signers, &msg);
+            assert!(schnorr_verify(&msg, &x_only_pub_key, &signature), "{:?}", subset);
+        }
+    }
+
+    #[test]
+    fn test_lagrange() {
+        let mut rng = thread_rng();
+        let constant_term = rng.gen();
+        let coefficients = random_coefficients(&mut rng, constant_term, 3);
+        let indices = [2u32, 4, 5];
+        let mut secret = ScalarN::new(Integer::new());
+        for index in indices.iter() {
+            let share = evaluate(&coefficients, *index);
+            secret = secret.add(lagrange_coefficient(*index, &indices).unwrap().mul(&share));
+        }
+        assert_eq!(secret, coefficients[0]);
+        assert_eq!(Err(Error::DuplicateIndex { index: 4 }), lagrange_coefficient(2, &[2, 4, 4]));
+        assert_eq!(Err(Error::InvalidIndex), lagrange_coefficient(2, &[0, 2, 4]));
+    }
+
+    #[test]
+    fn test_trusted_dealer_2_of_3() {
+        let mut rng = thread_rng();
+        let sec_key = rng.gen::<ScalarN>();
+        let (commitment, key_shares) = trusted_dealer_keygen(&mut rng, &sec_key, 2, 3).unwrap();
+        assert_eq!(commitment.group_pub_key(), &Point::from(generator_mul(&sec_key).unwrap()));
+        for key_share in key_shares.iter() {
+            assert!(commitment.verify_share(key_share.index, &key_share.sec_share));
+            assert_eq!(Some(key_share.pub_share.clone()), commitment.pub_share(key_share.index));
+        }
+        assert!(!commitment.verify_share(1, &key_shares[1].sec_share));
+        check_subsets(&key_shares, &[&[0, 1], &[0, 2], &[1, 2], &[2, 0], &[0, 1, 2]]);
+    }
+
+    #[test]
+    fn test_trusted_dealer_3_of_5() {
+        let mut rng = thread_rng();
+        let sec_key = rng.gen();
+        let (_, key_shares) = trusted_dealer_keygen(&mut rng, &sec_key, 3, 5).unwrap();
+        check_subsets(&key_shares, &[&[0, 1, 2], &[4, 2, 0], &[1, 3, 4], &[0, 1, 2, 3, 4]]);
+
+        // below the threshold the signature is not valid
+        let msg = [1u8; 32];
+        let signature = sign_with(&[&key_shares[0], &key_shares[1]], &msg);
+        let x_only_pub_key = NormalizedPoint::from(&key_shares[0].group_pub_key);
+        assert!(!schnorr_verify(&msg, &x_only_pub_key, &signature));
+    }
+
+    #[test]
+    fn test_dkg_3_of_5() {
+        let mut rng = thread_rng();
+        let (secrets, packages): (Vec<DkgSecret>, Vec<DkgPackage>) =
+            (1..=5).map(|index| dkg_round1(&mut rng, index, 3).unwrap()).unzip();
+        assert!(packages.iter().all(DkgPackage::verify_proof));
+
+        let mut key_shares = Vec::new();
+        for secret in secrets.iter() {
+            let (others, shares): (Vec<DkgPackage>, Vec<ScalarN>) = secrets
+                .iter()
+                .zip(packages.iter())
+                .filter(|(other, _)| other.index != secret.index)
+                .map(|(other, package)| (package.clone(), other.share_for(secret.index).unwrap()))
+                .unzip();
+            let key_share = secret.finalize(&others, &shares).unwrap();
+            assert_eq!(Some(key_share.pub_share.clone()), dkg_pub_share(&packages, key_share.index));
+            key_shares.push(key_share);
+        }
+        assert!(key_shares.iter().all(|el| el.group_pub_key == key_shares[0].group_pub_key));
+        check_subsets(&key_shares, &[&[0, 1, 2], &[2, 3, 4], &[4, 0, 3]]);
+
+        let others = packages[1..].to_vec();
+        let mut shares: Vec<ScalarN> = secrets[1..].iter().map(|el| el.share_for(1).unwrap()).collect();
+        shares[2] = shares[2].clone().add(scalar(1));
+        assert_eq!(Err(Error::InvalidShare { index: 4 }), secrets[0].finalize(&others, &shares).map(|_| ()));
+        let mut others = others;
+        others[0].proof.mu = others[0].proof.mu.clone().add(scalar(1));
+        assert_eq!(
+            Err(Error::InvalidProofOfKnowledge { index: 2 }),
+            secrets[0].finalize(&others, &shares).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn test_unknown_signer() {
+        let mut rng = thread_rng();
+        let sec_key = rng.gen();
+        let (_, key_shares) = trusted_dealer_keygen(&mut rng, &sec_key, 2, 3).unwrap();
+        let (nonces, commitment) = commit(&mut rng, &key_shares[0]);
+        let (_, other) = commit(&mut rng, &key_shares[1]);
+        let session = Session::new(&key_shares[0].group_pub_key, &[commitment, other], &[]).unwrap();
+        assert_eq!(Err(Error::UnknownSigner), session.sign(nonces, &key_shares[2]).map(|_| ()));
+    }
+
+    #[test]
+    fn test_invalid_indices() {
+        let mut rng = thread_rng();
+        assert_eq!(Err(Error::InvalidIndex), dkg_round1(&mut rng, 0, 2).map(|_| ()));
+        assert_eq!(Err(Error::InvalidThreshold { threshold: 0 }), dkg_round1(&mut rng, 1, 0).map(|_| ()));
+        let sec_key = rng.gen();
+        assert_eq!(
+            Err(Error::InvalidThreshold { threshold: 4 }),
+            trusted_dealer_keygen(&mut rng, &sec_key, 4, 3).map(|_| ())
+        );
+
+        let (secrets, packages): (Vec<DkgSecret>, Vec<DkgPackage>) =
+            (1..=3).map(|index| dkg_round1(&mut rng, index, 2).unwrap()).unzip();
+        // index 0 would receive the constant term, the secret of the participant
+        assert_eq!(Err(Error::InvalidIndex), secrets[0].share_for(0));
+        let shares: Vec<ScalarN> = secrets[1..].iter().map(|el| el.share_for(1).unwrap()).collect();
+
+        let mut zero = packages[1..].to_vec();
+        zero[0].index = 0;
+        assert_eq!(Err(Error::InvalidIndex), secrets[0].finalize(&zero, &shares).map(|_| ()));
+        let duplicate = vec![packages[1].clone(), packages[1].clone()];
+        assert_eq!(
+            Err(Error::DuplicateIndex { index: 2 }),
+            secrets[0].finalize(&duplicate, &shares).map(|_| ())
+        );
+        let own = vec![packages[0].clone(), packages[2].clone()];
+        assert_eq!(Err(Error::DuplicateIndex { index: 1 }), secrets[0].finalize(&own, &shares).map(|_| ()));
+        assert_eq!(
+            Err(Error::InvalidLength { expected: 2, found: 1 }),
+            secrets[0].finalize(&packages[1..], &shares[1..]).map(|_| ())
+        );
+
+        let (_, key_shares) = trusted_dealer_keygen(&mut rng, &sec_key, 2, 3).unwrap();
+        let (_, commitment) = commit(&mut rng, &key_shares[0]);
+        let (_, mut other) = commit(&mut rng, &key_shares[1]);
+        let group_pub_key = &key_shares[0].group_pub_key;
+        let duplicate = [commitment.clone(), commitment.clone()];
+        assert_eq!(
+            Err(Error::DuplicateIndex { index: 1 }),
+            Session::new(group_pub_key, &duplicate, &[]).map(|_| ())
+        );
+        other.index = 0;
+        assert_eq!(Err(Error::InvalidIndex), Session::new(group_pub_key, &[commitment, other], &[]).map(|_| ()));
+    }
+
+    #[test]
+    fn test_threshold_mismatch() {
+        let mut rng = thread_rng();
+        let (secret, _) = dkg_round1(&mut rng, 1, 3).unwrap();
+        let (other, package) = dkg_round1(&mut rng, 2, 2).unwrap();
+        let share = other.share_for(1).unwrap();
+        assert_eq!(
+            Err(Error::ThresholdMismatch { index: 2, expected: 3, found: 2 }),
+            secret.finalize(&[package], &[share]).map(|_| ())
+        );
+    }
+}
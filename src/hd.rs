@@ -0,0 +1,575 @@
+use backend::native::{JacobianPoint, Point};
+use crypto::digest::Digest;
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::ripemd160::Ripemd160;
+use crypto::sha2::Sha512;
+use error::{check_length, Error};
+use point::{generator_mul, jacobian_point_add};
+use scalar::sha256;
+use scalar::ScalarN;
+use std::ops::Add;
+use util::base58;
+
+// BIP32 hierarchical deterministic keys https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki
+// A child key is parent + I_L (or parent point + I_L*G) where I = HMAC-SHA512(chain code, data),
+// data is the parent secret key for hardened children and the parent public key otherwise,
+// I_R is the chain code of the child. Only mainnet xprv/xpub are serialized.
+
+/// Indexes from HARDENED on derive hardened children, written with ' or h in paths
+pub const HARDENED: u32 = 0x8000_0000;
+
+const XPRV_VERSION: [u8; 4] = [0x04, 0x88, 0xAD, 0xE4];
+const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xB2, 0x1E];
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ExtendedPrivKey {
+    pub depth: u8,
+    pub parent_fingerprint: [u8; 4],
+    pub child_number: u32,
+    pub chain_code: [u8; 32],
+    pub sec_key: ScalarN,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ExtendedPubKey {
+    pub depth: u8,
+    pub parent_fingerprint: [u8; 4],
+    pub child_number: u32,
+    pub chain_code: [u8; 32],
+    pub pub_key: Point,
+}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> ([u8; 32], [u8; 32]) {
+    let mut hmac = Hmac::new(Sha512::new(), key);
+    hmac.input(data);
+    let result = hmac.result();
+    let code = result.code();
+    let mut left = [0u8; 32];
+    let mut right = [0u8; 32];
+    left.copy_from_slice(&code[..32]);
+    right.copy_from_slice(&code[32..]);
+    (left, right)
+}
+
+fn fingerprint(pub_key: &Point) -> [u8; 4] {
+    let mut hasher = Ripemd160::new();
+    hasher.input(&sha256(&pub_key.as_compressed_bytes()));
+    let mut hash = [0u8; 20];
+    hasher.result(&mut hash);
+    let mut result = [0u8; 4];
+    result.copy_from_slice(&hash[..4]);
+    result
+}
+
+/// Parses paths like "m/86'/0'/0'/0/1", hardened indexes are marked with ' or h
+pub fn parse_path(path: &str) -> Result<Vec<u32>, Error> {
+    let mut parts = path.split('/');
+    if parts.next() != Some("m") {
+        return Err(Error::InvalidPath);
+    }
+    parts
+        .map(|part| {
+            let (number, hardened) =
+                if part.ends_with('\'') || part.ends_with('h') || part.ends_with('H') {
+                    (&part[..part.len() - 1], true)
+                } else {
+                    (part, false)
+                };
+            // reject signs and spaces accepted by parse
+            if number.is_empty() || !number.bytes().all(|el| el.is_ascii_digit()) {
+                return Err(Error::InvalidPath);
+            }
+            let index = number.parse::<u32>().map_err(|_| Error::InvalidPath)?;
+            if index >= HARDENED {
+                return Err(Error::InvalidPath);
+            }
+            Ok(if hardened { index + HARDENED } else { index })
+        })
+        .collect()
+}
+
+// version || depth || parent fingerprint || child number || chain code || key
+fn serialize(
+    version: &[u8; 4],
+    depth: u8,
+    parent_fingerprint: &[u8; 4],
+    child_number: u32,
+    chain_code: &[u8; 32],
+    key: &[u8; 33],
+) -> String {
+    let mut vec = Vec::with_capacity(78);
+    vec.extend(&version[..]);
+    vec.push(depth);
+    vec.extend(&parent_fingerprint[..]);
+    vec.extend(&child_number.to_be_bytes()[..]);
+    vec.extend(&chain_code[..]);
+    vec.extend(&key[..]);
+    base58::encode_check(&vec)
+}
+
+// checks what does not depend on the key type, returns the bytes
+fn deserialize(string: &str, version: &[u8; 4]) -> Result<Vec<u8>, Error> {
+    let bytes = base58::decode_check(string)?;
+    check_length(&bytes, 78)?;
+    if bytes[..4] != version[..] {
+        return Err(Error::InvalidExtendedKey);
+    }
+    let is_master = bytes[4] == 0;
+    if is_master && (bytes[5..13].iter().any(|el| *el != 0)) {
+        // a master key has no parent and child number 0
+        return Err(Error::InvalidExtendedKey);
+    }
+    Ok(bytes)
+}
+
+fn header(bytes: &[u8]) -> (u8, [u8; 4], u32, [u8; 32]) {
+    let mut parent_fingerprint = [0u8; 4];
+    parent_fingerprint.copy_from_slice(&bytes[5..9]);
+    let mut child_number = [0u8; 4];
+    child_number.copy_from_slice(&bytes[9..13]);
+    let mut chain_code = [0u8; 32];
+    chain_code.copy_from_slice(&bytes[13..45]);
+    (
+        bytes[4],
+        parent_fingerprint,
+        u32::from_be_bytes(child_number),
+        chain_code,
+    )
+}
+
+impl ExtendedPrivKey {
+    /// Master key from a seed of 16 to 64 bytes
+    pub fn new_master(seed: &[u8]) -> Result<Self, Error> {
+        if seed.len() < 16 || seed.len() > 64 {
+            return Err(Error::InvalidLength {
+                expected: if seed.len() < 16 { 16 } else { 64 },
+                found: seed.len(),
+            });
+        }
+        let (il, ir) = hmac_sha512(b"Bitcoin seed", seed);
+        let sec_key = ScalarN::try_from_bytes(&il)?;
+        if sec_key.is_zero() {
+            return Err(Error::ScalarOutOfRange);
+        }
+        Ok(ExtendedPrivKey {
+            depth: 0,
+            parent_fingerprint: [0u8; 4],
+            child_number: 0,
+            chain_code: ir,
+            sec_key,
+        })
+    }
+
+    pub fn pub_key(&self) -> Point {
+        Point::from(generator_mul(&self.sec_key).unwrap())
+    }
+
+    pub fn fingerprint(&self) -> [u8; 4] {
+        fingerprint(&self.pub_key())
+    }
+
+    /// Private parent key to private child key, fails with negligible probability
+    pub fn derive_child(&self, index: u32) -> Result<Self, Error> {
+        let mut data = Vec::with_capacity(37);
+        if index >= HARDENED {
+            data.push(0u8);
+            data.extend(&self.sec_key.to_32_bytes()[..]);
+        } else {
+            data.extend(&self.pub_key().as_compressed_bytes()[..]);
+        }
+        data.extend(&index.to_be_bytes()[..]);
+        let (il, ir) = hmac_sha512(&self.chain_code, &data);
+        let sec_key = ScalarN::try_from_bytes(&il)?.add(self.sec_key.clone());
+        if sec_key.is_zero() {
+            return Err(Error::ScalarOutOfRange);
+        }
+        Ok(ExtendedPrivKey {
+            depth: self.depth.checked_add(1).ok_or(Error::InvalidExtendedKey)?,
+            parent_fingerprint: self.fingerprint(),
+            child_number: index,
+            chain_code: ir,
+            sec_key,
+        })
+    }
+
+    pub fn derive_path(&self, path: &[u32]) -> Result<Self, Error> {
+        let mut key = self.clone();
+        for index in path.iter() {
+            key = key.derive_child(*index)?;
+        }
+        Ok(key)
+    }
+
+    pub fn to_extended_pub_key(&self) -> ExtendedPubKey {
+        ExtendedPubKey {
+            depth: self.depth,
+            parent_fingerprint: self.parent_fingerprint,
+            child_number: self.child_number,
+            chain_code: self.chain_code,
+            pub_key: self.pub_key(),
+        }
+    }
+
+    pub fn to_base58(&self) -> String {
+        let mut key = [0u8; 33];
+        key[1..].copy_from_slice(&self.sec_key.to_32_bytes()[..]);
+        serialize(
+            &XPRV_VERSION,
+            self.depth,
+            &self.parent_fingerprint,
+            self.child_number,
+            &self.chain_code,
+            &key,
+        )
+    }
+
+    pub fn from_base58(string: &str) -> Result<Self, Error> {
+        let bytes = deserialize(string, &XPRV_VERSION)?;
+        if bytes[45] != 0 {
+            return Err(Error::InvalidPrefix { found: bytes[45] });
+        }
+        let sec_key = ScalarN::try_from_bytes(&bytes[46..])?;
+        if sec_key.is_zero() {
+            return Err(Error::ScalarOutOfRange);
+        }
+        let (depth, parent_fingerprint, child_number, chain_code) = header(&bytes);
+        Ok(ExtendedPrivKey {
+            depth,
+            parent_fingerprint,
+            child_number,
+            chain_code,
+            sec_key,
+        })
+    }
+}
+
+impl ExtendedPubKey {
+    pub fn fingerprint(&self) -> [u8; 4] {
+        fingerprint(&self.pub_key)
+    }
+
+    /// Public parent key to public child key, only for non hardened indexes
+    pub fn derive_child(&self, index: u32) -> Result<Self, Error> {
+        if index >= HARDENED {
+            return Err(Error::HardenedFromPublic);
+        }
+        let mut data = self.pub_key.as_compressed_bytes().to_vec();
+        data.extend(&index.to_be_bytes()[..]);
+        let (il, ir) = hmac_sha512(&self.chain_code, &data);
+        // BIP32 says to skip to the next index when I_L is not lower than n
+        let il = ScalarN::try_from_bytes(&il)?;
+        let pub_key = jacobian_point_add(
+            generator_mul(&il).as_ref(),
+            Some(&JacobianPoint::from(self.pub_key.clone())),
+        )
+        .ok_or(Error::PointAtInfinity)?;
+        Ok(ExtendedPubKey {
+            depth: self.depth.checked_add(1).ok_or(Error::InvalidExtendedKey)?,
+            parent_fingerprint: self.fingerprint(),
+            child_number: index,
+            chain_code: ir,
+            pub_key: Point::from(pub_key),
+        })
+    }
+
+    pub fn derive_path(&self, path: &[u32]) -> Result<Self, Error> {
+        let mut key = self.clone();
+        for index in path.iter() {
+            key = key.derive_child(*index)?;
+        }
+        Ok(key)
+    }
+
+    pub fn to_base58(&self) -> String {
+        serialize(
+            &XPUB_VERSION,
+            self.depth,
+            &self.parent_fingerprint,
+            self.child_number,
+            &self.chain_code,
+            &self.pub_key.as_compressed_bytes(),
+        )
+    }
+
+    pub fn from_base58(string: &str) -> Result<Self, Error> {
+        let bytes = deserialize(string, &XPUB_VERSION)?;
+        let pub_key = Point::from_compressed_bytes(&bytes[45..])?;
+        let (depth, parent_fingerprint, child_number, chain_code) = header(&bytes);
+        Ok(ExtendedPubKey {
+            depth,
+            parent_fingerprint,
+            child_number,
+            chain_code,
+            pub_key,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_encoding::HEXLOWER;
+
+    // (path, xprv, xpub) from https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki#test-vectors
+    fn check_vector(seed: &str, chain: &[(&str, &str, &str)]) {
+        let master =
+            ExtendedPrivKey::new_master(&HEXLOWER.decode(seed.as_bytes()).unwrap()).unwrap();
+        for (path, xprv, xpub) in chain.iter() {
+            let key = master.derive_path(&parse_path(path).unwrap()).unwrap();
+            assert_eq!(key.to_base58(), *xprv);
+            assert_eq!(key.to_extended_pub_key().to_base58(), *xpub);
+            assert_eq!(Ok(key.clone()), ExtendedPrivKey::from_base58(xprv));
+            assert_eq!(
+                Ok(key.to_extended_pub_key()),
+                ExtendedPubKey::from_base58(xpub)
+            );
+        }
+    }
+
+    #[test]
+    fn test_vector_1() {
+        check_vector(
+            "000102030405060708090a0b0c0d0e0f",
+            &[
+                (
+                    "m",
+                    "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi",
+                    "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8",
+                ),
+                (
+                    "m/0h",
+                    "xprv9uHRZZhk6KAJC1avXpDAp4MDc3sQKNxDiPvvkX8Br5ngLNv1TxvUxt4cV1rGL5hj6KCesnDYUhd7oWgT11eZG7XnxHrnYeSvkzY7d2bhkJ7",
+                    "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw",
+                ),
+                (
+                    "m/0h/1",
+                    "xprv9wTYmMFdV23N2TdNG573QoEsfRrWKQgWeibmLntzniatZvR9BmLnvSxqu53Kw1UmYPxLgboyZQaXwTCg8MSY3H2EU4pWcQDnRnrVA1xe8fs",
+                    "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ",
+                ),
+                (
+                    "m/0h/1/2h",
+                    "xprv9z4pot5VBttmtdRTWfWQmoH1taj2axGVzFqSb8C9xaxKymcFzXBDptWmT7FwuEzG3ryjH4ktypQSAewRiNMjANTtpgP4mLTj34bhnZX7UiM",
+                    "xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5",
+                ),
+                (
+                    "m/0h/1/2h/2",
+                    "xprvA2JDeKCSNNZky6uBCviVfJSKyQ1mDYahRjijr5idH2WwLsEd4Hsb2Tyh8RfQMuPh7f7RtyzTtdrbdqqsunu5Mm3wDvUAKRHSC34sJ7in334",
+                    "xpub6FHa3pjLCk84BayeJxFW2SP4XRrFd1JYnxeLeU8EqN3vDfZmbqBqaGJAyiLjTAwm6ZLRQUMv1ZACTj37sR62cfN7fe5JnJ7dh8zL4fiyLHV",
+                ),
+                (
+                    "m/0h/1/2h/2/1000000000",
+                    "xprvA41z7zogVVwxVSgdKUHDy1SKmdb533PjDz7J6N6mV6uS3ze1ai8FHa8kmHScGpWmj4WggLyQjgPie1rFSruoUihUZREPSL39UNdE3BBDu76",
+                    "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_vector_2() {
+        check_vector(
+            "fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a29f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542",
+            &[
+                (
+                    "m",
+                    "xprv9s21ZrQH143K31xYSDQpPDxsXRTUcvj2iNHm5NUtrGiGG5e2DtALGdso3pGz6ssrdK4PFmM8NSpSBHNqPqm55Qn3LqFtT2emdEXVYsCzC2U",
+                    "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB",
+                ),
+                (
+                    "m/0",
+                    "xprv9vHkqa6EV4sPZHYqZznhT2NPtPCjKuDKGY38FBWLvgaDx45zo9WQRUT3dKYnjwih2yJD9mkrocEZXo1ex8G81dwSM1fwqWpWkeS3v86pgKt",
+                    "xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH",
+                ),
+                (
+                    "m/0/2147483647h",
+                    "xprv9wSp6B7kry3Vj9m1zSnLvN3xH8RdsPP1Mh7fAaR7aRLcQMKTR2vidYEeEg2mUCTAwCd6vnxVrcjfy2kRgVsFawNzmjuHc2YmYRmagcEPdU9",
+                    "xpub6ASAVgeehLbnwdqV6UKMHVzgqAG8Gr6riv3Fxxpj8ksbH9ebxaEyBLZ85ySDhKiLDBrQSARLq1uNRts8RuJiHjaDMBU4Zn9h8LZNnBC5y4a",
+                ),
+                (
+                    "m/0/2147483647h/1",
+                    "xprv9zFnWC6h2cLgpmSA46vutJzBcfJ8yaJGg8cX1e5StJh45BBciYTRXSd25UEPVuesF9yog62tGAQtHjXajPPdbRCHuWS6T8XA2ECKADdw4Ef",
+                    "xpub6DF8uhdarytz3FWdA8TvFSvvAh8dP3283MY7p2V4SeE2wyWmG5mg5EwVvmdMVCQcoNJxGoWaU9DCWh89LojfZ537wTfunKau47EL2dhHKon",
+                ),
+                (
+                    "m/0/2147483647h/1/2147483646h",
+                    "xprvA1RpRA33e1JQ7ifknakTFpgNXPmW2YvmhqLQYMmrj4xJXXWYpDPS3xz7iAxn8L39njGVyuoseXzU6rcxFLJ8HFsTjSyQbLYnMpCqE2VbFWc",
+                    "xpub6ERApfZwUNrhLCkDtcHTcxd75RbzS1ed54G1LkBUHQVHQKqhMkhgbmJbZRkrgZw4koxb5JaHWkY4ALHY2grBGRjaDMzQLcgJvLJuZZvRcEL",
+                ),
+                (
+                    "m/0/2147483647h/1/2147483646h/2",
+                    "xprvA2nrNbFZABcdryreWet9Ea4LvTJcGsqrMzxHx98MMrotbir7yrKCEXw7nadnHM8Dq38EGfSh6dqA9QWTyefMLEcBYJUuekgW4BYPJcr9E7j",
+                    "xpub6FnCn6nSzZAw5Tw7cgR9bi15UV96gLZhjDstkXXxvCLsUXBGXPdSnLFbdpq8p9HmGsApME5hQTZ3emM2rnY5agb9rXpVGyy3bdW6EEgAtqt",
+                ),
+            ],
+        );
+    }
+
+    // vectors 3 and 4 check the retention of leading zeros
+    #[test]
+    fn test_vector_3_and_4() {
+        check_vector(
+            "4b381541583be4423346c643850da4b320e46a87ae3d2a4e6da11eba819cd4acba45d239319ac14f863b8d5ab5a0d0c64d2e8a1e7d1457df2e5a3c51c73235be",
+            &[
+                (
+                    "m",
+                    "xprv9s21ZrQH143K25QhxbucbDDuQ4naNntJRi4KUfWT7xo4EKsHt2QJDu7KXp1A3u7Bi1j8ph3EGsZ9Xvz9dGuVrtHHs7pXeTzjuxBrCmmhgC6",
+                    "xpub661MyMwAqRbcEZVB4dScxMAdx6d4nFc9nvyvH3v4gJL378CSRZiYmhRoP7mBy6gSPSCYk6SzXPTf3ND1cZAceL7SfJ1Z3GC8vBgp2epUt13",
+                ),
+                (
+                    "m/0h",
+                    "xprv9uPDJpEQgRQfDcW7BkF7eTya6RPxXeJCqCJGHuCJ4GiRVLzkTXBAJMu2qaMWPrS7AANYqdq6vcBcBUdJCVVFceUvJFjaPdGZ2y9WACViL4L",
+                    "xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y",
+                ),
+            ],
+        );
+        check_vector(
+            "3ddd5602285899a946114506157c7997e5444528f3003f6134712147db19b678",
+            &[
+                (
+                    "m",
+                    "xprv9s21ZrQH143K48vGoLGRPxgo2JNkJ3J3fqkirQC2zVdk5Dgd5w14S7fRDyHH4dWNHUgkvsvNDCkvAwcSHNAQwhwgNMgZhLtQC63zxwhQmRv",
+                    "xpub661MyMwAqRbcGczjuMoRm6dXaLDEhW1u34gKenbeYqAix21mdUKJyuyu5F1rzYGVxyL6tmgBUAEPrEz92mBXjByMRiJdba9wpnN37RLLAXa",
+                ),
+                (
+                    "m/0'",
+                    "xprv9vB7xEWwNp9kh1wQRfCCQMnZUEG21LpbR9NPCNN1dwhiZkjjeGRnaALmPXCX7SgjFTiCTT6bXes17boXtjq3xLpcDjzEuGLQBM5ohqkao9G",
+                    "xpub69AUMk3qDBi3uW1sXgjCmVjJ2G6WQoYSnNHyzkmdCHEhSZ4tBok37xfFEqHd2AddP56Tqp4o56AePAgCjYdvpW2PU2jbUPFKsav5ut6Ch1m",
+                ),
+                (
+                    "m/0'/1'",
+                    "xprv9xJocDuwtYCMNAo3Zw76WENQeAS6WGXQ55RCy7tDJ8oALr4FWkuVoHJeHVAcAqiZLE7Je3vZJHxspZdFHfnBEjHqU5hG1Jaj32dVoS6XLT1",
+                    "xpub6BJA1jSqiukeaesWfxe6sNK9CCGaujFFSJLomWHprUL9DePQ4JDkM5d88n49sMGJxrhpjazuXYWdMf17C9T5XnxkopaeS7jGk1GyyVziaMt",
+                ),
+            ],
+        );
+    }
+
+    // invalid keys of test vector 5
+    #[test]
+    fn test_vector_5() {
+        // pubkey version / prvkey mismatch
+        assert_eq!(
+            Err(Error::InvalidPrefix { found: 0x00 }),
+            ExtendedPubKey::from_base58("xpub661MyMwAqRbcEYS8w7XLSVeEsBXy79zSzH1J8vCdxAZningWLdN3zgtU6LBpB85b3D2yc8sfvZU521AAwdZafEz7mnzBBsz4wKY5fTtTQBm")
+        );
+        // prvkey version / pubkey mismatch
+        assert_eq!(
+            Err(Error::InvalidPrefix { found: 0x03 }),
+            ExtendedPrivKey::from_base58("xprv9s21ZrQH143K24Mfq5zL5MhWK9hUhhGbd45hLXo2Pq2oqzMMo63oStZzFGTQQD3dC4H2D5GBj7vWvSQaaBv5cxi9gafk7NF3pnBju6dwKvH")
+        );
+        // invalid pubkey prefix 04
+        assert_eq!(
+            Err(Error::InvalidPrefix { found: 0x04 }),
+            ExtendedPubKey::from_base58("xpub661MyMwAqRbcEYS8w7XLSVeEsBXy79zSzH1J8vCdxAZningWLdN3zgtU6Txnt3siSujt9RCVYsx4qHZGc62TG4McvMGcAUjeuwZdduYEvFn")
+        );
+        // invalid prvkey prefix 04
+        assert_eq!(
+            Err(Error::InvalidPrefix { found: 0x04 }),
+            ExtendedPrivKey::from_base58("xprv9s21ZrQH143K24Mfq5zL5MhWK9hUhhGbd45hLXo2Pq2oqzMMo63oStZzFGpWnsj83BHtEy5Zt8CcDr1UiRXuWCmTQLxEK9vbz5gPstX92JQ")
+        );
+        // invalid pubkey prefix 01
+        assert_eq!(
+            Err(Error::InvalidPrefix { found: 0x01 }),
+            ExtendedPubKey::from_base58("xpub661MyMwAqRbcEYS8w7XLSVeEsBXy79zSzH1J8vCdxAZningWLdN3zgtU6N8ZMMXctdiCjxTNq964yKkwrkBJJwpzZS4HS2fxvyYUA4q2Xe4")
+        );
+        // invalid prvkey prefix 01
+        assert_eq!(
+            Err(Error::InvalidPrefix { found: 0x01 }),
+            ExtendedPrivKey::from_base58("xprv9s21ZrQH143K24Mfq5zL5MhWK9hUhhGbd45hLXo2Pq2oqzMMo63oStZzFAzHGBP2UuGCqWLTAPLcMtD9y5gkZ6Eq3Rjuahrv17fEQ3Qen6J")
+        );
+        // zero depth with non-zero parent fingerprint
+        assert_eq!(
+            Err(Error::InvalidExtendedKey),
+            ExtendedPrivKey::from_base58("xprv9s2SPatNQ9Vc6GTbVMFPFo7jsaZySyzk7L8n2uqKXJen3KUmvQNTuLh3fhZMBoG3G4ZW1N2kZuHEPY53qmbZzCHshoQnNf4GvELZfqTUrcv")
+        );
+        assert_eq!(
+            Err(Error::InvalidExtendedKey),
+            ExtendedPubKey::from_base58("xpub661no6RGEX3uJkY4bNnPcw4URcQTrSibUZ4NqJEw5eBkv7ovTwgiT91XX27VbEXGENhYRCf7hyEbWrR3FewATdCEebj6znwMfQkhRYHRLpJ")
+        );
+        // zero depth with non-zero index
+        assert_eq!(
+            Err(Error::InvalidExtendedKey),
+            ExtendedPrivKey::from_base58("xprv9s21ZrQH4r4TsiLvyLXqM9P7k1K3EYhA1kkD6xuquB5i39AU8KF42acDyL3qsDbU9NmZn6MsGSUYZEsuoePmjzsB3eFKSUEh3Gu1N3cqVUN")
+        );
+        assert_eq!(
+            Err(Error::InvalidExtendedKey),
+            ExtendedPubKey::from_base58("xpub661MyMwAuDcm6CRQ5N4qiHKrJ39Xe1R1NyfouMKTTWcguwVcfrZJaNvhpebzGerh7gucBvzEQWRugZDuDXjNDRmXzSZe4c7mnTK97pTvGS8")
+        );
+        // unknown extended key version
+        assert_eq!(
+            Err(Error::InvalidExtendedKey),
+            ExtendedPrivKey::from_base58("DMwo58pR1QLEFihHiXPVykYB6fJmsTeHvyTp7hRThAtCX8CvYzgPcn8XnmdfHGMQzT7ayAmfo4z3gY5KfbrZWZ6St24UVf2Qgo6oujFktLHdHY4")
+        );
+        assert_eq!(
+            Err(Error::InvalidExtendedKey),
+            ExtendedPubKey::from_base58("DMwo58pR1QLEFihHiXPVykYB6fJmsTeHvyTp7hRThAtCX8CvYzgPcn8XnmdfHPmHJiEDXkTiJTVV9rHEBUem2mwVbbNfvT2MTcAqj3nesx8uBf9")
+        );
+        // private key 0 not in 1..n-1
+        assert_eq!(
+            Err(Error::ScalarOutOfRange),
+            ExtendedPrivKey::from_base58("xprv9s21ZrQH143K24Mfq5zL5MhWK9hUhhGbd45hLXo2Pq2oqzMMo63oStZzF93Y5wvzdUayhgkkFoicQZcP3y52uPPxFnfoLZB21Teqt1VvEHx")
+        );
+        // private key n not in 1..n-1
+        assert_eq!(
+            Err(Error::ScalarOutOfRange),
+            ExtendedPrivKey::from_base58("xprv9s21ZrQH143K24Mfq5zL5MhWK9hUhhGbd45hLXo2Pq2oqzMMo63oStZzFAzHGBP2UuGCqWLTAPLcMtD9y5gkZ6Eq3Rjuahrv17fENZ3QzxW")
+        );
+        // invalid pubkey 020000000000000000000000000000000000000000000000000000000000000007
+        assert_eq!(
+            Err(Error::NotOnCurve),
+            ExtendedPubKey::from_base58("xpub661MyMwAqRbcEYS8w7XLSVeEsBXy79zSzH1J8vCdxAZningWLdN3zgtU6Q5JXayek4PRsn35jii4veMimro1xefsM58PgBMrvdYre8QyULY")
+        );
+        // invalid checksum
+        assert_eq!(
+            Err(Error::InvalidChecksum),
+            ExtendedPrivKey::from_base58("xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHL")
+        );
+    }
+
+    #[test]
+    fn test_public_derivation() {
+        let master = ExtendedPrivKey::new_master(&[7u8; 32]).unwrap();
+        let path = parse_path("m/86'/0'/0'").unwrap();
+        let account = master.derive_path(&path).unwrap();
+        let child = account.derive_path(&[0, 1]).unwrap();
+        assert_eq!(
+            Ok(child.to_extended_pub_key()),
+            account.to_extended_pub_key().derive_path(&[0, 1])
+        );
+        assert_eq!(
+            Err(Error::HardenedFromPublic),
+            account.to_extended_pub_key().derive_child(HARDENED)
+        );
+        assert_eq!(
+            child.parent_fingerprint,
+            account.derive_child(0).unwrap().fingerprint()
+        );
+    }
+
+    #[test]
+    fn test_parse_path() {
+        assert_eq!(Ok(vec![]), parse_path("m"));
+        assert_eq!(
+            Ok(vec![86 + HARDENED, HARDENED, 2 + HARDENED, 0, 1]),
+            parse_path("m/86'/0h/2H/0/1")
+        );
+        assert_eq!(Ok(vec![HARDENED - 1]), parse_path("m/2147483647"));
+        for path in [
+            "",
+            "m/",
+            "n/0",
+            "0/1",
+            "m/2147483648",
+            "m/-1",
+            "m/+1",
+            "m/1''",
+            "m/ 1",
+            "m//1",
+        ]
+        .iter()
+        {
+            assert_eq!(Err(Error::InvalidPath), parse_path(path), "{}", path);
+        }
+    }
+}
@@ -8,321 +8,316 @@ extern crate num_bigint;
 extern crate num_integer;
 extern crate num_traits;
 extern crate rand;
+#[cfg(feature = "parallel")]
+extern crate rayon;
 extern crate rug;
-
+#[cfg(test)]
+extern crate secp256k1;
+
+pub mod adaptor;
+pub mod backend;
+pub mod batch;
+pub mod commitment;
+pub mod conformance;
+pub mod constant_time;
 pub mod context;
+pub mod draft;
+pub mod ecdh;
+pub mod ecdsa;
+pub mod error;
+pub mod frost;
+pub mod hd;
+pub mod musig;
 pub mod old;
 pub mod point;
 pub mod scalar;
+pub mod taproot;
+pub mod taptree;
 pub mod util;
 
-use context::CONTEXT;
-use point::{generator_mul, jacobian_point_add};
+pub use batch::schnorr_batch_verify;
+
+use backend::native;
+use backend::{FieldOps, ScalarOps};
+use error::{check_length, Error, Reason};
+use point::{generator_mul, jacobian_point_add, jacobian_point_mul_wnaf};
 use point::{JacobianPoint, Point, NormalizedPoint};
-use rand::thread_rng;
-use rand::Rng;
-use rug::Integer;
-use scalar::concat_and_hash_BIPSchnorrDerive;
-use scalar::concat_and_hash_BIPSchnorr;
-use scalar::concat_and_hash;
+use scalar::concat_and_hash_BIP0340_challenge;
+use scalar::concat_and_tagged_hash;
+use scalar::sha256_BIP0340_aux;
 use scalar::ScalarN;
-use std::borrow::Borrow;
-use std::collections::BinaryHeap;
-use std::ops::{Add, Mul, Sub};
-use util::rug::integer_from_bytes;
 use util::signature::Signature;
-use util::term::Term;
 
 type Msg = [u8; 32];
 
-// https://github.com/sipa/bips/blob/bip-schnorr/bip-schnorr.mediawiki
+/// Revision of the Schnorr BIP followed by `schnorr_sign_variant` and `schnorr_verify_variant`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Variant {
+    /// October 2019 draft: square y for public key and R, "BIPSchnorr" tags, no aux randomness
+    Draft2019,
+    /// Final BIP340: even y for public key and R, "BIP0340/*" tags, 32 bytes of aux randomness
+    Bip340,
+}
+
+pub fn schnorr_sign_variant(
+    variant: Variant,
+    msg: &[u8],
+    sec_key: &ScalarN,
+    aux_rand: &[u8; 32],
+) -> Result<native::Signature, Error> {
+    match variant {
+        Variant::Draft2019 => {
+            // the 2019 draft signs only 32 bytes messages
+            check_length(msg, 32)?;
+            let mut msg32 = [0u8; 32];
+            msg32.copy_from_slice(msg);
+            Ok(draft::schnorr_sign(&msg32, sec_key))
+        }
+        Variant::Bip340 => Ok(schnorr_sign(msg, sec_key, aux_rand)),
+    }
+}
+
+pub fn schnorr_verify_variant(
+    variant: Variant,
+    msg: &[u8],
+    pub_key: &native::NormalizedPoint,
+    signature: &native::Signature,
+) -> bool {
+    match variant {
+        Variant::Draft2019 => {
+            if msg.len() != 32 {
+                return false;
+            }
+            let mut msg32 = [0u8; 32];
+            msg32.copy_from_slice(msg);
+            draft::schnorr_verify(&msg32, pub_key, signature)
+        }
+        Variant::Bip340 => schnorr_verify(msg, pub_key, signature),
+    }
+}
+
+// https://github.com/bitcoin/bips/blob/master/bip-0340.mediawiki
+// msg can be of any length, as allowed since the 2022-12 revision of BIP340
 
 #[allow(non_snake_case)]
-pub fn schnorr_sign(msg: &Msg, sec_key: &ScalarN) -> Signature {
-    //println!("schnorr_sign(msg, sec_key) with ({},{})", &HEXUPPER.encode(msg), &HEXUPPER.encode(&sec_key.to_32_bytes()));
-    let P_jacobian = generator_mul(&sec_key).unwrap();
+pub fn schnorr_sign<S: ScalarOps>(msg: &[u8], sec_key: &S, aux_rand: &[u8; 32]) -> Signature<S::Field, S> {
+    let P_jacobian = generator_mul(sec_key).unwrap();
     let P = Point::from(P_jacobian);
 
-    let sec_key_sq = if P.y.is_square() {
+    let sec_key_even = if P.has_even_y() {
         sec_key.clone()
     } else {
-        CONTEXT.n.clone().sub(sec_key)
+        sec_key.negate()
     };
 
-    let k0 = concat_and_hash_BIPSchnorrDerive(&sec_key_sq.to_32_bytes(), msg, &vec![]);
+    let mut t = sec_key_even.to_32_bytes();
+    for (t_i, aux_i) in t.iter_mut().zip(sha256_BIP0340_aux(aux_rand).iter()) {
+        *t_i ^= aux_i;
+    }
+
+    let k0: S = concat_and_tagged_hash(b"BIP0340/nonce", &t, &P.as_bytes(), msg);
     let R_jacobian = generator_mul(&k0).unwrap();
 
     let R = Point::from(R_jacobian);
-    let k = if R.y.is_square() {
+    let k = if R.has_even_y() {
         k0
     } else {
-        CONTEXT.n.clone().sub(&k0)
+        k0.negate()
     };
-    //println!("sign k={}", k);
 
-    let e = concat_and_hash_BIPSchnorr(&R.as_bytes(), &P.as_bytes(), msg);
-    //println!("sign e={}", e);
+    let e: S = concat_and_hash_BIP0340_challenge(&R.as_bytes(), &P.as_bytes(), msg);
 
-    let s = k.add(e.mul(&sec_key_sq));
+    let s = k.add(e.mul(&sec_key_even));
 
     Signature::new(R.x, s)
 }
 
-#[allow(non_snake_case)]
-pub fn schnorr_verify(msg: &Msg, pub_key: &NormalizedPoint, signature: &Signature) -> bool {
-    let pub_key: Point = pub_key.into();
-    //println!("schnorr_verify(msg, pub_key, signature) with ({},{},{})", &HEXUPPER.encode(&msg[..]), &HEXUPPER.encode(&pub_key.as_bytes()), &HEXUPPER.encode(&signature.as_bytes()));
-
-    if !pub_key.on_curve() {
-        return false;
-    }
-
-    let signature_bytes = signature.as_bytes();
-    let r = integer_from_bytes(&signature_bytes[..32]);
-    let s = integer_from_bytes(&signature_bytes[32..]);
-    if r >= CONTEXT.p.0 || s >= CONTEXT.n.0 {
-        return false;
-    }
-    let e = concat_and_hash_BIPSchnorr(&signature_bytes[..32], &pub_key.as_bytes()[..], msg);
-    //println!("e {}", e);
-
-    let a = generator_mul(&signature.s).unwrap();
-    let b = JacobianPoint::from(pub_key.to_owned()).mul(&CONTEXT.n.clone().sub(&e));
-    let R = jacobian_point_add(Some(&a), Some(&b));
-    if R.is_none() {
-        println!("3");
-
-        return false;
-    }
-    let R = R.unwrap();
-
-    let R = Point::from(R);
-    if !R.y.is_square() {
-        println!("4");
-        return false;
-    }
-
-    // x(P) ≠ r can be implemented as x ≠ z^2r mod p.
-    //let Rx = R.z.clone().mul(&R.z).mul(&signature.Rx);
-    if R.x != signature.Rx {
-        println!("5");
-        return false;
-    }
+pub fn schnorr_verify<F, S>(msg: &[u8], pub_key: &NormalizedPoint<F>, signature: &Signature<F, S>) -> bool
+where
+    F: FieldOps,
+    S: ScalarOps<Field = F>,
+{
+    check_signature(msg, pub_key, signature).is_ok()
+}
 
-    true
+/// Parses and verifies a BIP340 signature, the error tells which check failed
+pub fn verify_detailed(msg: &[u8], pub_key: &[u8], signature: &[u8]) -> Result<(), Error> {
+    let pub_key = NormalizedPoint::from_bytes(pub_key)?;
+    let signature = Signature::from_bytes(signature)?;
+    check_signature(msg, &pub_key, &signature)
 }
 
-// https://www.deadalnix.me/2017/02/17/schnorr-signatures-for-not-so-dummies/
 #[allow(non_snake_case)]
-pub fn schnorr_batch_verify(
-    messages: &Vec<Msg>,
-    pub_keys: &Vec<Point>,
-    signatures: &Vec<Signature>,
-) -> bool {
-    assert_eq!(messages.len(), pub_keys.len());
-    assert_eq!(messages.len(), signatures.len());
-    let mut R_vec = Vec::new();
-    let mut a_vec = Vec::new();
-    let mut e_vec = Vec::new();
-    let mut rng = thread_rng();
-    for i in 0..messages.len() {
-        let msg = &messages[i];
-        let P = &pub_keys[i];
-        let signature = &signatures[i];
-        if !P.on_curve() {
-            return false;
-        }
-        let e = concat_and_hash(&signature.Rx.to_32_bytes(), &P.as_bytes(), &msg[..]);
-        e_vec.push(e);
-        let c = signature
-            .Rx
-            .borrow()
-            .pow(&CONTEXT.three)
-            .add(&CONTEXT.seven);
-        let y = c.pow(&CONTEXT.p_add1_div4);
-        let y_pow2 = y.clone().mul(&y);
-        if y_pow2 != c {
-            return false;
-        }
-        R_vec.push(JacobianPoint::from(Point {
-            x: signature.Rx.clone(),
-            y,
-        }));
-        let a = if i == 0 {
-            ScalarN(Integer::from(1u32))
-        } else {
-            rng.gen::<ScalarN>()
-        };
-        a_vec.push(a);
-    }
+pub(crate) fn check_signature<F, S>(msg: &[u8], pub_key: &NormalizedPoint<F>, signature: &Signature<F, S>) -> Result<(), Error>
+where
+    F: FieldOps,
+    S: ScalarOps<Field = F>,
+{
+    let P = Point::lift_x(&pub_key.x).ok_or(Error::NotOnCurve)?;
+
+    // r < p and s < n are enforced when the signature is built
+    let e: S = concat_and_hash_BIP0340_challenge(&signature.Rx.to_32_bytes(), &P.as_bytes(), msg);
+
+    let a = generator_mul(&signature.s);
+    let b = jacobian_point_mul_wnaf(&JacobianPoint::from(P), &e.negate(), 5i8);
+    let R = match jacobian_point_add(a.as_ref(), b.as_ref()) {
+        Some(R) => Point::from(R),
+        None => return Err(Error::InvalidSignature { reason: Reason::RAtInfinity }),
+    };
 
-    //Fail if (s1 + a2s2 + ... + ausu)G ≠ R1 + a2R2 + ... + auRu + e1P1 + (a2e2)P2 + ... + (aueu)Pu
-    let mut coeff = ScalarN(Integer::new());
-    let mut inner_product: Vec<Term> = Vec::new();
-    for i in 0..messages.len() {
-        let signature = &signatures[i];
-        let R = &R_vec[i];
-        let a = &a_vec[i];
-        let e = &e_vec[i];
-        let P = &pub_keys[i];
-
-        coeff = coeff.add(a.to_owned().mul(&signature.s));
-        inner_product.push(Term {
-            coeff: a.to_owned(),
-            point: R.to_owned(),
-        });
-        inner_product.push(Term {
-            coeff: a.to_owned().mul(e),
-            point: JacobianPoint::from(P.to_owned()),
-        });
+    if !R.has_even_y() {
+        return Err(Error::InvalidSignature { reason: Reason::ROddY });
     }
-    inner_product.push(Term {
-        coeff: CONTEXT.n.clone().sub(&coeff),
-        point: CONTEXT.G_jacobian.clone(),
-    }); // -sG
-
-    let mut inner_product: BinaryHeap<Term> = BinaryHeap::from(inner_product);
-
-    while inner_product.len() > 1 {
-        let t0 = inner_product.pop().unwrap();
-        let t1 = inner_product.pop().unwrap();
-        let option = jacobian_point_add(Some(&t0.point), Some(&t1.point));
-        if option.is_none() && inner_product.len() == 0 {
-            return true;
-        }
-        inner_product.push(Term {
-            coeff: t1.coeff.clone(),
-            point: option.unwrap(),
-        });
-        if t0.coeff != t1.coeff {
-            inner_product.push(Term {
-                coeff: t0.coeff - &t1.coeff,
-                point: t0.point,
-            });
-        }
+    if R.x != signature.Rx {
+        return Err(Error::InvalidSignature { reason: Reason::RxMismatch });
     }
-    return false;
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use data_encoding::HEXUPPER;
-    use old;
+    use backend::native::Point;
     use rand::prelude::*;
-    use scalar::vec_to_32_bytes;
 
     #[test]
     fn test_sign_and_jacobi_sign() {
         let sec_key = thread_rng().gen::<ScalarN>();
         let msg = [0u8; 32];
-        let sign1 = schnorr_sign(&msg, &sec_key);
-        let sign2 = schnorr_sign(&msg, &sec_key);
+        let aux_rand = thread_rng().gen::<[u8; 32]>();
+        let sign1 = schnorr_sign(&msg, &sec_key, &aux_rand);
+        let sign2 = schnorr_sign(&msg, &sec_key, &aux_rand);
         assert_eq!(sign1, sign2);
+        let sign3 = schnorr_sign(&msg, &sec_key, &[0u8; 32]);
+        assert_ne!(sign1, sign3);
     }
 
 
     #[test]
     fn test_sign_and_verify() {
         let mut rng = thread_rng();
-        /*let mut messages = Vec::new();
+        let mut messages = Vec::new();
         let mut pub_keys = Vec::new();
-        let mut signatures = Vec::new();*/
+        let mut signatures = Vec::new();
 
         let mut msg = [0u8; 32];
 
-        for i in 0..10 {
-            println!("{}", i);
+        for _ in 0..10 {
             rng.fill_bytes(&mut msg);
             let sec_key = rng.gen::<ScalarN>();
+            let aux_rand = rng.gen::<[u8; 32]>();
             let pub_key: Point = generator_mul(&sec_key).unwrap().into();
-            let signature = schnorr_sign(&msg, &sec_key);
-            let result = schnorr_verify(&msg, &pub_key.into(), &signature);
-            println!("result {}", result);
+            let signature = schnorr_sign(&msg, &sec_key, &aux_rand);
+            let result = schnorr_verify(&msg, &NormalizedPoint::from(&pub_key), &signature);
             assert!(result);
-            /*
-            if i == 0 {
-                // too slow to do it every time
-                let result = old::schnorr_verify(&msg, &pub_key, &signature);
-                assert!(result);
-            }
-
+            assert!(!schnorr_verify(&[1u8; 32], &NormalizedPoint::from(&pub_key), &signature));
 
             messages.push(msg);
-            pub_keys.push(pub_key);
+            pub_keys.push(NormalizedPoint::from(pub_key));
             signatures.push(signature);
-            */
         }
-        /*
-        assert!(schnorr_batch_verify(&messages, &pub_keys, &signatures));
-        assert!(old::schnorr_batch_verify(
-            &messages[..2].to_vec(),
-            &pub_keys[..2].to_vec(),
-            &signatures[..2].to_vec()
-        ));
-        messages.pop();
-        messages.push([0u8; 32]);
-        assert!(!schnorr_batch_verify(&messages, &pub_keys, &signatures));
-        */
+        let mut batch: Vec<_> = (0..10).map(|i| (&messages[i][..], &pub_keys[i], &signatures[i])).collect();
+        assert_eq!(Ok(()), schnorr_batch_verify(&batch));
+        batch[9].0 = &[0u8; 32];
+        assert_eq!(Err(error::BatchError { invalid: vec![9] }), schnorr_batch_verify(&batch));
     }
 
     #[test]
-    fn test_new_bip_verify() {
-        let test_vectors = include_str!("../test-vectors.csv");
-
-        for (i,line) in test_vectors.lines().enumerate() {
-            if i == 0 {
-                continue;
-            }
-            let mut cols = line.split(",");
-            let index = cols.next().unwrap();
-            let secret_key = cols.next().unwrap();
-            let public_key = cols.next().unwrap();
-            let message = cols.next().unwrap();
-            let signature = cols.next().unwrap();
-            let result = cols.next().unwrap();
-            let comment = cols.next().unwrap();
-
-            if !secret_key.is_empty() {
-                test_vector(&secret_key, &public_key, &message, &signature, "TRUE" == result);
-            }
-            test_vector_verify(&public_key, &message, &signature, "TRUE" == result);
+    fn test_sign_and_verify_any_length() {
+        let mut rng = thread_rng();
+        let sec_key = rng.gen::<ScalarN>();
+        let pub_key: Point = generator_mul(&sec_key).unwrap().into();
+        let pub_key = NormalizedPoint::from(pub_key);
+        for len in [0usize, 1, 17, 32, 100, 1000].iter() {
+            let mut msg = vec![0u8; *len];
+            rng.fill_bytes(&mut msg);
+            let signature = schnorr_sign(&msg, &sec_key, &rng.gen());
+            assert!(schnorr_verify(&msg, &pub_key, &signature));
+            msg.push(0u8);
+            assert!(!schnorr_verify(&msg, &pub_key, &signature));
         }
+        let signature = schnorr_sign(&[0u8; 31], &sec_key, &[0u8; 32]);
+        assert!(!schnorr_verify_variant(Variant::Draft2019, &[0u8; 31], &pub_key, &signature));
+    }
 
+    #[test]
+    fn test_verify_detailed() {
+        let sec_key = thread_rng().gen::<ScalarN>();
+        let pub_key: Point = generator_mul(&sec_key).unwrap().into();
+        let pub_key = pub_key.as_bytes();
+        let msg = [3u8; 32];
+        let signature = schnorr_sign(&msg, &sec_key, &[0u8; 32]).as_bytes();
+        assert_eq!(Ok(()), verify_detailed(&msg, &pub_key, &signature));
+
+        // with another message R is a random point, which has odd y half of the time
+        let reason = match verify_detailed(&[4u8; 32], &pub_key, &signature) {
+            Err(Error::InvalidSignature { reason }) => reason,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert!(reason == Reason::RxMismatch || reason == Reason::ROddY);
+        assert_eq!(
+            Err(Error::InvalidLength { expected: 64, found: 63 }),
+            verify_detailed(&msg, &pub_key, &signature[1..])
+        );
+        assert_eq!(
+            Err(Error::InvalidLength { expected: 32, found: 33 }),
+            verify_detailed(&msg, &[2u8; 33], &signature)
+        );
+        let mut high_s = signature.clone();
+        high_s[32..].copy_from_slice(&[0xFFu8; 32]);
+        assert_eq!(Err(Error::ScalarOutOfRange), verify_detailed(&msg, &pub_key, &high_s));
+        let mut high_r = signature.clone();
+        high_r[..32].copy_from_slice(&[0xFFu8; 32]);
+        assert_eq!(Err(Error::FieldElementOutOfRange), verify_detailed(&msg, &pub_key, &high_r));
+        assert_eq!(Err(Error::FieldElementOutOfRange), verify_detailed(&msg, &[0xFFu8; 32], &signature));
+        // x = 5 is not on the curve
+        let mut not_on_curve = [0u8; 32];
+        not_on_curve[31] = 5;
+        assert_eq!(Err(Error::NotOnCurve), verify_detailed(&msg, &not_on_curve, &signature));
     }
 
-    fn test_vector_verify(public: &str, message: &str, signature: &str, result: bool) {
-        println!("{} {} {} {}", public, message, signature, result);
-        let pub_key = Point::from_bytes(&HEXUPPER.decode(public.as_bytes()).unwrap()).unwrap();
-        let message_bytes = vec_to_32_bytes(&HEXUPPER.decode(message.as_bytes()).unwrap());
-        let signature_bytes = HEXUPPER.decode(signature.as_bytes()).unwrap();
-        let signature_result = Signature::from_bytes(&signature_bytes);
-        if signature_result.is_ok() {
+    #[test]
+    fn test_verify_detailed_reasons() {
+        // BIP340 test vectors 6, 9 and 10
+        let hex = |string: &str| data_encoding::HEXUPPER.decode(string.as_bytes()).unwrap();
+        let pub_key = hex("DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659");
+        let msg = hex("243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89");
+        let cases = [
+            (
+                "FFF97BD5755EEEA420453A14355235D382F6472F8568A18B2F057A14602975563CC27944640AC607CD107AE10923D9EF7A73C643E166BE5EBEAFA34B1AC553E2",
+                Reason::ROddY,
+            ),
+            (
+                "0000000000000000000000000000000000000000000000000000000000000000123DDA8328AF9C23A94C1FEECFD123BA4FB73476F0D594DCB65C6425BD186051",
+                Reason::RAtInfinity,
+            ),
+            (
+                "00000000000000000000000000000000000000000000000000000000000000017615FBAF5AE28864013C099742DEADB4DBA87F11AC6754F93780D5A1837CF197",
+                Reason::RAtInfinity,
+            ),
+        ];
+        for (signature, reason) in cases.iter() {
             assert_eq!(
-                result,
-                schnorr_verify(
-                    &message_bytes,
-                    &pub_key.into(),
-                    &signature_result.unwrap()
-                )
+                Err(Error::InvalidSignature { reason: *reason }),
+                verify_detailed(&msg, &pub_key, &hex(signature))
             );
-        } else {
-            println!("ERRRRRRR");
         }
     }
 
-    fn test_vector(private: &str, public: &str, message: &str, signature: &str, result: bool) {
-        println!("{} {} {} {} {}", private, public, message, signature, result);
-
-        let sec_key_bytes = vec_to_32_bytes(&HEXUPPER.decode(private.as_bytes()).unwrap());
-        let sec_key = ScalarN::new(integer_from_bytes(&sec_key_bytes));
+    #[test]
+    fn test_variant() {
+        let sec_key = thread_rng().gen::<ScalarN>();
         let pub_key: Point = generator_mul(&sec_key).unwrap().into();
-        assert_eq!(HEXUPPER.encode(&pub_key.as_bytes()[..]), public);
-        let message = vec_to_32_bytes(&HEXUPPER.decode(message.as_bytes()).unwrap());
-
-        let signature_check = HEXUPPER.decode(signature.as_bytes()).unwrap();
-        let signature = schnorr_sign(&message, &sec_key);
-        assert_eq!(signature_check, signature.as_bytes());
+        let msg = [7u8; 32];
+        for variant in [Variant::Draft2019, Variant::Bip340].iter() {
+            let signature = schnorr_sign_variant(*variant, &msg, &sec_key, &[0u8; 32]).unwrap();
+            assert!(schnorr_verify_variant(*variant, &msg, &pub_key.clone().into(), &signature));
+        }
+        let draft = schnorr_sign_variant(Variant::Draft2019, &msg, &sec_key, &[0u8; 32]).unwrap();
+        assert!(!schnorr_verify(&msg, &pub_key.into(), &draft));
+        assert_eq!(
+            Err(Error::InvalidLength { expected: 32, found: 31 }),
+            schnorr_sign_variant(Variant::Draft2019, &msg[1..], &sec_key, &[0u8; 32])
+        );
     }
-
 }
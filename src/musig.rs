@@ -0,0 +1,578 @@
+use backend::native::{JacobianPoint, NormalizedPoint, Point, Signature};
+use context::CONTEXT;
+use error::{check_length, Error};
+use point::{generator_mul, jacobian_point_add, jacobian_point_mul_wnaf};
+use rand::{CryptoRng, Rng, RngCore};
+use scalar::{concat_and_hash_BIP0340_challenge, tagged_hash};
+use scalar::ScalarN;
+use std::ops::{Add, Mul};
+
+// MuSig2 as specified in BIP327
+// https://github.com/bitcoin/bips/blob/master/bip-0327.mediawiki
+// Every signer aggregates the public keys, sends two public nonces in the first round and a partial
+// signature in the second one, the sum of the partial signatures is a BIP340 signature for the
+// aggregated x-only key. Public keys and nonces use the 33 bytes compressed encoding.
+
+/// Aggregated public key Q with the accumulated tweak (tacc) and sign (gacc) of the tweaks applied
+#[allow(non_snake_case)]
+#[derive(Clone, Debug)]
+pub struct KeyAggContext {
+    pub_keys: Vec<Point>,
+    second_key: Option<Point>,
+    list_hash: [u8; 32],
+    Q: Point,
+    gacc: ScalarN,
+    tacc: ScalarN,
+}
+
+/// The two secret nonces of a signer, not `Clone` so that `partial_sign` consumes it once
+#[derive(Debug)]
+pub struct SecNonce {
+    k1: ScalarN,
+    k2: ScalarN,
+    pub_key: Point,
+}
+
+#[allow(non_snake_case)]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PubNonce {
+    pub R1: Point,
+    pub R2: Point,
+}
+
+/// Sum of the public nonces, each point may be the point at infinity
+#[allow(non_snake_case)]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AggNonce {
+    pub R1: Option<Point>,
+    pub R2: Option<Point>,
+}
+
+/// Values shared by all the signers once the aggregated nonce and the message are known
+#[allow(non_snake_case)]
+#[derive(Clone, Debug)]
+pub struct Session<'a> {
+    key_agg: &'a KeyAggContext,
+    agg_nonce: AggNonce,
+    b: ScalarN,
+    R: Point,
+    e: ScalarN,
+}
+
+fn one() -> ScalarN {
+    ScalarN::new(1.into())
+}
+
+fn negate(scalar: ScalarN) -> ScalarN {
+    scalar.negate()
+}
+
+fn point_mul(point: &Point, scalar: &ScalarN) -> Option<JacobianPoint> {
+    jacobian_point_mul_wnaf(&JacobianPoint::from(point.clone()), scalar, 5i8)
+}
+
+// the point at infinity is encoded as 33 zero bytes
+fn as_compressed_bytes_ext(point: &Option<Point>) -> [u8; 33] {
+    match point {
+        Some(point) => point.as_compressed_bytes(),
+        None => [0u8; 33],
+    }
+}
+
+fn from_compressed_bytes_ext(bytes: &[u8]) -> Result<Option<Point>, Error> {
+    if bytes.iter().all(|el| *el == 0) {
+        return Ok(None);
+    }
+    Point::from_compressed_bytes(bytes).map(Some)
+}
+
+impl KeyAggContext {
+    /// Aggregates the public keys in the given order, the order changes the result
+    #[allow(non_snake_case)]
+    pub fn new(pub_keys: &[Point]) -> Result<Self, Error> {
+        if pub_keys.is_empty() {
+            return Err(Error::NoPublicKeys);
+        }
+        let mut all_pub_keys_bytes = Vec::with_capacity(33 * pub_keys.len());
+        for pub_key in pub_keys.iter() {
+            all_pub_keys_bytes.extend(&pub_key.as_compressed_bytes()[..]);
+        }
+        let mut key_agg = KeyAggContext {
+            pub_keys: pub_keys.to_vec(),
+            second_key: pub_keys.iter().find(|el| **el != pub_keys[0]).cloned(),
+            list_hash: tagged_hash(b"KeyAgg list", &all_pub_keys_bytes),
+            Q: CONTEXT.G.clone(),
+            gacc: one(),
+            tacc: ScalarN::new(0.into()),
+        };
+
+        let mut Q: Option<JacobianPoint> = None;
+        for pub_key in pub_keys.iter() {
+            let term = point_mul(pub_key, &key_agg.coefficient(pub_key));
+            Q = jacobian_point_add(Q.as_ref(), term.as_ref());
+        }
+        key_agg.Q = Point::from(Q.ok_or(Error::PointAtInfinity)?);
+        Ok(key_agg)
+    }
+
+    /// Adds tweak*G to the aggregated key, an x-only tweak first negates Q if it has an odd y
+    #[allow(non_snake_case)]
+    pub fn apply_tweak(&mut self, tweak: &[u8], is_xonly: bool) -> Result<(), Error> {
+        let tweak = ScalarN::try_from_bytes(tweak)?;
+        let g = if is_xonly && !self.Q.has_even_y() {
+            negate(one())
+        } else {
+            one()
+        };
+        let gQ = point_mul(&self.Q, &g);
+        let Q = jacobian_point_add(gQ.as_ref(), generator_mul(&tweak).as_ref());
+        self.Q = Point::from(Q.ok_or(Error::PointAtInfinity)?);
+        self.gacc = g.clone().mul(&self.gacc);
+        self.tacc = tweak.add(g.mul(&self.tacc));
+        Ok(())
+    }
+
+    /// The aggregated public key with its parity, needed for further tweaking
+    pub fn agg_pub_key(&self) -> &Point {
+        &self.Q
+    }
+
+    /// The x-only aggregated public key, the one verifying the final signature
+    pub fn agg_x_only_pub_key(&self) -> NormalizedPoint {
+        NormalizedPoint::from(&self.Q)
+    }
+
+    pub fn pub_keys(&self) -> &[Point] {
+        &self.pub_keys
+    }
+
+    fn coefficient(&self, pub_key: &Point) -> ScalarN {
+        // the second distinct key gets 1, saving a multiplication
+        if self.second_key.as_ref() == Some(pub_key) {
+            return one();
+        }
+        let mut input = self.list_hash.to_vec();
+        input.extend(&pub_key.as_compressed_bytes()[..]);
+        ScalarN::from_bytes(&tagged_hash(b"KeyAgg coefficient", &input))
+    }
+}
+
+impl PubNonce {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        check_length(bytes, 66)?;
+        Ok(PubNonce {
+            R1: Point::from_compressed_bytes(&bytes[..33])?,
+            R2: Point::from_compressed_bytes(&bytes[33..])?,
+        })
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut vec = Vec::with_capacity(66);
+        vec.extend(&self.R1.as_compressed_bytes()[..]);
+        vec.extend(&self.R2.as_compressed_bytes()[..]);
+        vec
+    }
+}
+
+impl AggNonce {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        check_length(bytes, 66)?;
+        Ok(AggNonce {
+            R1: from_compressed_bytes_ext(&bytes[..33])?,
+            R2: from_compressed_bytes_ext(&bytes[33..])?,
+        })
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut vec = Vec::with_capacity(66);
+        vec.extend(&as_compressed_bytes_ext(&self.R1)[..]);
+        vec.extend(&as_compressed_bytes_ext(&self.R2)[..]);
+        vec
+    }
+}
+
+/// First round: derives the two secret nonces from 32 fresh random bytes and the optional inputs.
+/// Every optional input makes the nonce more robust against a weak `rand`, none replaces it
+#[allow(non_snake_case)]
+pub fn nonce_gen(
+    rand: &[u8; 32],
+    sec_key: Option<&ScalarN>,
+    pub_key: &Point,
+    agg_pub_key: Option<&NormalizedPoint>,
+    msg: Option<&[u8]>,
+    extra_in: Option<&[u8]>,
+) -> (SecNonce, PubNonce) {
+    let mut rand = *rand;
+    if let Some(sec_key) = sec_key {
+        let aux = tagged_hash(b"MuSig/aux", &rand);
+        for (r_i, (sk_i, aux_i)) in rand.iter_mut().zip(sec_key.to_32_bytes().iter().zip(aux.iter())) {
+            *r_i = sk_i ^ aux_i;
+        }
+    }
+
+    let mut input = rand.to_vec();
+    input.push(33u8);
+    input.extend(&pub_key.as_compressed_bytes()[..]);
+    match agg_pub_key {
+        Some(agg_pub_key) => {
+            input.push(32u8);
+            input.extend(&agg_pub_key.as_bytes()[..]);
+        }
+        None => input.push(0u8),
+    }
+    match msg {
+        Some(msg) => {
+            input.push(1u8);
+            input.extend(&(msg.len() as u64).to_be_bytes()[..]);
+            input.extend(msg);
+        }
+        None => input.push(0u8),
+    }
+    let extra_in = extra_in.unwrap_or(&[]);
+    input.extend(&(extra_in.len() as u32).to_be_bytes()[..]);
+    input.extend(extra_in);
+
+    let derive = |i: u8| {
+        let mut input = input.clone();
+        input.push(i);
+        ScalarN::from_bytes(&tagged_hash(b"MuSig/nonce", &input))
+    };
+    let k1 = derive(0);
+    let k2 = derive(1);
+    // k1 or k2 equal to 0 happens with negligible probability
+    let R1 = Point::from(generator_mul(&k1).unwrap());
+    let R2 = Point::from(generator_mul(&k2).unwrap());
+
+    let sec_nonce = SecNonce {
+        k1,
+        k2,
+        pub_key: pub_key.clone(),
+    };
+    (sec_nonce, PubNonce { R1, R2 })
+}
+
+/// `nonce_gen` with the 32 random bytes drawn from `rng`
+pub fn nonce_gen_with_rng<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    sec_key: Option<&ScalarN>,
+    pub_key: &Point,
+    agg_pub_key: Option<&NormalizedPoint>,
+    msg: Option<&[u8]>,
+    extra_in: Option<&[u8]>,
+) -> (SecNonce, PubNonce) {
+    nonce_gen(&rng.gen(), sec_key, pub_key, agg_pub_key, msg, extra_in)
+}
+
+/// Sums the public nonces of all the signers, can be done by any of them or by a coordinator
+#[allow(non_snake_case)]
+pub fn nonce_agg(pub_nonces: &[PubNonce]) -> AggNonce {
+    let mut R1: Option<JacobianPoint> = None;
+    let mut R2: Option<JacobianPoint> = None;
+    for pub_nonce in pub_nonces.iter() {
+        R1 = jacobian_point_add(R1.as_ref(), Some(&JacobianPoint::from(pub_nonce.R1.clone())));
+        R2 = jacobian_point_add(R2.as_ref(), Some(&JacobianPoint::from(pub_nonce.R2.clone())));
+    }
+    AggNonce {
+        R1: R1.map(Point::from),
+        R2: R2.map(Point::from),
+    }
+}
+
+impl<'a> Session<'a> {
+    #[allow(non_snake_case)]
+    pub fn new(key_agg: &'a KeyAggContext, agg_nonce: &AggNonce, msg: &[u8]) -> Self {
+        let Q_bytes = key_agg.Q.as_bytes();
+        let mut input = agg_nonce.as_bytes();
+        input.extend(&Q_bytes[..]);
+        input.extend(msg);
+        let b = ScalarN::from_bytes(&tagged_hash(b"MuSig/noncecoef", &input));
+
+        let R1 = agg_nonce.R1.clone().map(JacobianPoint::from);
+        let bR2 = agg_nonce.R2.as_ref().and_then(|R2| point_mul(R2, &b));
+        // when R is infinity the signers can't be blamed, G is used instead
+        let R = match jacobian_point_add(R1.as_ref(), bR2.as_ref()) {
+            Some(R) => Point::from(R),
+            None => CONTEXT.G.clone(),
+        };
+        let e = concat_and_hash_BIP0340_challenge(&R.as_bytes(), &Q_bytes, msg);
+
+        Session {
+            key_agg,
+            agg_nonce: agg_nonce.clone(),
+            b,
+            R,
+            e,
+        }
+    }
+
+    /// Second round: the partial signature of the owner of `sec_key`
+    pub fn partial_sign(&self, sec_nonce: SecNonce, sec_key: &ScalarN) -> Result<ScalarN, Error> {
+        let pub_key = Point::from(generator_mul(sec_key).ok_or(Error::ScalarOutOfRange)?);
+        if pub_key != sec_nonce.pub_key {
+            return Err(Error::NonceKeyMismatch);
+        }
+        if !self.key_agg.pub_keys.contains(&pub_key) {
+            return Err(Error::UnknownSigner);
+        }
+        let (k1, k2) = if self.R.has_even_y() {
+            (sec_nonce.k1, sec_nonce.k2)
+        } else {
+            (negate(sec_nonce.k1), negate(sec_nonce.k2))
+        };
+        let a = self.key_agg.coefficient(&pub_key);
+        let d = self.g().mul(&self.key_agg.gacc).mul(sec_key);
+
+        Ok(k1.add(self.b.clone().mul(&k2)).add(self.e.clone().mul(&a).mul(&d)))
+    }
+
+    /// Checks s*G = R1 + b*R2 + e*a*g*P for the signer with the given nonce and public key
+    #[allow(non_snake_case)]
+    pub fn partial_sig_verify(&self, partial_sig: &ScalarN, pub_nonce: &PubNonce, pub_key: &Point) -> bool {
+        if !self.key_agg.pub_keys.contains(pub_key) {
+            return false;
+        }
+        let bR2 = point_mul(&pub_nonce.R2, &self.b);
+        let Re = jacobian_point_add(Some(&JacobianPoint::from(pub_nonce.R1.clone())), bR2.as_ref());
+        let Re = match Re {
+            Some(Re) if self.R.has_even_y() => Some(Re),
+            Some(Re) => Some(Re.negate()),
+            None => None,
+        };
+        let g = self.g().mul(&self.key_agg.gacc);
+        let eagP = point_mul(pub_key, &self.e.clone().mul(&self.key_agg.coefficient(pub_key)).mul(&g));
+
+        let left = generator_mul(partial_sig);
+        let right = jacobian_point_add(Re.as_ref(), eagP.as_ref());
+        match (left, right) {
+            (Some(left), Some(right)) => left == right,
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    /// Sums the partial signatures into a BIP340 signature for `agg_x_only_pub_key`
+    pub fn partial_sig_agg(&self, partial_sigs: &[ScalarN]) -> Signature {
+        let mut s = self.e.clone().mul(&self.g()).mul(&self.key_agg.tacc);
+        for partial_sig in partial_sigs.iter() {
+            s = s.add(partial_sig.clone());
+        }
+        Signature::new(self.R.x.clone(), s)
+    }
+
+    pub fn agg_nonce(&self) -> &AggNonce {
+        &self.agg_nonce
+    }
+
+    // negates the secret keys when the aggregated key has an odd y
+    fn g(&self) -> ScalarN {
+        if self.key_agg.Q.has_even_y() {
+            one()
+        } else {
+            negate(one())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_encoding::HEXUPPER;
+    use rand::prelude::*;
+    use scalar::to_32_bytes;
+    use scalar::ScalarP;
+    use schnorr_verify;
+
+    fn hex(string: &str) -> Vec<u8> {
+        HEXUPPER.decode(string.as_bytes()).unwrap()
+    }
+
+    fn point(string: &str) -> Point {
+        Point::from_compressed_bytes(&hex(string)).unwrap()
+    }
+
+    // https://github.com/bitcoin/bips/blob/master/bip-0327/vectors
+    const PUB_KEYS: [&str; 3] = [
+        "02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+        "03DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+        "023590A94E768F8E1815C2F24B4D80A8E3149316C3518CE7B7AD338368D038CA66",
+    ];
+
+    #[test]
+    fn test_key_agg_vectors() {
+        let keys: Vec<Point> = PUB_KEYS.iter().map(|el| point(el)).collect();
+        let cases = [
+            (vec![0, 1, 2], "90539EEDE565F5D054F32CC0C220126889ED1E5D193BAF15AEF344FE59D4610C"),
+            (vec![2, 1, 0], "6204DE8B083426DC6EAF9502D27024D53FC826BF7D2012148A0575435DF54B2B"),
+            (vec![0, 0, 0], "B436E3BAD62B8CD409969A224731C193D051162D8C5AE8B109306127DA3AA935"),
+            (vec![0, 0, 1, 1], "69BC22BFA5D106306E48A20679DE1D7389386124D07571D0D872686028C26A3E"),
+        ];
+        for (indices, expected) in cases.iter() {
+            let pub_keys: Vec<Point> = indices.iter().map(|i| keys[*i].clone()).collect();
+            let key_agg = KeyAggContext::new(&pub_keys).unwrap();
+            assert_eq!(&key_agg.agg_x_only_pub_key().as_bytes()[..], &hex(expected)[..]);
+        }
+
+        let mut key_agg = KeyAggContext::new(&keys[..2]).unwrap();
+        let n_bytes = to_32_bytes(&CONTEXT.n);
+        assert_eq!(Err(Error::ScalarOutOfRange), key_agg.apply_tweak(&n_bytes, true));
+        assert_eq!(Err(Error::NoPublicKeys), KeyAggContext::new(&[]).map(|_| ()));
+    }
+
+    #[test]
+    fn test_nonce_gen_vectors() {
+        let (sec_nonce, pub_nonce) = nonce_gen(
+            &[0x0Fu8; 32],
+            Some(&ScalarN::from_bytes(&[0x02u8; 32])),
+            &point("024D4B6CD1361032CA9BD2AEB9D900AA4D45D9EAD80AC9423374C451A7254D0766"),
+            Some(&NormalizedPoint { x: ScalarP::from_bytes(&[0x07u8; 32]) }),
+            Some(&[0x01u8; 32]),
+            Some(&[0x08u8; 32]),
+        );
+        assert_eq!(
+            &sec_nonce.k1.to_32_bytes()[..],
+            &hex("B114E502BEAA4E301DD08A50264172C84E41650E6CB726B410C0694D59EFFB64")[..]
+        );
+        assert_eq!(
+            pub_nonce.as_bytes(),
+            hex("02F7BE7089E8376EB355272368766B17E88E7DB72047D05E56AA881EA52B3B35DF02C29C8046FDD0DED4C7E55869137200FBDBFE2EB654267B6D7013602CAED3115A")
+        );
+
+        let (_, pub_nonce) = nonce_gen(&[0x0Fu8; 32], None, &point(PUB_KEYS[0]), None, None, None);
+        assert_eq!(
+            pub_nonce.as_bytes(),
+            hex("02C96E7CB1E8AA5DAC64D872947914198F607D90ECDE5200DE52978AD5DED63C000299EC5117C2D29EDEE8A2092587C3909BE694D5CFF0667D6C02EA4059F7CD9786")
+        );
+    }
+
+    #[test]
+    fn test_sign_vectors() {
+        let sec_key = ScalarN::from_bytes(&hex("7FB9E0E687ADA1EEBF7ECFE2F21E73EBDB51A7D450948DFE8D76D7F2D1007671"));
+        let keys = [
+            point("03935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9"),
+            point("02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9"),
+            point("02DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA661"),
+        ];
+        let pub_nonces = [
+            PubNonce::from_bytes(&hex("0337C87821AFD50A8644D820A8F3E02E499C931865C2360FB43D0A0D20DAFE07EA0287BF891D2A6DEAEBADC909352AA9405D1428C15F4B75F04DAE642A95C2548480")).unwrap(),
+            PubNonce::from_bytes(&hex("0279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F817980279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798")).unwrap(),
+            PubNonce::from_bytes(&hex("032DE2662628C90B03F5E720284EB52FF7D71F4284F627B68A853D78C78E1FFE9303E4C5524E83FFE1493B9077CF1CA6BEB2090C93D930321071AD40B2F44E599046")).unwrap(),
+        ];
+        let agg_nonce = AggNonce::from_bytes(&hex("028465FCF0BBDBCF443AABCCE533D42B4B5A10966AC09A49655E8C42DAAB8FCD61037496A3CC86926D452CAFCFD55D25972CA1675D549310DE296BFF42F72EEEA8C9")).unwrap();
+        assert_eq!(agg_nonce, nonce_agg(&pub_nonces));
+        let msg = hex("F95466D086770E689964664219266FE5ED215C92AE20BAB5C9D79ADDDDF3C0CF");
+        let sec_nonce = || SecNonce {
+            k1: ScalarN::from_bytes(&hex("508B81A611F100A6B2B6B29656590898AF488BCF2E1F55CF22E5CFB84421FE61")),
+            k2: ScalarN::from_bytes(&hex("FA27FD49B1D50085B481285E1CA205D55C82CC1B31FF5CD54A489829355901F7")),
+            pub_key: keys[0].clone(),
+        };
+
+        let cases = [
+            (vec![0, 1, 2], "012ABBCB52B3016AC03AD82395A1A415C48B93DEF78718E62A7A90052FE224FB"),
+            (vec![1, 0, 2], "9FF2F7AAA856150CC8819254218D3ADEEB0535269051897724F9DB3789513A52"),
+            (vec![1, 2, 0], "FA23C359F6FAC4E7796BB93BC9F0532A95468C539BA20FF86D7C76ED92227900"),
+        ];
+        for (indices, expected) in cases.iter() {
+            let pub_keys: Vec<Point> = indices.iter().map(|i| keys[*i].clone()).collect();
+            let key_agg = KeyAggContext::new(&pub_keys).unwrap();
+            let session = Session::new(&key_agg, &agg_nonce, &msg);
+            let partial_sig = session.partial_sign(sec_nonce(), &sec_key).unwrap();
+            assert_eq!(&partial_sig.to_32_bytes()[..], &hex(expected)[..]);
+            assert!(session.partial_sig_verify(&partial_sig, &pub_nonces[0], &keys[0]));
+            assert!(!session.partial_sig_verify(&partial_sig, &pub_nonces[1], &keys[0]));
+            assert!(!session.partial_sig_verify(&partial_sig, &pub_nonces[0], &keys[1]));
+        }
+
+        let key_agg = KeyAggContext::new(&keys[1..]).unwrap();
+        let session = Session::new(&key_agg, &agg_nonce, &msg);
+        assert_eq!(Err(Error::UnknownSigner), session.partial_sign(sec_nonce(), &sec_key));
+        let key_agg = KeyAggContext::new(&keys).unwrap();
+        let session = Session::new(&key_agg, &agg_nonce, &msg);
+        let other_nonce = SecNonce {
+            pub_key: keys[1].clone(),
+            ..sec_nonce()
+        };
+        assert_eq!(Err(Error::NonceKeyMismatch), session.partial_sign(other_nonce, &sec_key));
+    }
+
+    #[test]
+    fn test_tweak_vectors() {
+        let sec_key = ScalarN::from_bytes(&hex("7FB9E0E687ADA1EEBF7ECFE2F21E73EBDB51A7D450948DFE8D76D7F2D1007671"));
+        let pub_keys = [
+            point("02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9"),
+            point("02DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659"),
+            point("03935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9"),
+        ];
+        let agg_nonce = AggNonce::from_bytes(&hex("028465FCF0BBDBCF443AABCCE533D42B4B5A10966AC09A49655E8C42DAAB8FCD61037496A3CC86926D452CAFCFD55D25972CA1675D549310DE296BFF42F72EEEA8C9")).unwrap();
+        let msg = hex("F95466D086770E689964664219266FE5ED215C92AE20BAB5C9D79ADDDDF3C0CF");
+        let tweaks = [
+            "E8F791FF9225A2AF0102AFFF4A9A723D9612A682A25EBE79802B263CDFCD83BB",
+            "AE2EA797CC0FE72AC5B97B97F3C6957D7E4199A167A58EB08BCAFFDA70AC0455",
+            "F52ECBC565B3D8BEA2DFD5B75A4F457E54369809322E4120831626F290FA87E0",
+            "1969AD73CC177FA0B4FCED6DF1F7BF9907E665FDE9BA196A74FED0A3CF5AEF9D",
+        ];
+        let cases = [
+            (vec![true], "E28A5C66E61E178C2BA19DB77B6CF9F7E2F0F56C17918CD13135E60CC848FE91"),
+            (vec![false], "38B0767798252F21BF5702C48028B095428320F73A4B14DB1E25DE58543D2D2D"),
+            (vec![false, true], "408A0A21C4A0F5DACAF9646AD6EB6FECD7F7A11F03ED1F48DFFF2185BC2C2408"),
+            (vec![false, false, true, true], "45ABD206E61E3DF2EC9E264A6FEC8292141A633C28586388235541F9ADE75435"),
+            (vec![true, false, true, false], "B255FDCAC27B40C7CE7848E2D3B7BF5EA0ED756DA81565AC804CCCA3E1D5D239"),
+        ];
+        for (is_xonly, expected) in cases.iter() {
+            let mut key_agg = KeyAggContext::new(&pub_keys).unwrap();
+            for (tweak, is_xonly) in tweaks.iter().zip(is_xonly.iter()) {
+                key_agg.apply_tweak(&hex(tweak), *is_xonly).unwrap();
+            }
+            let session = Session::new(&key_agg, &agg_nonce, &msg);
+            let sec_nonce = SecNonce {
+                k1: ScalarN::from_bytes(&hex("508B81A611F100A6B2B6B29656590898AF488BCF2E1F55CF22E5CFB84421FE61")),
+                k2: ScalarN::from_bytes(&hex("FA27FD49B1D50085B481285E1CA205D55C82CC1B31FF5CD54A489829355901F7")),
+                pub_key: pub_keys[2].clone(),
+            };
+            let partial_sig = session.partial_sign(sec_nonce, &sec_key).unwrap();
+            assert_eq!(&partial_sig.to_32_bytes()[..], &hex(expected)[..]);
+        }
+    }
+
+    #[test]
+    fn test_musig() {
+        let mut rng = thread_rng();
+        for (signers, tweak) in [(2usize, false), (5, false), (3, true)].iter() {
+            let sec_keys: Vec<ScalarN> = (0..*signers).map(|_| rng.gen()).collect();
+            let pub_keys: Vec<Point> = sec_keys
+                .iter()
+                .map(|sec_key| Point::from(generator_mul(sec_key).unwrap()))
+                .collect();
+            let mut key_agg = KeyAggContext::new(&pub_keys).unwrap();
+            if *tweak {
+                key_agg.apply_tweak(&rng.gen::<[u8; 32]>(), true).unwrap();
+            }
+            let msg = rng.gen::<[u8; 32]>();
+
+            // first round
+            let (sec_nonces, pub_nonces): (Vec<SecNonce>, Vec<PubNonce>) = sec_keys
+                .iter()
+                .zip(pub_keys.iter())
+                .map(|(sec_key, pub_key)| {
+                    let agg_pub_key = key_agg.agg_x_only_pub_key();
+                    nonce_gen_with_rng(&mut rng, Some(sec_key), pub_key, Some(&agg_pub_key), Some(&msg), None)
+                })
+                .unzip();
+            let agg_nonce = nonce_agg(&pub_nonces);
+
+            // second round
+            let session = Session::new(&key_agg, &agg_nonce, &msg);
+            let mut partial_sigs = Vec::new();
+            for (i, (sec_nonce, sec_key)) in sec_nonces.into_iter().zip(sec_keys.iter()).enumerate() {
+                let partial_sig = session.partial_sign(sec_nonce, sec_key).unwrap();
+                assert!(session.partial_sig_verify(&partial_sig, &pub_nonces[i], &pub_keys[i]));
+                partial_sigs.push(partial_sig);
+            }
+
+            let signature = session.partial_sig_agg(&partial_sigs);
+            assert!(schnorr_verify(&msg, &key_agg.agg_x_only_pub_key(), &signature));
+            assert!(!schnorr_verify(&[0u8; 32], &key_agg.agg_x_only_pub_key(), &signature));
+            let signature = session.partial_sig_agg(&partial_sigs[1..]);
+            assert!(!schnorr_verify(&msg, &key_agg.agg_x_only_pub_key(), &signature));
+        }
+    }
+}
@@ -1,19 +1,16 @@
+use backend::native::{JacobianPoint, Point, Signature};
 use context::CONTEXT;
 use point::jacobian_point::generator_mul;
 use point::jacobian_point::jacobian_point_add;
 use point::jacobian_point::jacobian_point_mul;
-use point::jacobian_point::JacobianPoint;
 use point::point::point_add;
 use point::point::point_mul;
-use point::Point;
 use rand::thread_rng;
 use rand::Rng;
-use rug::Integer;
 use scalar::concat_and_hash;
 use scalar::ScalarN;
-use std::ops::{Add, Mul, Sub};
+use std::ops::{Add, Mul};
 use util::rug::integer_from_bytes;
-use util::signature::Signature;
 use Msg;
 
 /// The following functions are less efficient and kept here for reference to be run by benchmarks
@@ -25,7 +22,7 @@ pub fn schnorr_sign(msg: &Msg, sec_key: &ScalarN) -> Signature {
     let mut k = concat_and_hash(&sec_key_bytes, msg, &vec![]);
     let R = CONTEXT.G.clone().mul(&k);
     if !R.y.is_square() {
-        k = CONTEXT.n.clone().sub(&k);
+        k = k.negate();
     }
     let Rx = R.x.to_32_bytes();
     let dG = CONTEXT.G.clone().mul(sec_key).as_bytes();
@@ -44,13 +41,13 @@ pub fn schnorr_verify(msg: &Msg, pub_key: &Point, signature: &Signature) -> bool
     let signature_bytes = signature.as_bytes();
     let r = integer_from_bytes(&signature_bytes[..32]);
     let s = integer_from_bytes(&signature_bytes[32..]);
-    if r >= CONTEXT.p.0 || s >= CONTEXT.n.0 {
+    if r >= CONTEXT.p || s >= CONTEXT.n {
         // TODO Probably can't happen since ScalarN always < N
         return false;
     }
     let e = concat_and_hash(&signature_bytes[..32], &pub_key.as_bytes()[..], msg);
     let a = point_mul(CONTEXT.G.clone(), signature.s.clone());
-    let b = point_mul(pub_key.to_owned(), CONTEXT.n.clone().sub(&e));
+    let b = point_mul(pub_key.to_owned(), e.negate());
     let R = point_add(a, b);
 
     if R.is_none() {
@@ -100,14 +97,14 @@ pub fn schnorr_batch_verify(
             y,
         });
         let a = if i == 0 {
-            ScalarN(Integer::from(1))
+            ScalarN::from_u64(1)
         } else {
             rng.gen::<ScalarN>()
         };
         a_vec.push(a);
     }
 
-    let mut coeff = ScalarN(Integer::new());
+    let mut coeff = ScalarN::from_u64(0);
     let mut R_point_sum = None;
     let mut P_point_sum = None;
     for i in 0..messages.len() {
@@ -159,14 +156,14 @@ pub fn schnorr_jacobi_batch_verify(
             y,
         }));
         let a = if i == 0 {
-            ScalarN(Integer::from(1))
+            ScalarN::from_u64(1)
         } else {
             rng.gen::<ScalarN>()
         };
         a_vec.push(a);
     }
 
-    let mut coeff = ScalarN(Integer::new());
+    let mut coeff = ScalarN::from_u64(0);
     let mut R_point_sum = None;
     let mut P_point_sum = None;
     //Fail if (s1 + a2s2 + ... + ausu)G ≠ R1 + a2R2 + ... + auRu + e1P1 + (a2e2)P2 + ... + (aueu)Pu
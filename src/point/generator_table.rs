@@ -0,0 +1,169 @@
+use backend::native::{JacobianPoint, Point};
+use context::{compute_g_mul_table, CONTEXT, G_MUL_CACHE, G_MUL_WINDOW};
+use point::jacobian_point::window_digit;
+use point::{batch_normalize, jacobian_point_add, mixed_point_add};
+use scalar::ScalarN;
+use std::borrow::Cow;
+use std::mem;
+
+// Precomputed multiples of G for `generator_mul`, the caller picks the memory it can afford.
+// `windows` is the layout of `G_MUL_CACHE`: one row of 2^w points per window of w bits of the
+// scalar, a multiplication is an addition per window and no doubling. `comb` is the Lim-Lee comb:
+// the scalar is cut in w teeth of d = 256 / w bits and a single row of 2^w points holds every sum
+// of 2^(j*d) * G, a multiplication is d doublings and d additions, for tables of a few KB.
+
+/// How the points of a `GeneratorTable` are arranged
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Layout {
+    Windows,
+    Comb,
+}
+
+/// Multiples of G to multiply it, see `GeneratorTable::windows` and `GeneratorTable::comb`
+#[derive(Clone, Debug)]
+pub struct GeneratorTable {
+    layout: Layout,
+    window: usize,
+    // borrows `G_MUL_CACHE` instead of copying it
+    points: Cow<'static, [Point]>,
+}
+
+impl GeneratorTable {
+    /// ceil(256 / window) rows of 2^window points, window from 1 to 16 (16 is about 80MB in
+    /// memory), the built in table is shared when window is `G_MUL_WINDOW`
+    pub fn windows(window: usize) -> Self {
+        assert!((1..=16).contains(&window));
+        let points = if window == G_MUL_WINDOW {
+            Cow::Borrowed(&G_MUL_CACHE[..])
+        } else {
+            Cow::Owned(compute_g_mul_table(window))
+        };
+        GeneratorTable {
+            layout: Layout::Windows,
+            window,
+            points,
+        }
+    }
+
+    /// The lightweight comb of 2^teeth points, teeth from 1 to 16
+    pub fn comb(teeth: usize) -> Self {
+        assert!((1..=16).contains(&teeth));
+        let spacing = 256usize.div_ceil(teeth);
+        // the junk G at index 0 keeps the indexes of the sums
+        let mut sums = vec![CONTEXT.G_jacobian.clone()];
+        let mut tooth = CONTEXT.G_jacobian.clone();
+        for _ in 0..teeth {
+            // the sums with this tooth are the previous ones plus the tooth
+            let with_tooth: Vec<JacobianPoint> = sums
+                .iter()
+                .enumerate()
+                .map(|(i, sum)| match i {
+                    0 => tooth.clone(),
+                    _ => jacobian_point_add(Some(sum), Some(&tooth)).unwrap(),
+                })
+                .collect();
+            sums.extend(with_tooth);
+            for _ in 0..spacing {
+                tooth = tooth.double().unwrap();
+            }
+        }
+        GeneratorTable {
+            layout: Layout::Comb,
+            window: teeth,
+            points: Cow::Owned(batch_normalize(&sums)),
+        }
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// The bits of the windows or the teeth of the comb
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Number of precomputed points
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Memory of the points
+    pub fn size_in_bytes(&self) -> usize {
+        self.points.len() * mem::size_of::<Point>()
+    }
+
+    /// n * G, `None` for zero
+    pub fn mul(&self, n: &ScalarN) -> Option<JacobianPoint> {
+        let bytes = n.to_32_bytes();
+        let mut acc: Option<JacobianPoint> = None;
+        match self.layout {
+            Layout::Windows => {
+                for row in 0..256usize.div_ceil(self.window) {
+                    let digit = window_digit(&bytes, row, self.window);
+                    if digit != 0 {
+                        let point = self.points.get((row << self.window) + digit);
+                        acc = mixed_point_add(acc.as_ref(), point);
+                    }
+                }
+            }
+            Layout::Comb => {
+                let spacing = 256usize.div_ceil(self.window);
+                for i in (0..spacing).rev() {
+                    if acc.is_some() {
+                        acc = acc.unwrap().double();
+                    }
+                    let mut index = 0usize;
+                    for j in (0..self.window).rev() {
+                        index = (index << 1) | window_digit(&bytes, j * spacing + i, 1);
+                    }
+                    if index != 0 {
+                        acc = mixed_point_add(acc.as_ref(), self.points.get(index));
+                    }
+                }
+            }
+        }
+        acc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use point::generator_mul;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_tables() {
+        let mut rng = thread_rng();
+        let mut values = vec![
+            ScalarN::from_u64(0),
+            ScalarN::from_u64(1),
+            ScalarN::from_u64(1).negate(),
+        ];
+        for _ in 0..10 {
+            values.push(rng.gen());
+        }
+        let tables = [
+            GeneratorTable::windows(4),
+            GeneratorTable::windows(5),
+            GeneratorTable::windows(G_MUL_WINDOW),
+            GeneratorTable::comb(1),
+            GeneratorTable::comb(4),
+            GeneratorTable::comb(6),
+        ];
+        for table in tables.iter() {
+            for n in values.iter() {
+                assert_eq!(generator_mul(n), table.mul(n), "{:?} {}", table.layout(), table.window());
+            }
+        }
+        assert_eq!(16, GeneratorTable::comb(4).len());
+        assert_eq!(64 * 16, GeneratorTable::windows(4).len());
+        // the built in table is not copied
+        assert_eq!(G_MUL_CACHE.as_ptr(), GeneratorTable::windows(G_MUL_WINDOW).points.as_ptr());
+    }
+}
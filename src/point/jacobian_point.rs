@@ -1,51 +1,48 @@
-use context::CONTEXT;
-use context::G_MUL_CACHE;
-use num_bigint::BigUint;
-use num_traits::Num;
+use backend::{DefaultField, FieldOps, ScalarOps};
+use context::{CONTEXT, G_MUL_ROWS, G_MUL_WINDOW};
+use error::Error;
 use point::Point;
-use rug::Assign;
-use rug::Integer;
 use scalar::ScalarN;
 use scalar::ScalarP;
-use std::borrow::Borrow;
 use std::fmt;
-use std::ops::MulAssign;
-use std::ops::{Add, Mul, Sub};
+use std::ops::Add;
 
 #[derive(Clone, Debug)]
-pub struct JacobianPoint {
-    pub x: ScalarP,
-    pub y: ScalarP,
-    pub z: ScalarP,
+pub struct JacobianPoint<F = DefaultField> {
+    pub x: F,
+    pub y: F,
+    pub z: F,
 }
 
-impl fmt::Display for JacobianPoint {
+impl fmt::Display for JacobianPoint<ScalarP> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "({},{},{})", self.x, self.y, self.z)
     }
 }
 
-impl From<Point> for JacobianPoint {
-    fn from(p: Point) -> Self {
+impl<F: FieldOps> From<Point<F>> for JacobianPoint<F> {
+    fn from(p: Point<F>) -> Self {
         JacobianPoint {
             x: p.x,
             y: p.y,
-            z: ScalarP(Integer::from(1)),
+            z: F::from_u64(1),
         }
     }
 }
 
-impl PartialEq for JacobianPoint {
-    fn eq(&self, other: &JacobianPoint) -> bool {
+impl<F: FieldOps> PartialEq for JacobianPoint<F> {
+    fn eq(&self, other: &JacobianPoint<F>) -> bool {
         if self.x == other.x && self.y == other.y && self.z == other.z {
             return true;
         }
 
-        let u1 = self.x.clone().mul(&other.z.clone().pow(&CONTEXT.two));
-        let u2 = other.x.clone().mul(&self.z.clone().pow(&CONTEXT.two));
+        let self_z_pow2 = self.z.clone().mul(&self.z);
+        let other_z_pow2 = other.z.clone().mul(&other.z);
+        let u1 = self.x.clone().mul(&other_z_pow2);
+        let u2 = other.x.clone().mul(&self_z_pow2);
 
-        let s1 = self.y.clone().mul(&other.z.clone().pow(&CONTEXT.three));
-        let s2 = other.y.clone().mul(&self.z.clone().pow(&CONTEXT.three));
+        let s1 = self.y.clone().mul(&other_z_pow2.mul(&other.z));
+        let s2 = other.y.clone().mul(&self_z_pow2.mul(&self.z));
 
         if u1 == u2 && s1 == s2 {
             return true;
@@ -54,95 +51,92 @@ impl PartialEq for JacobianPoint {
         false
     }
 }
-impl Eq for JacobianPoint {}
+impl<F: FieldOps> Eq for JacobianPoint<F> {}
 
-impl JacobianPoint {
-    pub fn double(&self) -> Option<JacobianPoint> {
-        jacobian_point_double(&self)
+impl<F: FieldOps> JacobianPoint<F> {
+    pub fn double(&self) -> Option<JacobianPoint<F>> {
+        jacobian_point_double(self)
     }
-    pub fn normalize(self) -> JacobianPoint {
+    pub fn normalize(self) -> JacobianPoint<F> {
         JacobianPoint::from(Point::from(self))
     }
     pub fn as_bytes(self) -> [u8; 32] {
         Point::from(self).as_bytes()
     }
 
-    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
-        if bytes.len() != 33 {
-            return None;
-        }
-        Some(JacobianPoint::from(Point::from_bytes(bytes).unwrap()))
-    }
-
     pub fn as_uncompressed_bytes(self) -> [u8; 64] {
         Point::from(self).as_uncompressed_bytes()
     }
 
-    pub fn from_uncompressed_bytes(bytes: &[u8]) -> Option<Self> {
-        if bytes.len() != 64 {
-            return None;
-        }
-        Some(JacobianPoint::from(
-            Point::from_uncompressed_bytes(bytes).unwrap(),
-        ))
-    }
-
-    pub fn mul(&self, n: &ScalarN) -> Self {
+    pub fn mul<S: ScalarOps>(&self, n: &S) -> Self {
         jacobian_point_mul_wnaf(self, n, 5i8).unwrap()
     }
 
     pub fn negate(self) -> Self {
         JacobianPoint {
             x: self.x,
-            y: CONTEXT.p.clone() - &self.y,
+            y: self.y.negate(),
             z: self.z,
         }
     }
+}
+
+impl JacobianPoint<ScalarP> {
 
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Point::from_bytes(bytes).map(JacobianPoint::from)
+    }
+
+    pub fn from_uncompressed_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Point::from_uncompressed_bytes(bytes).map(JacobianPoint::from)
+    }
+
+    /// lambda times the point, (beta * x, y)
+    pub fn endomorphism(&self) -> Self {
+        JacobianPoint {
+            x: self.x.clone() * &CONTEXT.beta,
+            y: self.y.clone(),
+            z: self.z.clone(),
+        }
+    }
 }
 
-impl Add for JacobianPoint {
-    type Output = JacobianPoint;
+impl<F: FieldOps> Add for JacobianPoint<F> {
+    type Output = JacobianPoint<F>;
 
-    fn add(self, other: JacobianPoint) -> JacobianPoint {
+    fn add(self, other: JacobianPoint<F>) -> JacobianPoint<F> {
         jacobian_point_add(Some(&self), Some(&other)).unwrap()
     }
 }
 
-pub fn jacobian_point_double(p: &JacobianPoint) -> Option<JacobianPoint> {
-    if p.y.0 == 0 {
+pub fn jacobian_point_double<F: FieldOps>(p: &JacobianPoint<F>) -> Option<JacobianPoint<F>> {
+    if p.y.is_zero() {
         return None;
     }
+    let two = F::from_u64(2);
     let p_x_pow2 = p.x.clone().mul(&p.x);
     let p_y_pow2 = p.y.clone().mul(&p.y);
     let p_y_pow4 = p_y_pow2.clone().mul(&p_y_pow2);
-    let s = p_y_pow2.mul(&p.x).mul(&CONTEXT.four);
-    let m = p_x_pow2.mul(&CONTEXT.three);
-    let x = m.clone().mul(&m).sub(&s.clone().mul(&CONTEXT.two));
+    let s = p_y_pow2.mul(&p.x).mul(&F::from_u64(4));
+    let m = p_x_pow2.mul(&F::from_u64(3));
+    let x = m.clone().mul(&m).sub(&s.clone().mul(&two));
     let y = m
-        .mul(s.sub(&x).borrow())
-        .sub(p_y_pow4.mul(&CONTEXT.eight).borrow());
-    let z = CONTEXT.two.clone().mul(&p.y).mul(&p.z);
+        .mul(&s.sub(&x))
+        .sub(&p_y_pow4.mul(&F::from_u64(8)));
+    let z = two.mul(&p.y).mul(&p.z);
     Some(JacobianPoint { x, y, z })
 }
 
-pub fn mixed_point_add(p1: Option<&JacobianPoint>, p2: Option<&Point>) -> Option<JacobianPoint> {
+pub fn mixed_point_add<F: FieldOps>(p1: Option<&JacobianPoint<F>>, p2: Option<&Point<F>>) -> Option<JacobianPoint<F>> {
     match (p1, p2) {
         (None, None) => None,
         (Some(p1), None) => Some(p1.clone()),
         (None, Some(p2)) => Some(JacobianPoint::from(p2.clone())),
         (Some(p1), Some(p2)) => {
-            let mut p1_z_pow2 = Integer::with_capacity(512);
-            p1_z_pow2.assign(&p1.z.0);
-            p1_z_pow2.mul_assign(&p1.z.0);
-            let p1_z_pow2 = ScalarP::new(p1_z_pow2);
+            let p1_z_pow2 = p1.z.clone().mul(&p1.z);
 
             let u1 = &p1.x;
-
-            let mut u2 = Integer::with_capacity(512);
-            u2.assign(&p2.x.0);
-            u2.mul_assign(&p1_z_pow2.0);
-            let u2 = ScalarP::new(u2);
+            let u2 = p2.x.clone().mul(&p1_z_pow2);
 
             let s1 = &p1.y;
             let s2 = p1_z_pow2.mul(&p1.z).mul(&p2.y);
@@ -154,23 +148,23 @@ pub fn mixed_point_add(p1: Option<&JacobianPoint>, p2: Option<&Point>) -> Option
                     return None;
                 }
             }
-            let h = u2.sub(&u1);
+            let h = u2.sub(u1);
             let h_pow2 = h.clone().mul(&h);
             //println!("capacity {}", h_pow2.0.capacity());
 
             let h_pow3 = h_pow2.clone().mul(&h);
-            let r = s2.sub(&s1);
+            let r = s2.sub(s1);
             let r_pow2 = r.clone().mul(&r);
 
             let x3 = r_pow2
                 .sub(&h_pow3)
-                .sub(&u1.clone().mul(&CONTEXT.two).mul(&h_pow2));
+                .sub(&u1.clone().mul(&F::from_u64(2)).mul(&h_pow2));
 
-            let u1_mul_h_pow2 = h_pow2.mul(&u1);
+            let u1_mul_h_pow2 = h_pow2.mul(u1);
 
             let y3 = r
-                .mul(u1_mul_h_pow2.sub(&x3).borrow())
-                .sub(h_pow3.mul(&s1).borrow());
+                .mul(&u1_mul_h_pow2.sub(&x3))
+                .sub(&h_pow3.mul(s1));
 
             let z3 = h.mul(&p1.z);
             Some(JacobianPoint {
@@ -182,10 +176,10 @@ pub fn mixed_point_add(p1: Option<&JacobianPoint>, p2: Option<&Point>) -> Option
     }
 }
 
-pub fn jacobian_point_add(
-    p1: Option<&JacobianPoint>,
-    p2: Option<&JacobianPoint>,
-) -> Option<JacobianPoint> {
+pub fn jacobian_point_add<F: FieldOps>(
+    p1: Option<&JacobianPoint<F>>,
+    p2: Option<&JacobianPoint<F>>,
+) -> Option<JacobianPoint<F>> {
     match (p1, p2) {
         (None, None) => None,
         (Some(p1), None) => Some(p1.clone()),
@@ -214,10 +208,10 @@ pub fn jacobian_point_add(
             let r_pow2 = r.clone().mul(&r);
             let x3 = r_pow2
                 .sub(&h_pow3)
-                .sub(&u1.clone().mul(&CONTEXT.two).mul(&h_pow2));
+                .sub(&u1.clone().mul(&F::from_u64(2)).mul(&h_pow2));
             let y3 = r
-                .mul(u1.mul(&h_pow2).sub(&x3).borrow())
-                .sub(s1.mul(&h_pow3).borrow());
+                .mul(&u1.mul(&h_pow2).sub(&x3))
+                .sub(&s1.mul(&h_pow3));
             let z3 = h.mul(&p1.z).mul(&p2.z);
             Some(JacobianPoint {
                 x: x3,
@@ -228,21 +222,53 @@ pub fn jacobian_point_add(
     }
 }
 
-pub fn generator_mul(n: &ScalarN) -> Option<JacobianPoint> {
-    let mut acc: Option<JacobianPoint> = None;
-    let mut _junk: Option<JacobianPoint> = None;
-    let string_radix = n.0.to_string_radix(16);
-    let bi = BigUint::from_str_radix(&string_radix, 16).unwrap();
-    for (i, byte) in bi.to_bytes_le().iter().enumerate() {
-        let index = i * 256usize + usize::from(*byte);
-        let point = G_MUL_CACHE.get(index);
-        if *byte != 0u8 {
+// the digit of window bits of the big endian bytes at row, bits above 255 are zero
+pub(crate) fn window_digit(bytes: &[u8; 32], row: usize, window: usize) -> usize {
+    let mut digit = 0usize;
+    for i in (row * window..((row + 1) * window).min(256)).rev() {
+        digit = (digit << 1) | usize::from((bytes[31 - i / 8] >> (i % 8)) & 1);
+    }
+    digit
+}
+
+/// The affine points with a single inversion, Montgomery's trick
+pub fn batch_normalize<F: FieldOps>(points: &[JacobianPoint<F>]) -> Vec<Point<F>> {
+    let mut prefix = Vec::with_capacity(points.len());
+    let mut acc = F::from_u64(1);
+    for point in points.iter() {
+        prefix.push(acc.clone());
+        acc = acc * &point.z;
+    }
+    let mut inv = acc.inv();
+    let mut result = Vec::with_capacity(points.len());
+    for (point, prefix) in points.iter().zip(prefix.iter()).rev() {
+        let z_inv = inv.clone() * prefix;
+        inv = inv * &point.z;
+        let z_inv_pow2 = z_inv.clone() * &z_inv;
+        result.push(Point {
+            x: point.x.clone() * &z_inv_pow2,
+            y: point.y.clone() * &z_inv_pow2 * &z_inv,
+        });
+    }
+    result.reverse();
+    result
+}
+
+pub fn generator_mul<S: ScalarOps>(n: &S) -> Option<JacobianPoint<S::Field>> {
+    let cache = S::Field::g_mul_cache();
+    let bytes = n.to_32_bytes();
+    let mut acc: Option<JacobianPoint<S::Field>> = None;
+    let mut _junk: Option<JacobianPoint<S::Field>> = None;
+    for row in 0..G_MUL_ROWS {
+        let digit = window_digit(&bytes, row, G_MUL_WINDOW);
+        let point = cache.get((row << G_MUL_WINDOW) + digit);
+        if digit != 0 {
             acc = mixed_point_add(acc.as_ref(), point);
         } else {
             // the purpose of this arm is to try to achieve constant time
             // who knows if the compiler removes it, however you should not read this
-            // this lib is totally unsecure
-            _junk = mixed_point_add(acc.as_ref(), Some(&CONTEXT.G));
+            // this lib is totally unsecure, see the constant_time module
+            _junk = mixed_point_add(acc.as_ref(), cache.get(1));
         }
     }
     acc
@@ -251,30 +277,23 @@ pub fn generator_mul(n: &ScalarN) -> Option<JacobianPoint> {
 // https://en.wikipedia.org/wiki/Elliptic_curve_point_multiplication
 
 #[allow(non_snake_case)]
-pub fn jacobian_point_mul(P: &JacobianPoint, n: &ScalarN) -> Option<JacobianPoint> {
-    let mut exponent: Integer = Integer::from(1) << 255;
-    let mut acc: Option<JacobianPoint> = None;
+pub fn jacobian_point_mul(P: &JacobianPoint<ScalarP>, n: &ScalarN) -> Option<JacobianPoint<ScalarP>> {
+    let mut acc: Option<JacobianPoint<ScalarP>> = None;
 
-    loop {
+    for i in (0..256).rev() {
         if acc.is_some() {
             acc = acc.unwrap().double();
         }
-        let val: Integer = (&n.0 & &exponent).into();
-        if val != 0 {
+        if n.bit(i) {
             acc = jacobian_point_add(acc.as_ref(), Some(P));
         }
-        exponent = exponent >> 1;
-        if exponent == 0 {
-            break;
-        }
     }
     acc
 }
 
+// P, 3P, .. (2^(w-1) - 1)P preceded by their negations, the digit d of a wNAF is at (d + max) / 2
 #[allow(non_snake_case)]
-pub fn jacobian_point_mul_wnaf(P: &JacobianPoint, n: &ScalarN, w: i8) -> Option<JacobianPoint> {
-    assert!(w > 1 && w < 8);
-    let vec = n.to_owned().to_wnaf(w);
+pub(crate) fn wnaf_table<F: FieldOps>(P: &JacobianPoint<F>, w: i8) -> Vec<JacobianPoint<F>> {
     let times = 2i8.pow(w as u32 - 2);
     let mut positives = Vec::new();
     let mut prec = P.to_owned();
@@ -292,9 +311,21 @@ pub fn jacobian_point_mul_wnaf(P: &JacobianPoint, n: &ScalarN, w: i8) -> Option<
     for el in positives.iter() {
         precomputed.push(el.to_owned());
     }
+    precomputed
+}
+
+#[allow(non_snake_case)]
+pub fn jacobian_point_mul_wnaf<F: FieldOps, S: ScalarOps>(
+    P: &JacobianPoint<F>,
+    n: &S,
+    w: i8,
+) -> Option<JacobianPoint<F>> {
+    assert!(w > 1 && w < 8);
+    let vec = n.wnaf(w);
+    let precomputed = wnaf_table(P, w);
     let max = 2i8.pow(w as u32 - 1) - 1;
 
-    let mut acc: Option<JacobianPoint> = None;
+    let mut acc: Option<JacobianPoint<F>> = None;
 
     for el in vec.iter() {
         if acc.is_some() {
@@ -309,12 +340,61 @@ pub fn jacobian_point_mul_wnaf(P: &JacobianPoint, n: &ScalarN, w: i8) -> Option<
     acc
 }
 
+// GLV: n = k1 + k2 * lambda with k1 and k2 of about 128 bits, so n * P = k1 * P + k2 * lambda(P)
+// where lambda(x, y) = (beta * x, y) costs a field multiplication. The two wNAF are walked
+// together, half the doublings of `jacobian_point_mul_wnaf`.
+#[allow(non_snake_case)]
+pub fn jacobian_point_mul_glv(
+    P: &JacobianPoint<ScalarP>,
+    n: &ScalarN,
+    w: i8,
+) -> Option<JacobianPoint<ScalarP>> {
+    assert!(w > 1 && w < 8);
+    let (k1, k2) = n.split_lambda();
+    // a negative half is the positive one on the negated point
+    let signed = |k: ScalarN| {
+        if k.is_high() {
+            (k.negate().to_wnaf(w), P.clone().negate())
+        } else {
+            (k.to_wnaf(w), P.clone())
+        }
+    };
+    let (naf1, P1) = signed(k1);
+    let (naf2, P2) = signed(k2);
+
+    let table1 = wnaf_table(&P1, w);
+    let table2: Vec<JacobianPoint<ScalarP>> = wnaf_table(&P2, w).iter().map(JacobianPoint::endomorphism).collect();
+    let max = 2i8.pow(w as u32 - 1) - 1;
+
+    let len = naf1.len().max(naf2.len());
+    let mut acc: Option<JacobianPoint<ScalarP>> = None;
+
+    for i in 0..len {
+        if acc.is_some() {
+            acc = acc.unwrap().double();
+        }
+        // the shorter wNAF is aligned on the least significant digit
+        for (naf, table) in [(&naf1, &table1), (&naf2, &table2)].iter() {
+            if i + naf.len() < len {
+                continue;
+            }
+            let el = naf[i + naf.len() - len];
+            if el != 0i8 {
+                let index = (el + max) / 2;
+                acc = jacobian_point_add(acc.as_ref(), table.get(index as usize));
+            }
+        }
+    }
+    acc
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use context::CONTEXT;
     use point::point::point_add;
     use rand::prelude::*;
+    use scalar::ScalarElement;
 
     #[test]
     fn test_conversion() {
@@ -331,7 +411,7 @@ mod tests {
         let g3 = point_add(Some(CONTEXT.G.clone()), Some(g2.clone())).unwrap();
         let g3_jac = jacobian_point_add(Some(&j), Some(&g2_jac)).unwrap();
         assert_eq!(g3.clone(), Point::from(g3_jac));
-        let three = ScalarN(Integer::from(3));
+        let three = ScalarN::from_u64(3);
 
         let g3_jac = jacobian_point_mul(&j, &three).unwrap();
         assert_eq!(g3, Point::from(g3_jac.clone()));
@@ -358,10 +438,31 @@ mod tests {
         assert_eq!(b, Point::from(a));
     }
 
+    #[test]
+    fn test_mul_glv() {
+        let mut rng = thread_rng();
+        let mut values = vec![
+            ScalarN::from_u64(0),
+            ScalarN::from_u64(1),
+            ScalarN::from_u64(1).negate(),
+            ScalarN(ScalarElement::lambda()),
+        ];
+        for _ in 0..20 {
+            values.push(rng.gen());
+        }
+        let point = jacobian_point_mul(&CONTEXT.G_jacobian, &rng.gen()).unwrap();
+        for n in values.iter() {
+            let expected = jacobian_point_mul(&point, n);
+            for w in 2..8 {
+                assert_eq!(expected, jacobian_point_mul_glv(&point, n, w));
+            }
+        }
+    }
+
     #[test]
     fn test_mul_wnaf() {
         //jacobian_point_mul_4naf
-        let two = ScalarN(Integer::from(2));
+        let two = ScalarN::from_u64(2);
 
         let option = jacobian_point_mul_wnaf(&CONTEXT.G_jacobian, &two, 4i8);
         assert_eq!(CONTEXT.G_jacobian.clone().double(), option);
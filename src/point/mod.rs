@@ -1,5 +1,7 @@
+pub use self::generator_table::{GeneratorTable, Layout};
 pub use self::jacobian_point::*;
 pub use self::point::*;
 
+pub mod generator_table;
 pub mod jacobian_point;
 pub mod point;
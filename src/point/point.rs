@@ -1,69 +1,95 @@
+use backend::{DefaultField, FieldOps};
 use context::CONTEXT;
+use error::{check_length, Error};
 use point::JacobianPoint;
-use rug::Integer;
 use scalar::ScalarN;
 use scalar::ScalarP;
 use std::fmt;
 use std::ops::{Add, Mul, Sub};
-use util::rug::integer_from_bytes;
 
+/// (p + 1) / 4, the exponent of the square root since p = 3 mod 4
+const P_ADD1_DIV4: [u8; 32] = [
+    0x3F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xBF, 0xFF, 0xFF, 0x0C,
+];
+
+/// Affine point, the coordinates are the field of `backend::DefaultBackend` unless another is given
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
-pub struct Point {
-    pub x: ScalarP,
-    pub y: ScalarP,
+pub struct Point<F = DefaultField> {
+    pub x: F,
+    pub y: F,
+}
+
+/// x-only public key, the implicit y is the even one (BIP340)
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct NormalizedPoint<F = DefaultField> {
+    pub x: F,
+}
+
+impl NormalizedPoint<ScalarP> {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let x = ScalarP::try_from_bytes(bytes)?;
+        match Point::lift_x(&x) {
+            Some(point) => Ok(NormalizedPoint::from(point)),
+            None => Err(Error::NotOnCurve),
+        }
+    }
+
 }
 
-pub struct NormalizedPoint {
-    pub x: ScalarP,
+impl<F: FieldOps> NormalizedPoint<F> {
+    pub fn as_bytes(&self) -> [u8; 32] {
+        self.x.to_32_bytes()
+    }
 }
 
-impl From<NormalizedPoint> for Point {
-    fn from(n: NormalizedPoint) -> Self {
-        Point::from_bytes(&n.x.to_32_bytes()).unwrap()
+impl<F: FieldOps> From<NormalizedPoint<F>> for Point<F> {
+    fn from(n: NormalizedPoint<F>) -> Self {
+        Point::lift_x(&n.x).unwrap()
     }
 }
 
-impl From<&NormalizedPoint> for Point {
-    fn from(n: &NormalizedPoint) -> Self {
-        Point::from_bytes(&n.x.to_32_bytes()).unwrap()
+impl<F: FieldOps> From<&NormalizedPoint<F>> for Point<F> {
+    fn from(n: &NormalizedPoint<F>) -> Self {
+        Point::lift_x(&n.x).unwrap()
     }
 }
 
-impl From<Point> for NormalizedPoint {
-    fn from(p: Point) -> Self {
+impl<F: FieldOps> From<Point<F>> for NormalizedPoint<F> {
+    fn from(p: Point<F>) -> Self {
         NormalizedPoint{
             x: p.x
         }
     }
 }
 
-impl From<&Point> for NormalizedPoint {
-    fn from(p: &Point) -> Self {
+impl<F: FieldOps> From<&Point<F>> for NormalizedPoint<F> {
+    fn from(p: &Point<F>) -> Self {
         NormalizedPoint{
             x: p.x.clone()
         }
     }
 }
 
-impl fmt::Display for Point {
+impl fmt::Display for Point<ScalarP> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "({}, {})", self.x, self.y)
     }
 }
 
-impl Default for Point {
+impl Default for Point<ScalarP> {
     fn default() -> Self {
         Point {
-            x: ScalarP(Integer::new()),
-            y: ScalarP(Integer::new()),
+            x: ScalarP::from_u64(0),
+            y: ScalarP::from_u64(0),
         }
     }
 }
 
-impl From<JacobianPoint> for Point {
-    fn from(j: JacobianPoint) -> Self {
+impl<F: FieldOps> From<JacobianPoint<F>> for Point<F> {
+    fn from(j: JacobianPoint<F>) -> Self {
         //(X / Z^2, Y / Z^3).
-        if j.z.0 == 1 {
+        if j.z == F::from_u64(1) {
             return Point { x: j.x, y: j.y };
         }
         let z_pow2 = j.z.clone().mul(&j.z);
@@ -74,13 +100,13 @@ impl From<JacobianPoint> for Point {
     }
 }
 
-impl Point {
+impl<F: FieldOps> Point<F> {
     pub fn on_curve(&self) -> bool {
         let pow1 = self.y.clone().mul(&self.y);
         let pow2 = self.x.clone().mul(&self.x).mul(&self.x);
         let sub = pow1.sub(&pow2);
 
-        sub % &CONTEXT.p == CONTEXT.seven
+        sub == F::from_u64(7)
     }
 
     pub fn as_bytes(&self) -> [u8; 32] {
@@ -94,29 +120,76 @@ impl Point {
         res
     }
 
-    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
-        if bytes.len() != 32 {
+    /// SEC1 compressed encoding: 0x02 or 0x03 for even or odd y, followed by x
+    pub fn as_compressed_bytes(&self) -> [u8; 33] {
+        let mut res = [0u8; 33];
+        res[0] = if self.has_even_y() { 2 } else { 3 };
+        res[1..].copy_from_slice(&self.x.to_32_bytes()[..]);
+        res
+    }
+
+    /// Returns the point with the given x and even y, `None` if x is not on the curve
+    pub fn lift_x(x: &F) -> Option<Self> {
+        let y2 = x.clone().mul(x).mul(x).add(&F::from_u64(7));
+        let y = y2.pow_bytes(&P_ADD1_DIV4);
+        if y.clone().mul(&y) != y2 {
             return None;
         }
-        let x = ScalarP::new(integer_from_bytes(&bytes[..]));
+        let y = if y.is_even() { y } else { y.negate() };
+        Some(Point { x: x.to_owned(), y })
+    }
+
+    pub fn has_even_y(&self) -> bool {
+        self.y.is_even()
+    }
+
+    pub fn negate(self) -> Self {
+        Point {
+            x: self.x,
+            y: self.y.negate(),
+        }
+    }
+}
+
+impl Point<ScalarP> {
+    /// Lifts x to the point with a square y (2019 draft convention), see `lift_x` for the even y
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let x = ScalarP::try_from_bytes(bytes)?;
         let y2 = x.pow(&CONTEXT.three).add(&CONTEXT.seven);
 
         // in secp256k1 sqrt is equal to pow( (p-1)/4 )
         let y = y2.pow(&CONTEXT.p_add1_div4);
 
-        Some(Point { x, y })
+        let point = Point { x, y };
+        if !point.on_curve() {
+            return Err(Error::NotOnCurve);
+        }
+        Ok(point)
     }
 
-    pub fn from_uncompressed_bytes(bytes: &[u8]) -> Option<Self> {
-        if bytes.len() != 64 {
-            return None;
+    pub fn from_compressed_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        check_length(bytes, 33)?;
+        let x = ScalarP::try_from_bytes(&bytes[1..])?;
+        let point = Point::lift_x(&x).ok_or(Error::NotOnCurve)?;
+        match bytes[0] {
+            2 => Ok(point),
+            3 => Ok(point.negate()),
+            prefix => Err(Error::InvalidPrefix { found: prefix }),
         }
-        let x = ScalarP::new(integer_from_bytes(&bytes[..32]));
-        let y = ScalarP::new(integer_from_bytes(&bytes[32..]));
-        if x.0 == 0 && y.0 == 0 {
-            return None;
+    }
+
+    pub fn from_uncompressed_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        check_length(bytes, 64)?;
+        let x = ScalarP::try_from_bytes(&bytes[..32])?;
+        let y = ScalarP::try_from_bytes(&bytes[32..])?;
+        if x.is_zero() && y.is_zero() {
+            return Err(Error::PointAtInfinity);
+        }
+        let point = Point { x, y };
+        if !point.on_curve() {
+            return Err(Error::NotOnCurve);
         }
-        Some(Point { x, y })
+        Ok(point)
     }
 
     pub fn from_signature_x(x: &ScalarP) -> Self {
@@ -129,45 +202,35 @@ impl Point {
         Point { x: x.to_owned(), y }
     }
 
-    pub fn mul(self, n: &ScalarN) -> Point {
+    pub fn mul(self, n: &ScalarN) -> Point<ScalarP> {
         point_mul(self, n.to_owned()).unwrap()
     }
-
-    pub fn negate(self) -> Self {
-        Point {
-            x: self.x,
-            y: CONTEXT.p.clone() - &self.y,
-        }
-    }
 }
 
-impl Add for Point {
-    type Output = Point;
+impl Add for Point<ScalarP> {
+    type Output = Point<ScalarP>;
 
-    fn add(self, other: Point) -> <Self as Add<Point>>::Output {
+    fn add(self, other: Point<ScalarP>) -> Point<ScalarP> {
         point_add(Some(self), Some(other)).unwrap()
     }
 }
 
-pub fn point_mul(mut p: Point, n: ScalarN) -> Option<Point> {
-    let mut r: Option<Point> = None;
-    let mut n = n.0;
+pub fn point_mul(mut p: Point<ScalarP>, n: ScalarN) -> Option<Point<ScalarP>> {
+    let mut r: Option<Point<ScalarP>> = None;
+    let bits = (0..256).rev().find(|i| n.bit(*i)).map_or(0, |i| i + 1);
 
-    loop {
-        let (ris, rem) = n.div_rem(CONTEXT.two.0.clone());
-
-        if rem == 1 {
+    for i in 0..bits {
+        if n.bit(i) {
             r = point_add(r, Some(p.clone()));
         }
-        if ris == 0 {
-            return r;
+        if i + 1 < bits {
+            p = point_add(Some(p.clone()), Some(p)).unwrap();
         }
-        p = point_add(Some(p.clone()), Some(p)).unwrap();
-        n = ris;
     }
+    r
 }
 
-pub fn point_add(p1: Option<Point>, p2: Option<Point>) -> Option<Point> {
+pub fn point_add(p1: Option<Point<ScalarP>>, p2: Option<Point<ScalarP>>) -> Option<Point<ScalarP>> {
     match (p1, p2) {
         (None, None) => None,
         (Some(p1), None) => Some(p1.clone()),
@@ -207,62 +270,62 @@ mod tests {
     fn text_context_mul_and_add() {
         assert_eq!(
             "115792089237316195423570985008687907853269984665640564039457584007908834671663",
-            format!("{}", CONTEXT.p.0)
+            format!("{}", CONTEXT.p)
         );
         assert_eq!(
             "55066263022277343669578718895168534326250603453777594175500187360389116729240",
-            format!("{}", CONTEXT.G.x.0)
+            format!("{}", CONTEXT.G.x)
         );
         assert_eq!(
             "32670510020758816978083085130507043184471273380659243275938904335757337482424",
-            format!("{}", CONTEXT.G.y.0)
+            format!("{}", CONTEXT.G.y)
         );
 
         let g2 = point_add(Some(CONTEXT.G.clone()), Some(CONTEXT.G.clone())).unwrap();
         assert_eq!(
             "89565891926547004231252920425935692360644145829622209833684329913297188986597",
-            format!("{}", g2.x.0)
+            format!("{}", g2.x)
         );
         assert_eq!(
             "12158399299693830322967808612713398636155367887041628176798871954788371653930",
-            format!("{}", g2.y.0)
+            format!("{}", g2.y)
         );
 
-        let g2b = point_mul(CONTEXT.G.clone(), ScalarN(Integer::from(2u32))).unwrap();
+        let g2b = point_mul(CONTEXT.G.clone(), ScalarN::from_u64(2)).unwrap();
         assert_eq!(g2.x, g2b.x);
         assert_eq!(g2.y, g2b.y);
 
         let g3 = point_add(Some(CONTEXT.G.clone()), Some(g2.clone())).unwrap();
         assert_eq!(
             "25583027980570883691656905877401976406448868254816295069919888960541586679410",
-            format!("{}", g3.y.0)
+            format!("{}", g3.y)
         );
         assert_eq!(
             "112711660439710606056748659173929673102114977341539408544630613555209775888121",
-            format!("{}", g3.x.0)
+            format!("{}", g3.x)
         );
 
-        let g3b = point_mul(CONTEXT.G.clone(), ScalarN(Integer::from(3u32))).unwrap();
+        let g3b = point_mul(CONTEXT.G.clone(), ScalarN::from_u64(3)).unwrap();
         assert_eq!(
             "112711660439710606056748659173929673102114977341539408544630613555209775888121",
-            format!("{}", g3b.x.0)
+            format!("{}", g3b.x)
         );
 
-        let g3b = point_mul(CONTEXT.G.clone(), ScalarN(Integer::from(3u32))).unwrap();
+        let g3b = point_mul(CONTEXT.G.clone(), ScalarN::from_u64(3)).unwrap();
         assert_eq!(g3.x, g3b.x);
         assert_eq!(g3.y, g3b.y);
 
-        let g8675309 = point_mul(CONTEXT.G.clone(), ScalarN(Integer::from(8675309))).unwrap();
+        let g8675309 = point_mul(CONTEXT.G.clone(), ScalarN::from_u64(8675309)).unwrap();
         assert_eq!(
             "66641067246008511739397675128206923493293851901978595085468284019495272794983",
-            format!("{}", g8675309.x.0)
+            format!("{}", g8675309.x)
         );
         assert_eq!(
             "22882405661336615738255795181502754819791112635438119114432507482219105379189",
-            format!("{}", g8675309.y.0)
+            format!("{}", g8675309.y)
         );
 
-        assert!(&CONTEXT.p.0.is_odd());
+        assert!(&CONTEXT.p.is_odd());
     }
 
     #[test]
@@ -288,5 +351,14 @@ mod tests {
         let g_deserialized = Point::from_bytes(&x_bytes).unwrap();
         assert_eq!(&CONTEXT.G.x, &g_deserialized.x);
         assert_eq!(&CONTEXT.G.y, &g_deserialized.y);
+
+        let g_compressed = CONTEXT.G.as_compressed_bytes();
+        assert_eq!(g_compressed[0], 2);
+        assert_eq!(CONTEXT.G, Point::from_compressed_bytes(&g_compressed).unwrap());
+        let minus_g = CONTEXT.G.clone().negate();
+        assert_eq!(minus_g, Point::from_compressed_bytes(&minus_g.as_compressed_bytes()).unwrap());
+        let mut bad_prefix = g_compressed;
+        bad_prefix[0] = 4;
+        assert_eq!(Err(Error::InvalidPrefix { found: 4 }), Point::from_compressed_bytes(&bad_prefix));
     }
 }
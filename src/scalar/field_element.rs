@@ -0,0 +1,332 @@
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::ptr;
+
+// Field element mod p = 2^256 - 0x1000003D1 in 5 limbs of 52 bits (the last one 48 bits),
+// the same representation as field_5x52 of libsecp256k1. Products of two limbs fit in u128 and
+// the part above 2^256 folds back multiplied by 0x1000003D1, so nothing is allocated.
+// Unlike libsecp256k1 every operation returns a normalized element (limbs in range and value
+// lower than p), which costs a bit more but keeps equality and hashing on the limbs.
+
+const M52: u64 = 0xF_FFFF_FFFF_FFFF;
+const M48: u64 = 0xFFFF_FFFF_FFFF;
+const P0: u64 = 0xF_FFFE_FFFF_FC2F;
+/// 2^256 mod p
+const R256: u64 = 0x1_0000_03D1;
+/// 2^260 mod p
+const R260: u64 = 0x10_0000_3D10;
+const P_SUB2: [u8; 32] = [
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE, 0xFF, 0xFF, 0xFC, 0x2D,
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FieldElement {
+    n: [u64; 5],
+}
+
+impl fmt::Debug for FieldElement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FieldElement(")?;
+        for byte in self.to_32_bytes().iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        write!(f, ")")
+    }
+}
+
+// carries the limbs and subtracts p when needed, limbs must be lower than 2^63
+fn normalize(r: [u64; 5]) -> [u64; 5] {
+    let (mut t0, mut t1, mut t2, mut t3, mut t4) = (r[0], r[1], r[2], r[3], r[4]);
+
+    // fold what is above 2^256
+    let x = t4 >> 48;
+    t4 &= M48;
+    t0 += x * R256;
+    t1 += t0 >> 52;
+    t0 &= M52;
+    t2 += t1 >> 52;
+    t1 &= M52;
+    let mut m = t1;
+    t3 += t2 >> 52;
+    t2 &= M52;
+    m &= t2;
+    t4 += t3 >> 52;
+    t3 &= M52;
+    m &= t3;
+
+    // at most one more subtraction of p, when t4 overflowed or the value is in [p, 2^256)
+    let x = (t4 >> 48) | ((t4 == M48) as u64 & (m == M52) as u64 & (t0 >= P0) as u64);
+    t0 += x * R256;
+    t1 += t0 >> 52;
+    t0 &= M52;
+    t2 += t1 >> 52;
+    t1 &= M52;
+    t3 += t2 >> 52;
+    t2 &= M52;
+    t4 += t3 >> 52;
+    t3 &= M52;
+    t4 &= M48;
+    [t0, t1, t2, t3, t4]
+}
+
+impl FieldElement {
+    pub fn zero() -> Self {
+        FieldElement { n: [0; 5] }
+    }
+
+    pub fn one() -> Self {
+        FieldElement::from_u64(1)
+    }
+
+    pub fn from_u64(val: u64) -> Self {
+        FieldElement {
+            n: normalize([val & M52, val >> 52, 0, 0, 0]),
+        }
+    }
+
+    fn limbs_from_bytes(bytes: &[u8; 32]) -> [u64; 5] {
+        let mut w = [0u64; 4];
+        for (i, word) in w.iter_mut().enumerate() {
+            for byte in bytes[24 - 8 * i..32 - 8 * i].iter() {
+                *word = (*word << 8) | u64::from(*byte);
+            }
+        }
+        [
+            w[0] & M52,
+            ((w[0] >> 52) | (w[1] << 12)) & M52,
+            ((w[1] >> 40) | (w[2] << 24)) & M52,
+            ((w[2] >> 28) | (w[3] << 36)) & M52,
+            w[3] >> 16,
+        ]
+    }
+
+    /// Big endian bytes reduced mod p
+    pub fn from_bytes(bytes: &[u8; 32]) -> Self {
+        FieldElement {
+            n: normalize(FieldElement::limbs_from_bytes(bytes)),
+        }
+    }
+
+    /// Big endian bytes, `None` if they are not lower than p
+    pub fn from_bytes_checked(bytes: &[u8; 32]) -> Option<Self> {
+        let n = FieldElement::limbs_from_bytes(bytes);
+        let normalized = normalize(n);
+        if normalized == n {
+            Some(FieldElement { n })
+        } else {
+            None
+        }
+    }
+
+    pub fn to_32_bytes(&self) -> [u8; 32] {
+        let n = &self.n;
+        let w = [
+            n[0] | (n[1] << 52),
+            (n[1] >> 12) | (n[2] << 40),
+            (n[2] >> 24) | (n[3] << 28),
+            (n[3] >> 36) | (n[4] << 16),
+        ];
+        let mut result = [0u8; 32];
+        for (i, word) in w.iter().enumerate() {
+            result[24 - 8 * i..32 - 8 * i].copy_from_slice(&word.to_be_bytes());
+        }
+        result
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.n == [0; 5]
+    }
+
+    pub fn is_even(&self) -> bool {
+        self.n[0] & 1 == 0
+    }
+
+    pub fn square(&self) -> Self {
+        *self * *self
+    }
+
+    /// self^exponent with the exponent as big endian bytes
+    pub fn pow(&self, exponent: &[u8; 32]) -> Self {
+        let mut result = FieldElement::one();
+        for byte in exponent.iter() {
+            for i in (0..8).rev() {
+                result = result.square();
+                if (byte >> i) & 1 == 1 {
+                    result = result * *self;
+                }
+            }
+        }
+        result
+    }
+
+    /// Fermat inversion self^(p-2), zero for zero
+    pub fn inv(&self) -> Self {
+        self.pow(&P_SUB2)
+    }
+
+    /// a if choice is false, b if it is true, with a mask instead of a branch
+    pub fn conditional_select(a: &Self, b: &Self, choice: bool) -> Self {
+        // the volatile read hides the choice from the optimizer, which otherwise turns the mask
+        // back into a branch
+        let choice = unsafe { ptr::read_volatile(&choice) };
+        let mask = u64::from(choice).wrapping_neg();
+        let mut n = a.n;
+        for (x, y) in n.iter_mut().zip(b.n.iter()) {
+            *x ^= (*x ^ y) & mask;
+        }
+        FieldElement { n }
+    }
+
+    /// p - self if choice is true, `Neg` has no branches either
+    pub fn conditional_negate(&self, choice: bool) -> Self {
+        FieldElement::conditional_select(self, &-*self, choice)
+    }
+}
+
+// 2p, each limb is not lower than the limb of a normalized element
+const TWO_P: [u64; 5] = [2 * P0, 2 * M52, 2 * M52, 2 * M52, 2 * M48];
+
+impl Add for FieldElement {
+    type Output = FieldElement;
+
+    fn add(self, other: FieldElement) -> FieldElement {
+        let mut r = [0u64; 5];
+        for (i, el) in r.iter_mut().enumerate() {
+            *el = self.n[i] + other.n[i];
+        }
+        FieldElement { n: normalize(r) }
+    }
+}
+
+impl Neg for FieldElement {
+    type Output = FieldElement;
+
+    fn neg(self) -> FieldElement {
+        let mut r = [0u64; 5];
+        for (i, el) in r.iter_mut().enumerate() {
+            *el = TWO_P[i] - self.n[i];
+        }
+        FieldElement { n: normalize(r) }
+    }
+}
+
+impl Sub for FieldElement {
+    type Output = FieldElement;
+
+    fn sub(self, other: FieldElement) -> FieldElement {
+        let mut r = [0u64; 5];
+        for (i, el) in r.iter_mut().enumerate() {
+            *el = self.n[i] + TWO_P[i] - other.n[i];
+        }
+        FieldElement { n: normalize(r) }
+    }
+}
+
+impl Mul for FieldElement {
+    type Output = FieldElement;
+
+    fn mul(self, other: FieldElement) -> FieldElement {
+        let (a, b) = (&self.n, &other.n);
+        // schoolbook product, each column is lower than 5 * 2^104
+        let mut t = [0u128; 9];
+        for i in 0..5 {
+            for j in 0..5 {
+                t[i + j] += u128::from(a[i]) * u128::from(b[j]);
+            }
+        }
+        // 52 bits limbs, the product is lower than 2^512 so the last is lower than 2^44
+        let mut c = [0u64; 10];
+        let mut carry = 0u128;
+        for (k, el) in t.iter().enumerate() {
+            let v = el + carry;
+            c[k] = (v as u64) & M52;
+            carry = v >> 52;
+        }
+        c[9] = carry as u64;
+        // limb 5 + i has the weight 2^260 * 2^(52 * i)
+        let mut r = [0u64; 5];
+        let mut carry = 0u128;
+        for i in 0..4 {
+            let v = u128::from(c[i]) + u128::from(c[i + 5]) * u128::from(R260) + carry;
+            r[i] = (v as u64) & M52;
+            carry = v >> 52;
+        }
+        let v = u128::from(c[4]) + u128::from(c[9]) * u128::from(R260) + carry;
+        r[4] = (v as u64) & M48;
+        // what is above 2^256
+        let v = u128::from(r[0]) + (v >> 48) * u128::from(R256);
+        r[0] = (v as u64) & M52;
+        r[1] += (v >> 52) as u64;
+        FieldElement { n: normalize(r) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use context::CONTEXT;
+    use rand::prelude::*;
+    use rug::Integer;
+    use scalar::to_32_bytes;
+    use util::rug::integer_from_bytes;
+
+    fn to_integer(el: &FieldElement) -> Integer {
+        integer_from_bytes(&el.to_32_bytes())
+    }
+
+    fn random(rng: &mut ThreadRng) -> FieldElement {
+        FieldElement::from_bytes(&rng.gen())
+    }
+
+    // the rug backend is the reference
+    #[test]
+    fn test_against_rug() {
+        let mut rng = thread_rng();
+        let p = &CONTEXT.p;
+        for _ in 0..1000 {
+            let a = random(&mut rng);
+            let b = random(&mut rng);
+            let (x, y) = (to_integer(&a), to_integer(&b));
+            assert_eq!(to_integer(&(a + b)), Integer::from(&x + &y) % p);
+            assert_eq!(to_integer(&(a - b)), (Integer::from(&x - &y) + p) % p);
+            assert_eq!(to_integer(&(a * b)), Integer::from(&x * &y) % p);
+            assert_eq!(to_integer(&(-a)), Integer::from(p - &x) % p);
+            assert_eq!(a, FieldElement::from_bytes(&a.to_32_bytes()));
+        }
+        let a = random(&mut rng);
+        assert_eq!(FieldElement::one(), a * a.inv());
+        assert_eq!(
+            to_integer(&a.pow(&to_32_bytes(&Integer::from(12345)))),
+            to_integer(&a).pow_mod(&Integer::from(12345), p).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_edges() {
+        let p_sub1 = FieldElement::zero() - FieldElement::one();
+        assert_eq!(to_integer(&p_sub1), Integer::from(&CONTEXT.p - 1));
+        assert_eq!(FieldElement::zero(), p_sub1 + FieldElement::one());
+        assert_eq!(FieldElement::one(), p_sub1 * p_sub1);
+        assert_eq!(FieldElement::zero(), -FieldElement::zero());
+        assert_eq!(FieldElement::zero(), FieldElement::zero().inv());
+
+        // p and values up to 2^256 - 1 are reduced, or rejected by the checked version
+        let p = to_32_bytes(&CONTEXT.p);
+        assert_eq!(FieldElement::zero(), FieldElement::from_bytes(&p));
+        assert_eq!(None, FieldElement::from_bytes_checked(&p));
+        assert_eq!(Some(p_sub1), FieldElement::from_bytes_checked(&p_sub1.to_32_bytes()));
+        assert_eq!(FieldElement::from_u64(0x1_0000_03D0), FieldElement::from_bytes(&[0xFFu8; 32]));
+    }
+
+    #[test]
+    fn test_conditional() {
+        let a = random(&mut thread_rng());
+        let b = random(&mut thread_rng());
+        assert_eq!(a, FieldElement::conditional_select(&a, &b, false));
+        assert_eq!(b, FieldElement::conditional_select(&a, &b, true));
+        assert_eq!(a, a.conditional_negate(false));
+        assert_eq!(-a, a.conditional_negate(true));
+        assert_eq!(FieldElement::zero(), FieldElement::zero().conditional_negate(true));
+    }
+}
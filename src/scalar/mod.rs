@@ -1,11 +1,16 @@
+pub use self::field_element::FieldElement;
+pub use self::scalar_element::ScalarElement;
 pub use self::scalar_n::ScalarN;
 pub use self::scalar_p::ScalarP;
+use backend::ScalarOps;
 use crypto::digest::Digest;
 use crypto::sha2::Sha256;
 use data_encoding::HEXLOWER;
 use rug::Integer;
 use util::rug::integer_from_bytes;
 
+pub mod field_element;
+pub mod scalar_element;
 pub mod scalar_n;
 pub mod scalar_p;
 
@@ -19,7 +24,16 @@ pub fn sha256_BIPSchnorrDerive(input: &[u8]) -> Integer {
     tagged_sha256(b"BIPSchnorrDerive", input)
 }
 
+#[allow(non_snake_case)]
+pub fn sha256_BIP0340_aux(input: &[u8]) -> [u8; 32] {
+    tagged_hash(b"BIP0340/aux", input)
+}
+
 pub fn tagged_sha256(tag :&[u8], input: &[u8]) -> Integer {
+    integer_from_bytes(&tagged_hash(tag, input)[..])
+}
+
+pub fn tagged_hash(tag: &[u8], input: &[u8]) -> [u8; 32] {
     let mut hashed = [0u8; 32];
     let mut hasher = Sha256::new();
     let tag_hash = sha256(tag);
@@ -28,7 +42,7 @@ pub fn tagged_sha256(tag :&[u8], input: &[u8]) -> Integer {
     hasher.input(&tag_hash);
     hasher.input(input);
     hasher.result(&mut hashed);
-    integer_from_bytes(&hashed[..])
+    hashed
 }
 
 pub fn sha256(input: &[u8]) -> [u8; 32] {
@@ -60,6 +74,20 @@ pub fn concat_and_hash_BIPSchnorrDerive(a: &[u8], b: &[u8], c: &[u8]) -> ScalarN
     ScalarN::new(sha256_BIPSchnorrDerive(&vec))
 }
 
+/// Tagged hash of a || b || c reduced to a scalar of any backend, the BIP340 nonce and challenge
+pub fn concat_and_tagged_hash<S: ScalarOps>(tag: &[u8], a: &[u8], b: &[u8], c: &[u8]) -> S {
+    let mut vec = Vec::with_capacity(a.len() + b.len() + c.len());
+    vec.extend(a);
+    vec.extend(b);
+    vec.extend(c);
+    S::from_32_bytes(&tagged_hash(tag, &vec))
+}
+
+/// The BIP340 challenge e = hash(R || P || msg), every signature scheme of the crate uses this one
+#[allow(non_snake_case)]
+pub fn concat_and_hash_BIP0340_challenge<S: ScalarOps>(a: &[u8], b: &[u8], c: &[u8]) -> S {
+    concat_and_tagged_hash(b"BIP0340/challenge", a, b, c)
+}
 
 pub fn concat_and_hash(a: &[u8], b: &[u8], c: &[u8]) -> ScalarN {
     let mut vec = Vec::with_capacity(a.len() + b.len() + c.len());
@@ -70,7 +98,7 @@ pub fn concat_and_hash(a: &[u8], b: &[u8], c: &[u8]) -> ScalarN {
 }
 
 
-fn to_32_bytes(val: &Integer) -> [u8; 32] {
+pub(crate) fn to_32_bytes(val: &Integer) -> [u8; 32] {
     let mut string = val.to_string_radix(16);
     if string.len() % 2 == 1 {
         string = format!("0{}", string);
@@ -0,0 +1,527 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::ptr;
+
+// Scalar mod the group order n in 4 little endian limbs of 64 bits. Products are 512 bits and are
+// reduced with Barrett (HAC 14.42) using the precomputed MU = floor(2^512 / n), so there is no
+// division and nothing is allocated. Like `FieldElement` every operation returns a value lower
+// than n, so equality, ordering and hashing work on the limbs.
+
+const N: [u64; 4] = [
+    0xBFD2_5E8C_D036_4141,
+    0xBAAE_DCE6_AF48_A03B,
+    0xFFFF_FFFF_FFFF_FFFE,
+    0xFFFF_FFFF_FFFF_FFFF,
+];
+/// floor(2^512 / n), 257 bits
+const MU: [u64; 5] = [0x402D_A173_2FC9_BEC0, 0x4551_2319_50B7_5FC4, 1, 0, 1];
+const N_SUB2: [u8; 32] = [
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE,
+    0xBA, 0xAE, 0xDC, 0xE6, 0xAF, 0x48, 0xA0, 0x3B, 0xBF, 0xD2, 0x5E, 0x8C, 0xD0, 0x36, 0x41, 0x3F,
+];
+/// (n - 1) / 2
+const N_HALF: [u64; 4] = [
+    0xDFE9_2F46_681B_20A0,
+    0x5D57_6E73_57A4_501D,
+    0xFFFF_FFFF_FFFF_FFFF,
+    0x7FFF_FFFF_FFFF_FFFF,
+];
+/// Cube root of unity mod n, lambda * (x, y) = (beta * x, y) for every point
+const LAMBDA: [u64; 4] = [
+    0xDF02_967C_1B23_BD72,
+    0x122E_22EA_2081_6678,
+    0xA526_1C02_8812_645A,
+    0x5363_AD4C_C05C_30E0,
+];
+// The lattice (a1, b1), (a2, b2) of the GLV decomposition, b2 = a1 and g1, g2 are round(2^384 * b2 / n)
+// and round(2^384 * -b1 / n), see "Guide to Elliptic Curve Cryptography" algorithm 3.74
+const G1: [u64; 4] = [
+    0xE893_209A_45DB_B031,
+    0x3DAA_8A14_71E8_CA7F,
+    0xE86C_90E4_9284_EB15,
+    0x3086_D221_A7D4_6BCD,
+];
+const G2: [u64; 4] = [
+    0x1571_B4AE_8AC4_7F71,
+    0x2212_08AC_9DF5_06C6,
+    0x6F54_7FA9_0ABF_E4C4,
+    0xE443_7ED6_010E_8828,
+];
+const MINUS_B1: [u64; 4] = [0x6F54_7FA9_0ABF_E4C3, 0xE443_7ED6_010E_8828, 0, 0];
+/// -b2 mod n
+const MINUS_B2: [u64; 4] = [
+    0xD765_CDA8_3DB1_562C,
+    0x8A28_0AC5_0774_346D,
+    0xFFFF_FFFF_FFFF_FFFE,
+    0xFFFF_FFFF_FFFF_FFFF,
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScalarElement {
+    n: [u64; 4],
+}
+
+impl fmt::Debug for ScalarElement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ScalarElement(")?;
+        for byte in self.to_32_bytes().iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        write!(f, ")")
+    }
+}
+
+// compares from the most significant limb
+fn cmp_limbs(a: &[u64], b: &[u64]) -> Ordering {
+    for i in (0..a.len()).rev() {
+        match a[i].cmp(&b[i]) {
+            Ordering::Equal => continue,
+            other => return other,
+        }
+    }
+    Ordering::Equal
+}
+
+// a += b, returns the carry
+fn add_limbs(a: &mut [u64], b: &[u64]) -> bool {
+    let mut carry = false;
+    for (i, el) in a.iter_mut().enumerate() {
+        let (v, c1) = el.overflowing_add(*b.get(i).unwrap_or(&0));
+        let (v, c2) = v.overflowing_add(carry as u64);
+        *el = v;
+        carry = c1 | c2;
+    }
+    carry
+}
+
+// a -= b, returns the borrow
+fn sub_limbs(a: &mut [u64], b: &[u64]) -> bool {
+    let mut borrow = false;
+    for (i, el) in a.iter_mut().enumerate() {
+        let (v, b1) = el.overflowing_sub(*b.get(i).unwrap_or(&0));
+        let (v, b2) = v.overflowing_sub(borrow as u64);
+        *el = v;
+        borrow = b1 | b2;
+    }
+    borrow
+}
+
+// the product a * b truncated to the length of out, which must be zeroed
+fn mul_limbs(a: &[u64], b: &[u64], out: &mut [u64]) {
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0u128;
+        for (j, y) in b.iter().enumerate() {
+            if i + j >= out.len() {
+                break;
+            }
+            let v = u128::from(out[i + j]) + u128::from(*x) * u128::from(*y) + carry;
+            out[i + j] = v as u64;
+            carry = v >> 64;
+        }
+        if i + b.len() < out.len() {
+            out[i + b.len()] = carry as u64;
+        }
+    }
+}
+
+// round(a * b / 2^384), lower than 2^128 for the constants g1 and g2
+fn mul_shift_384(a: &[u64; 4], b: &[u64; 4]) -> [u64; 4] {
+    let mut x = [0u64; 8];
+    mul_limbs(a, b, &mut x);
+    let mut result = [x[6], x[7], 0, 0];
+    add_limbs(&mut result, &[x[5] >> 63]);
+    result
+}
+
+// r - n if r is not lower than n, without branches on the value, a fifth limb is a carry
+fn reduce_once(r: &mut [u64]) {
+    let mut t = [0u64; 5];
+    let t = &mut t[..r.len()];
+    t.copy_from_slice(r);
+    let borrow = sub_limbs(t, &N);
+    select_limbs(r, t, !borrow);
+}
+
+// a = b if choice, with a mask instead of a branch, see `FieldElement::conditional_select`
+fn select_limbs(a: &mut [u64], b: &[u64], choice: bool) {
+    let choice = unsafe { ptr::read_volatile(&choice) };
+    let mask = u64::from(choice).wrapping_neg();
+    for (x, y) in a.iter_mut().zip(b.iter()) {
+        *x ^= (*x ^ y) & mask;
+    }
+}
+
+// Barrett reduction of a 512 bits value
+fn reduce(x: &[u64; 8]) -> [u64; 4] {
+    // q3 = floor(floor(x / 2^192) * MU / 2^320) is at most 2 lower than floor(x / n)
+    let mut q2 = [0u64; 10];
+    mul_limbs(&x[3..], &MU, &mut q2);
+    // r = x - q3 * n mod 2^320
+    let mut q3n = [0u64; 5];
+    mul_limbs(&q2[5..], &N, &mut q3n);
+    let mut r = [0u64; 5];
+    r.copy_from_slice(&x[..5]);
+    sub_limbs(&mut r, &q3n);
+    // r < 3n
+    reduce_once(&mut r);
+    reduce_once(&mut r);
+    [r[0], r[1], r[2], r[3]]
+}
+
+impl ScalarElement {
+    pub fn zero() -> Self {
+        ScalarElement { n: [0; 4] }
+    }
+
+    pub fn one() -> Self {
+        ScalarElement::from_u64(1)
+    }
+
+    pub fn from_u64(val: u64) -> Self {
+        ScalarElement { n: [val, 0, 0, 0] }
+    }
+
+    fn limbs_from_bytes(bytes: &[u8; 32]) -> [u64; 4] {
+        let mut n = [0u64; 4];
+        for (i, limb) in n.iter_mut().enumerate() {
+            for byte in bytes[24 - 8 * i..32 - 8 * i].iter() {
+                *limb = (*limb << 8) | u64::from(*byte);
+            }
+        }
+        n
+    }
+
+    /// Big endian bytes reduced mod n
+    pub fn from_bytes(bytes: &[u8; 32]) -> Self {
+        let mut n = ScalarElement::limbs_from_bytes(bytes);
+        // 2^256 < 2n, one subtraction is enough
+        reduce_once(&mut n);
+        ScalarElement { n }
+    }
+
+    /// Big endian bytes, `None` if they are not lower than n
+    pub fn from_bytes_checked(bytes: &[u8; 32]) -> Option<Self> {
+        let n = ScalarElement::limbs_from_bytes(bytes);
+        match cmp_limbs(&n, &N) {
+            Ordering::Less => Some(ScalarElement { n }),
+            _ => None,
+        }
+    }
+
+    /// 64 big endian bytes reduced mod n, like a wide hash output
+    pub fn from_64_bytes(bytes: &[u8; 64]) -> Self {
+        let mut x = [0u64; 8];
+        for (i, limb) in x.iter_mut().enumerate() {
+            for byte in bytes[56 - 8 * i..64 - 8 * i].iter() {
+                *limb = (*limb << 8) | u64::from(*byte);
+            }
+        }
+        ScalarElement { n: reduce(&x) }
+    }
+
+    pub fn to_32_bytes(&self) -> [u8; 32] {
+        let mut result = [0u8; 32];
+        for (i, limb) in self.n.iter().enumerate() {
+            result[24 - 8 * i..32 - 8 * i].copy_from_slice(&limb.to_be_bytes());
+        }
+        result
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.n == [0; 4]
+    }
+
+    pub fn is_even(&self) -> bool {
+        self.n[0] & 1 == 0
+    }
+
+    /// Greater than (n - 1) / 2, the values whose negation is lower
+    pub fn is_high(&self) -> bool {
+        cmp_limbs(&self.n, &N_HALF) == Ordering::Greater
+    }
+
+    /// The bit at position i, 0 is the least significant
+    pub fn bit(&self, i: usize) -> bool {
+        (self.n[i / 64] >> (i % 64)) & 1 == 1
+    }
+
+    pub fn square(&self) -> Self {
+        *self * *self
+    }
+
+    /// self^exponent with the exponent as big endian bytes
+    pub fn pow(&self, exponent: &[u8; 32]) -> Self {
+        let mut result = ScalarElement::one();
+        for byte in exponent.iter() {
+            for i in (0..8).rev() {
+                result = result.square();
+                if (byte >> i) & 1 == 1 {
+                    result = result * *self;
+                }
+            }
+        }
+        result
+    }
+
+    /// Fermat inversion self^(n-2), zero for zero
+    pub fn inv(&self) -> Self {
+        self.pow(&N_SUB2)
+    }
+
+    /// a if choice is false, b if it is true, without branches on the values
+    pub fn conditional_select(a: &Self, b: &Self, choice: bool) -> Self {
+        let mut n = a.n;
+        select_limbs(&mut n, &b.n, choice);
+        ScalarElement { n }
+    }
+
+    /// n - self if choice is true, without branches, unlike `Neg` which tests for zero
+    pub fn conditional_negate(&self, choice: bool) -> Self {
+        let mut neg = N;
+        sub_limbs(&mut neg, &self.n);
+        // n - 0 must stay 0
+        let non_zero = (self.n[0] | self.n[1] | self.n[2] | self.n[3]) != 0;
+        let mut n = self.n;
+        select_limbs(&mut n, &neg, choice & non_zero);
+        ScalarElement { n }
+    }
+
+    /// lambda, the cube root of unity of the endomorphism
+    pub fn lambda() -> Self {
+        ScalarElement { n: LAMBDA }
+    }
+
+    /// (k1, k2) such that self = k1 + k2 * lambda, both k1 and k2 or their negations are lower
+    /// than 2^128
+    pub fn split_lambda(&self) -> (Self, Self) {
+        let c1 = ScalarElement { n: mul_shift_384(&self.n, &G1) };
+        let c2 = ScalarElement { n: mul_shift_384(&self.n, &G2) };
+        let k2 = c1 * ScalarElement { n: MINUS_B1 } + c2 * ScalarElement { n: MINUS_B2 };
+        let k1 = *self - k2 * ScalarElement { n: LAMBDA };
+        (k1, k2)
+    }
+
+    /// Width w non adjacent form, most significant digit first: every non zero digit is odd and
+    /// lower than 2^(w-1) in absolute value, and is followed by at least w-1 zeros
+    pub fn wnaf(&self, w: i8) -> Vec<i8> {
+        assert!(w > 1 && w < 8);
+        let window = 1i64 << w;
+        // one more limb, adding the negative digits back may carry above 2^256
+        let mut d = [self.n[0], self.n[1], self.n[2], self.n[3], 0];
+        let mut naf = Vec::with_capacity(257);
+        while d != [0; 5] {
+            if d[0] & 1 == 1 {
+                let mut digit = (d[0] & (window as u64 - 1)) as i64;
+                if digit >= window / 2 {
+                    digit -= window;
+                }
+                if digit > 0 {
+                    sub_limbs(&mut d, &[digit as u64]);
+                } else {
+                    add_limbs(&mut d, &[(-digit) as u64]);
+                }
+                naf.push(digit as i8);
+            } else {
+                naf.push(0i8);
+            }
+            for i in 0..4 {
+                d[i] = (d[i] >> 1) | (d[i + 1] << 63);
+            }
+            d[4] >>= 1;
+        }
+        naf.reverse();
+        naf
+    }
+}
+
+impl Ord for ScalarElement {
+    fn cmp(&self, other: &ScalarElement) -> Ordering {
+        cmp_limbs(&self.n, &other.n)
+    }
+}
+
+impl PartialOrd for ScalarElement {
+    fn partial_cmp(&self, other: &ScalarElement) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for ScalarElement {
+    type Output = ScalarElement;
+
+    fn add(self, other: ScalarElement) -> ScalarElement {
+        let mut r = [self.n[0], self.n[1], self.n[2], self.n[3], 0];
+        r[4] = add_limbs(&mut r[..4], &other.n) as u64;
+        reduce_once(&mut r);
+        ScalarElement {
+            n: [r[0], r[1], r[2], r[3]],
+        }
+    }
+}
+
+impl Neg for ScalarElement {
+    type Output = ScalarElement;
+
+    fn neg(self) -> ScalarElement {
+        if self.is_zero() {
+            return self;
+        }
+        let mut n = N;
+        sub_limbs(&mut n, &self.n);
+        ScalarElement { n }
+    }
+}
+
+impl Sub for ScalarElement {
+    type Output = ScalarElement;
+
+    fn sub(self, other: ScalarElement) -> ScalarElement {
+        let mut n = self.n;
+        let borrow = sub_limbs(&mut n, &other.n);
+        let mut plus_n = n;
+        add_limbs(&mut plus_n, &N);
+        select_limbs(&mut n, &plus_n, borrow);
+        ScalarElement { n }
+    }
+}
+
+impl Mul for ScalarElement {
+    type Output = ScalarElement;
+
+    fn mul(self, other: ScalarElement) -> ScalarElement {
+        let mut x = [0u64; 8];
+        mul_limbs(&self.n, &other.n, &mut x);
+        ScalarElement { n: reduce(&x) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use context::CONTEXT;
+    use rand::prelude::*;
+    use rug::Integer;
+    use scalar::to_32_bytes;
+    use util::rug::integer_from_bytes;
+
+    fn to_integer(el: &ScalarElement) -> Integer {
+        integer_from_bytes(&el.to_32_bytes())
+    }
+
+    fn random(rng: &mut ThreadRng) -> ScalarElement {
+        ScalarElement::from_bytes(&rng.gen())
+    }
+
+    // the rug backend is the reference
+    #[test]
+    fn test_against_rug() {
+        let mut rng = thread_rng();
+        let n = &CONTEXT.n;
+        for _ in 0..1000 {
+            let a = random(&mut rng);
+            let b = random(&mut rng);
+            let (x, y) = (to_integer(&a), to_integer(&b));
+            assert_eq!(to_integer(&(a + b)), Integer::from(&x + &y) % n);
+            assert_eq!(to_integer(&(a - b)), (Integer::from(&x - &y) + n) % n);
+            assert_eq!(to_integer(&(a * b)), Integer::from(&x * &y) % n);
+            assert_eq!(to_integer(&(-a)), Integer::from(n - &x) % n);
+            assert_eq!(a.cmp(&b), x.cmp(&y));
+            assert_eq!(a.is_high(), x > Integer::from(n / 2));
+
+            let mut wide = [0u8; 64];
+            rng.fill_bytes(&mut wide);
+            let expected = integer_from_bytes(&wide) % n;
+            assert_eq!(to_integer(&ScalarElement::from_64_bytes(&wide)), expected);
+        }
+        let a = random(&mut rng);
+        assert_eq!(ScalarElement::one(), a * a.inv());
+        assert_eq!(
+            to_integer(&a.pow(&to_32_bytes(&Integer::from(12345)))),
+            to_integer(&a).pow_mod(&Integer::from(12345), n).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_edges() {
+        let n_sub1 = ScalarElement::zero() - ScalarElement::one();
+        assert_eq!(to_integer(&n_sub1), Integer::from(&CONTEXT.n - 1));
+        assert_eq!(ScalarElement::zero(), n_sub1 + ScalarElement::one());
+        assert_eq!(ScalarElement::one(), n_sub1 * n_sub1);
+        assert_eq!(n_sub1 + n_sub1, -ScalarElement::from_u64(2));
+        assert_eq!(ScalarElement::zero(), -ScalarElement::zero());
+        assert_eq!(ScalarElement::zero(), ScalarElement::zero().inv());
+        assert!(n_sub1.is_high());
+        assert!(!(-n_sub1).is_high());
+
+        let n = to_32_bytes(&CONTEXT.n);
+        assert_eq!(ScalarElement::zero(), ScalarElement::from_bytes(&n));
+        assert_eq!(None, ScalarElement::from_bytes_checked(&n));
+        assert_eq!(Some(n_sub1), ScalarElement::from_bytes_checked(&n_sub1.to_32_bytes()));
+        let max = Integer::from(Integer::u_pow_u(2, 256)) - 1;
+        assert_eq!(to_integer(&ScalarElement::from_bytes(&[0xFFu8; 32])), max % &CONTEXT.n);
+        assert_eq!(ScalarElement::one(), ScalarElement::from_64_bytes(&{
+            let mut bytes = [0u8; 64];
+            bytes[32..].copy_from_slice(&n);
+            bytes[63] += 1;
+            bytes
+        }));
+    }
+
+    #[test]
+    fn test_wnaf() {
+        let mut rng = thread_rng();
+        for w in 2..8 {
+            let a = random(&mut rng);
+            let naf = a.wnaf(w);
+            let mut acc = Integer::new();
+            for digit in naf.iter() {
+                acc = acc * 2 + *digit;
+                assert!(*digit == 0 || (digit % 2 != 0 && i32::from(digit.abs()) < 1 << (w - 1)));
+            }
+            assert_eq!(acc, to_integer(&a));
+        }
+        // -1 is n - 1, which needs a digit above 2^256
+        let n_sub1 = -ScalarElement::one();
+        assert_eq!(257, n_sub1.wnaf(5).len());
+    }
+
+    #[test]
+    fn test_conditional() {
+        let a = random(&mut thread_rng());
+        let b = random(&mut thread_rng());
+        assert_eq!(a, ScalarElement::conditional_select(&a, &b, false));
+        assert_eq!(b, ScalarElement::conditional_select(&a, &b, true));
+        assert_eq!(a, a.conditional_negate(false));
+        assert_eq!(-a, a.conditional_negate(true));
+        assert_eq!(ScalarElement::zero(), ScalarElement::zero().conditional_negate(true));
+    }
+
+    #[test]
+    fn test_split_lambda() {
+        let lambda = ScalarElement::lambda();
+        assert_eq!(ScalarElement::one(), lambda * lambda * lambda);
+
+        let mut rng = thread_rng();
+        let mut values = vec![
+            ScalarElement::zero(),
+            ScalarElement::one(),
+            -ScalarElement::one(),
+            lambda,
+            -lambda,
+            ScalarElement { n: N_HALF },
+            ScalarElement { n: [0, 0, 1, 0] },
+        ];
+        for _ in 0..1000 {
+            values.push(random(&mut rng));
+        }
+        for a in values.iter() {
+            let (k1, k2) = a.split_lambda();
+            assert_eq!(*a, k1 + k2 * lambda);
+            for k in [k1, k2].iter() {
+                let abs = if k.is_high() { -*k } else { *k };
+                assert_eq!([0, 0], abs.n[2..]);
+            }
+        }
+    }
+}
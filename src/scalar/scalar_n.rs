@@ -1,37 +1,107 @@
 use super::to_32_bytes;
-use context::CONTEXT;
+use error::{check_length, Error};
 use rand::distributions::Distribution;
 use rand::distributions::Standard;
 use rand::Rng;
+use rug::ops::RemRounding;
 use rug::Integer;
-use std::borrow::Borrow;
+use scalar::ScalarElement;
+use std::cmp::Ordering;
 use std::fmt;
-use std::ops::DivAssign;
-use std::ops::SubAssign;
-use std::ops::{Add, Mul, Rem, Sub};
+use std::ops::{Add, Mul, Sub};
 use util::rug::integer_from_bytes;
 
+const N_HEX: &str = "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141";
+
+/// Scalar mod the group order n, backed by the stack allocated `ScalarElement`
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
-pub struct ScalarN(pub Integer);
+pub struct ScalarN(pub ScalarElement);
 
 impl fmt::Display for ScalarN {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.to_integer())
     }
 }
 
 impl ScalarN {
     pub fn new(val: Integer) -> Self {
-        match val < CONTEXT.n.0 {
-            true => ScalarN(val),
-            false => ScalarN(val.rem(&CONTEXT.n.0)), // TODO not sure if panic here
+        if val >= 0 && val.significant_bits() <= 256 {
+            return ScalarN(ScalarElement::from_bytes(&to_32_bytes(&val)));
         }
+        let n = Integer::from_str_radix(N_HEX, 16).unwrap();
+        ScalarN(ScalarElement::from_bytes(&to_32_bytes(&val.rem_euc(&n))))
+    }
+    pub fn from_u64(val: u64) -> Self {
+        ScalarN(ScalarElement::from_u64(val))
     }
     pub fn from_bytes(bytes: &[u8]) -> Self {
+        if bytes.len() == 32 {
+            let mut array = [0u8; 32];
+            array.copy_from_slice(bytes);
+            return ScalarN(ScalarElement::from_bytes(&array));
+        }
         Self::new(integer_from_bytes(bytes))
     }
+    /// Like `from_bytes` but requires 32 bytes and rejects values not lower than n instead of reducing
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        check_length(bytes, 32)?;
+        let mut array = [0u8; 32];
+        array.copy_from_slice(bytes);
+        ScalarElement::from_bytes_checked(&array)
+            .map(ScalarN)
+            .ok_or(Error::ScalarOutOfRange)
+    }
     pub fn to_32_bytes(&self) -> [u8; 32] {
-        to_32_bytes(&self.0)
+        self.0.to_32_bytes()
+    }
+    pub fn to_integer(&self) -> Integer {
+        integer_from_bytes(&self.to_32_bytes())
+    }
+    /// Multiplicative inverse mod n, zero for zero
+    pub fn inv(&self) -> Self {
+        ScalarN(self.0.inv())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    /// Greater than (n - 1) / 2
+    pub fn is_high(&self) -> bool {
+        self.0.is_high()
+    }
+
+    /// The bit at position i, 0 is the least significant
+    pub fn bit(&self, i: usize) -> bool {
+        self.0.bit(i)
+    }
+
+    /// n - self
+    pub fn negate(&self) -> Self {
+        ScalarN(-self.0)
+    }
+
+    /// n - self if choice is true, in constant time
+    pub fn conditional_negate(&self, choice: bool) -> Self {
+        ScalarN(self.0.conditional_negate(choice))
+    }
+
+    /// (k1, k2) of about 128 bits, or negative, such that self = k1 + k2 * lambda
+    pub fn split_lambda(&self) -> (ScalarN, ScalarN) {
+        let (k1, k2) = self.0.split_lambda();
+        (ScalarN(k1), ScalarN(k2))
+    }
+}
+
+impl Ord for ScalarN {
+    fn cmp(&self, other: &ScalarN) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl PartialOrd for ScalarN {
+    fn partial_cmp(&self, other: &ScalarN) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
@@ -39,29 +109,23 @@ impl Add for ScalarN {
     type Output = ScalarN;
 
     fn add(self, other: ScalarN) -> <Self as Add<ScalarN>>::Output {
-        ScalarN::new(self.0.add(other.0))
+        ScalarN(self.0 + other.0)
     }
 }
 
-impl<'a> Mul<&'a ScalarN> for ScalarN {
+impl Mul<&ScalarN> for ScalarN {
     type Output = ScalarN;
 
     fn mul(self, other: &ScalarN) -> ScalarN {
-        ScalarN::new(self.0.borrow().mul(&other.0).into())
+        ScalarN(self.0 * other.0)
     }
 }
 
-impl<'a> Sub<&'a ScalarN> for ScalarN {
+impl Sub<&ScalarN> for ScalarN {
     type Output = ScalarN;
 
     fn sub(self, other: &ScalarN) -> ScalarN {
-        let value = if self.0 > other.0 {
-            self.0.sub(&other.0)
-        } else {
-            self.0.add(&CONTEXT.p.0).sub(&other.0)
-        };
-
-        ScalarN::new(value)
+        ScalarN(self.0 - other.0)
     }
 }
 
@@ -70,82 +134,77 @@ impl Distribution<ScalarN> for Standard {
         let mut bytes = [0u8; 32];
         loop {
             rng.fill_bytes(&mut bytes);
-            let be = integer_from_bytes(&bytes);
-            if be < CONTEXT.n.0 {
-                return ScalarN::new(be);
+            if let Some(el) = ScalarElement::from_bytes_checked(&bytes) {
+                return ScalarN(el);
             }
         }
     }
 }
 
-/*
-   i ← 0
-   while (d > 0) do
-       if (d mod 2) = 1 then
-           di ← d mods 2w
-           d ← d − di
-       else
-           di = 0
-       d ← d/2
-       i ← i + 1
-   return (di−1, di-2, …, d0)
-*/
 impl ScalarN {
+    /// Width w non adjacent form, most significant digit first, see `ScalarElement::wnaf`
     pub fn to_wnaf(self, w: i8) -> Vec<i8> {
-        assert!(w > 1 && w < 8);
-
-        let mut d = self.0.clone();
-        let mut naf = Vec::with_capacity(256usize);
-        let two_raised_w = Integer::from(2i16.pow(w as u32));
-        let two_raised_w_sub1 = Integer::from(2i8.pow((w - 1) as u32));
-        while d != 0 {
-            if d.is_odd() {
-                let mods = mods(&d, &two_raised_w, &two_raised_w_sub1);
-                naf.push(mods.to_i8().unwrap());
-                d.sub_assign(mods);
-            } else {
-                naf.push(0i8);
-            }
-            d.div_assign(2i32);
-        }
-        naf.reverse();
-        naf
+        self.0.wnaf(w)
     }
 
     pub fn from_naf(v: Vec<i8>) -> Self {
-        let mut acc = Integer::new();
-        for (i, el) in v.iter().enumerate() {
-            if i > 0 {
-                acc = acc * 2;
-            }
-            acc = acc + (*el as i32);
+        let mut acc = ScalarN::from_u64(0);
+        for el in v.iter() {
+            acc = acc.clone() + acc;
+            let digit = ScalarN::from_u64(u64::from(el.unsigned_abs()));
+            acc = if *el < 0 { acc - &digit } else { acc + digit };
         }
-        ScalarN(acc)
-    }
-}
-
-/*
-   if (d mod 2w) >= 2w−1
-       return (d mod 2w) − 2w
-   else
-       return d mod 2w
-*/
-
-fn mods(d: &Integer, two_raised_w: &Integer, two_raised_w_sub1: &Integer) -> Integer {
-    let a: Integer = d.rem(two_raised_w).into();
-    match a >= *two_raised_w_sub1 {
-        true => a - two_raised_w,
-        false => a,
+        acc
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use context::CONTEXT;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_inv_and_sub() {
+        let a = ScalarN::from_u64(3);
+        let b = ScalarN::from_u64(5);
+        assert!(a.clone().mul(&a.inv()) == ScalarN::from_u64(1));
+        // 3 - 5 = n - 2
+        assert!(a.sub(&b).add(ScalarN::from_u64(2)).is_zero());
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(ScalarN::new(CONTEXT.n.clone()).is_zero());
+        assert_eq!(ScalarN::from_u64(3), ScalarN::new(Integer::from(&CONTEXT.n * 2) + 3));
+        assert_eq!(ScalarN::from_u64(3).negate(), ScalarN::new(Integer::from(-3)));
+        assert_eq!(Err(Error::ScalarOutOfRange), ScalarN::try_from_bytes(&to_32_bytes(&CONTEXT.n)));
+    }
+
+    // the previous rug based implementation is the reference
+    #[test]
+    fn test_against_rug() {
+        let mut rng = thread_rng();
+        let n = &CONTEXT.n;
+        for _ in 0..100 {
+            let a = rng.gen::<ScalarN>();
+            let b = rng.gen::<ScalarN>();
+            let (x, y) = (a.to_integer(), b.to_integer());
+            assert_eq!((a.clone() + b.clone()).to_integer(), Integer::from(&x + &y) % n);
+            assert_eq!((a.clone() - &b).to_integer(), (Integer::from(&x - &y) + n) % n);
+            assert_eq!((a.clone() * &b).to_integer(), Integer::from(&x * &y) % n);
+            assert_eq!(a.inv().to_integer(), x.clone().invert(n).unwrap());
+            assert_eq!(a.negate().to_integer(), Integer::from(n - &x));
+            assert_eq!(a, ScalarN::from_bytes(&a.to_32_bytes()));
+            for i in 0..256 {
+                assert_eq!(a.bit(i), x.get_bit(i as u32));
+            }
+        }
+    }
 
     #[test]
     fn test_naf() {
-        let n = ScalarN(Integer::from(7u32));
+        let n = ScalarN::from_u64(7);
         //println!("n: {:#018b}", n.0);
         //println!("{:?}", );
         let expected = [1i8, 0, 0, -1];
@@ -156,7 +215,7 @@ mod tests {
         assert_eq!(n, ScalarN::from_naf(naf));
 
         for i in 1..100u32 {
-            let n = ScalarN(Integer::from(i));
+            let n = ScalarN::from_u64(u64::from(i));
 
             assert_eq!(&n, &ScalarN::from_naf(n.clone().to_wnaf(7)));
             assert_eq!(&n, &ScalarN::from_naf(n.clone().to_wnaf(6)));
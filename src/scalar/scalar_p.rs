@@ -1,108 +1,112 @@
 use super::to_32_bytes;
 use context::CONTEXT;
+use error::{check_length, Error};
 use rand::distributions::Distribution;
 use rand::distributions::Standard;
 use rand::Rng;
+use rug::ops::RemRounding;
 use rug::Integer;
+use scalar::FieldElement;
 use std::fmt;
-use std::ops::{Add, Div, Mul, Rem, Sub};
+use std::ops::{Add, Mul, Sub};
 use util::rug::integer_from_bytes;
 
+const P_HEX: &str = "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F";
+
+/// Element of the field of the curve coordinates, backed by the stack allocated `FieldElement`
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
-pub struct ScalarP(pub Integer);
+pub struct ScalarP(pub FieldElement);
 
 impl fmt::Display for ScalarP {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.to_integer())
     }
 }
 
 impl ScalarP {
     pub fn new(val: Integer) -> Self {
-        match val < CONTEXT.p.0 {
-            true => ScalarP(val),
-            false => ScalarP(val.rem(&CONTEXT.p.0)), // TODO not sure if panic here
+        if val >= 0 && val.significant_bits() <= 256 {
+            return ScalarP(FieldElement::from_bytes(&to_32_bytes(&val)));
         }
+        // can't use CONTEXT.p, the context is built with this function
+        let p = Integer::from_str_radix(P_HEX, 16).unwrap();
+        ScalarP(FieldElement::from_bytes(&to_32_bytes(&val.rem_euc(&p))))
+    }
+    pub fn from_u64(val: u64) -> Self {
+        ScalarP(FieldElement::from_u64(val))
     }
     pub fn from_bytes(bytes: &[u8]) -> Self {
+        if bytes.len() == 32 {
+            let mut array = [0u8; 32];
+            array.copy_from_slice(bytes);
+            return ScalarP(FieldElement::from_bytes(&array));
+        }
         Self::new(integer_from_bytes(bytes))
     }
+    /// Like `from_bytes` but requires 32 bytes and rejects values not lower than p instead of reducing
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        check_length(bytes, 32)?;
+        let mut array = [0u8; 32];
+        array.copy_from_slice(bytes);
+        FieldElement::from_bytes_checked(&array)
+            .map(ScalarP)
+            .ok_or(Error::FieldElementOutOfRange)
+    }
     pub fn to_32_bytes(&self) -> [u8; 32] {
-        to_32_bytes(&self.0)
+        self.0.to_32_bytes()
+    }
+    pub fn to_integer(&self) -> Integer {
+        integer_from_bytes(&self.to_32_bytes())
     }
     pub fn pow(&self, n: &ScalarP) -> Self {
-        /*if self.0 == BigUint::one() {
-            return ScalarP(BigUint::one());
-        }*/
-        ScalarP(self.0.clone().pow_mod(&n.0, &CONTEXT.p.0).unwrap())
+        ScalarP(self.0.pow(&n.0.to_32_bytes()))
     }
     pub fn inv(&self) -> Self {
-        ScalarP(
-            self.0
-                .clone()
-                .pow_mod(&CONTEXT.p_sub2.0, &CONTEXT.p.0)
-                .unwrap(),
-        )
+        ScalarP(self.0.inv())
     }
 
     pub fn is_square(&self) -> bool {
-        self.pow(&CONTEXT.p_sub1_div2).0 == 1
+        self.pow(&CONTEXT.p_sub1_div2).0 == FieldElement::one()
     }
 
-}
-impl<'a> Sub<&'a ScalarP> for ScalarP {
-    type Output = ScalarP;
-
-    fn sub(self, other: &ScalarP) -> ScalarP {
-        let value = if self.0 > other.0 {
-            self.0.sub(&other.0)
-        } else {
-            self.0.add(&CONTEXT.p.0).sub(&other.0)
-        };
-
-        ScalarP::new(value)
+    pub fn is_even(&self) -> bool {
+        self.0.is_even()
     }
-}
 
-impl<'a> Add<&'a ScalarP> for ScalarP {
-    type Output = ScalarP;
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
 
-    fn add(self, other: &ScalarP) -> ScalarP {
-        ScalarP::new(self.0.add(&other.0))
+    /// p - self
+    pub fn negate(&self) -> Self {
+        ScalarP(-self.0)
     }
-}
-impl<'a> Mul<&'a ScalarP> for ScalarP {
-    type Output = ScalarP;
 
-    fn mul(self, other: &ScalarP) -> ScalarP {
-        ScalarP::new(self.0.mul(&other.0))
+    /// p - self if choice is true, in constant time
+    pub fn conditional_negate(&self, choice: bool) -> Self {
+        ScalarP(self.0.conditional_negate(choice))
     }
 }
-
-/*
-impl<'a, 'b> Mul<&'b ScalarP> for &'a ScalarP {
+impl<'a> Sub<&'a ScalarP> for ScalarP {
     type Output = ScalarP;
 
-    fn mul(self, other: &'b ScalarP) -> ScalarP {
-        ScalarP::new((&other.0 * &self.0).into() )
+    fn sub(self, other: &ScalarP) -> ScalarP {
+        ScalarP(self.0 - other.0)
     }
 }
-*/
-
-//TODO add mul u32
 
-impl<'a> Rem<&'a ScalarP> for ScalarP {
+impl<'a> Add<&'a ScalarP> for ScalarP {
     type Output = ScalarP;
 
-    fn rem(self, other: &ScalarP) -> ScalarP {
-        ScalarP(self.0.rem(&other.0))
+    fn add(self, other: &ScalarP) -> ScalarP {
+        ScalarP(self.0 + other.0)
     }
 }
-impl<'a> Div<&'a ScalarP> for ScalarP {
+impl<'a> Mul<&'a ScalarP> for ScalarP {
     type Output = ScalarP;
 
-    fn div(self, other: &ScalarP) -> ScalarP {
-        ScalarP::new(self.0.div(&other.0))
+    fn mul(self, other: &ScalarP) -> ScalarP {
+        ScalarP(self.0 * other.0)
     }
 }
 
@@ -111,9 +115,8 @@ impl Distribution<ScalarP> for Standard {
         let mut bytes = [0u8; 32];
         loop {
             rng.fill_bytes(&mut bytes);
-            let be = integer_from_bytes(&bytes);
-            if be < CONTEXT.p.0 {
-                return ScalarP::new(be);
+            if let Some(el) = FieldElement::from_bytes_checked(&bytes) {
+                return ScalarP(el);
             }
         }
     }
@@ -122,11 +125,10 @@ impl Distribution<ScalarP> for Standard {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use context::CONTEXT;
 
     #[test]
     fn test_inv() {
-        assert!(CONTEXT.G.x.clone().inv().mul(&CONTEXT.G.x).0 == 1);
+        assert!(CONTEXT.G.x.clone().inv().mul(&CONTEXT.G.x) == ScalarP::from_u64(1));
     }
 
     #[test]
@@ -137,4 +139,14 @@ mod tests {
 
         assert_eq!(c, Integer::from(2u32));
     }
+
+    #[test]
+    fn test_new() {
+        assert_eq!(ScalarP::from_u64(0), ScalarP::new(CONTEXT.p.clone()));
+        assert_eq!(ScalarP::from_u64(3), ScalarP::new(Integer::from(&CONTEXT.p * 2) + 3));
+        assert_eq!(ScalarP::from_u64(3).negate(), ScalarP::new(Integer::from(-3)));
+        assert_eq!(CONTEXT.p_sub1_div2.to_integer(), Integer::from(&CONTEXT.p - 1) / 2);
+        // -1 is not a square, p = 3 mod 4
+        assert!(CONTEXT.G.y.is_square() != CONTEXT.G.y.negate().is_square());
+    }
 }
@@ -0,0 +1,177 @@
+use backend::native::{JacobianPoint, NormalizedPoint, Point, Signature};
+use error::Error;
+use point::{generator_mul, jacobian_point_add};
+use scalar::tagged_hash;
+use scalar::ScalarN;
+use std::ops::Add;
+use schnorr_sign;
+
+// BIP341 key tweaking https://github.com/bitcoin/bips/blob/master/bip-0341.mediawiki
+// The output key Q = P + t*G with t = hashTapTweak(P || merkle_root) commits to the script tree,
+// without a script tree the merkle root is omitted. Q is x-only, the parity of its y goes in the
+// control block of script path spends.
+
+pub fn tap_tweak_hash(internal_key: &NormalizedPoint, merkle_root: Option<&[u8; 32]>) -> [u8; 32] {
+    let mut input = internal_key.as_bytes().to_vec();
+    if let Some(merkle_root) = merkle_root {
+        input.extend(&merkle_root[..]);
+    }
+    tagged_hash(b"TapTweak", &input)
+}
+
+/// The tweak as a scalar, fails if the hash is not lower than n
+pub fn tap_tweak(internal_key: &NormalizedPoint, merkle_root: Option<&[u8; 32]>) -> Result<ScalarN, Error> {
+    ScalarN::try_from_bytes(&tap_tweak_hash(internal_key, merkle_root))
+}
+
+/// Returns the x-only output key and its parity, `true` when y is odd
+#[allow(non_snake_case)]
+pub fn taproot_output_key(
+    internal_key: &NormalizedPoint,
+    merkle_root: Option<&[u8; 32]>,
+) -> Result<(NormalizedPoint, bool), Error> {
+    let P = Point::lift_x(&internal_key.x).ok_or(Error::NotOnCurve)?;
+    let t = tap_tweak(internal_key, merkle_root)?;
+    let Q = jacobian_point_add(Some(&JacobianPoint::from(P)), generator_mul(&t).as_ref())
+        .ok_or(Error::PointAtInfinity)?;
+    let Q = Point::from(Q);
+    let parity = !Q.has_even_y();
+    Ok((NormalizedPoint::from(Q), parity))
+}
+
+/// Checks that `output_key` with the given parity commits to the internal key and merkle root,
+/// the check a script path spend does with the data of the control block
+pub fn verify_taproot_commitment(
+    output_key: &NormalizedPoint,
+    parity: bool,
+    internal_key: &NormalizedPoint,
+    merkle_root: Option<&[u8; 32]>,
+) -> bool {
+    match taproot_output_key(internal_key, merkle_root) {
+        Ok((expected_key, expected_parity)) => expected_key == *output_key && expected_parity == parity,
+        Err(_) => false,
+    }
+}
+
+/// The secret key of the output key: the internal secret key, negated if its point has odd y,
+/// plus the tweak
+#[allow(non_snake_case)]
+pub fn taproot_tweak_sec_key(sec_key: &ScalarN, merkle_root: Option<&[u8; 32]>) -> Result<ScalarN, Error> {
+    let P = Point::from(generator_mul(sec_key).ok_or(Error::ScalarOutOfRange)?);
+    let sec_key_even = if P.has_even_y() {
+        sec_key.clone()
+    } else {
+        sec_key.negate()
+    };
+    let t = tap_tweak(&NormalizedPoint::from(P), merkle_root)?;
+    let tweaked = sec_key_even.add(t);
+    if tweaked.is_zero() {
+        return Err(Error::PointAtInfinity);
+    }
+    Ok(tweaked)
+}
+
+/// Key path spend: a BIP340 signature valid for the output key
+pub fn taproot_sign(
+    msg: &[u8],
+    sec_key: &ScalarN,
+    merkle_root: Option<&[u8; 32]>,
+    aux_rand: &[u8; 32],
+) -> Result<Signature, Error> {
+    let tweaked = taproot_tweak_sec_key(sec_key, merkle_root)?;
+    Ok(schnorr_sign(msg, &tweaked, aux_rand))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_encoding::HEXLOWER;
+    use rand::prelude::*;
+    use schnorr_verify;
+
+    fn hex(string: &str) -> Vec<u8> {
+        HEXLOWER.decode(string.as_bytes()).unwrap()
+    }
+
+    fn hex_32(string: &str) -> [u8; 32] {
+        let mut result = [0u8; 32];
+        result.copy_from_slice(&hex(string));
+        result
+    }
+
+    // scriptPubKey section of https://github.com/bitcoin/bips/blob/master/bip-0341/wallet-test-vectors.json
+    #[test]
+    fn test_output_key_vectors() {
+        let vectors = [
+            (
+                "d6889cb081036e0faefa3a35157ad71086b123b2b144b649798b494c300a961d",
+                None,
+                "b86e7be8f39bab32a6f2c0443abbc210f0edac0e2c53d501b36b64437d9c6c70",
+                "53a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343",
+            ),
+            (
+                "187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27",
+                Some("5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21"),
+                "cbd8679ba636c1110ea247542cfbd964131a6be84f873f7f3b62a777528ed001",
+                "147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3",
+            ),
+            (
+                "ee4fe085983462a184015d1f782d6a5f8b9c2b60130aff050ce221ecf3786592",
+                Some("6c2dc106ab816b73f9d07e3cd1ef2c8c1256f519748e0813e4edd2405d277bef"),
+                "9e0517edc8259bb3359255400b23ca9507f2a91cd1e4250ba068b4eafceba4a9",
+                "712447206d7a5238acc7ff53fbe94a3b64539ad291c7cdbc490b7577e4b17df5",
+            ),
+        ];
+        for (internal_key, merkle_root, tweak, output_key) in vectors.iter() {
+            let internal_key = NormalizedPoint::from_bytes(&hex(internal_key)).unwrap();
+            let merkle_root = merkle_root.map(hex_32);
+            assert_eq!(tap_tweak_hash(&internal_key, merkle_root.as_ref()), hex_32(tweak));
+            let (key, parity) = taproot_output_key(&internal_key, merkle_root.as_ref()).unwrap();
+            assert_eq!(key.as_bytes(), hex_32(output_key));
+            assert!(verify_taproot_commitment(&key, parity, &internal_key, merkle_root.as_ref()));
+            assert!(!verify_taproot_commitment(&key, !parity, &internal_key, merkle_root.as_ref()));
+            assert!(!verify_taproot_commitment(&key, parity, &internal_key, Some(&[0u8; 32])));
+        }
+        // the control blocks of vector 1 and 3 start with 0xc1 and 0xc0
+        let internal_key = NormalizedPoint::from_bytes(&hex(vectors[1].0)).unwrap();
+        let merkle_root = hex_32(vectors[1].1.unwrap());
+        assert!(taproot_output_key(&internal_key, Some(&merkle_root)).unwrap().1);
+        let internal_key = NormalizedPoint::from_bytes(&hex(vectors[2].0)).unwrap();
+        let merkle_root = hex_32(vectors[2].1.unwrap());
+        assert!(!taproot_output_key(&internal_key, Some(&merkle_root)).unwrap().1);
+    }
+
+    // keyPathSpending section of the same file, input 0
+    #[test]
+    fn test_key_path_vector() {
+        let sec_key = ScalarN::from_bytes(&hex("6b973d88838f27366ed61c9ad6367663045cb456e28335c109e30717ae0c6baa"));
+        let tweaked = taproot_tweak_sec_key(&sec_key, None).unwrap();
+        assert_eq!(
+            tweaked.to_32_bytes(),
+            hex_32("2405b971772ad26915c8dcdf10f238753a9b837e5f8e6a86fd7c0cce5b7296d9")
+        );
+        let sig_hash = hex("2514a6272f85cfa0f45eb907fcb0d121b808ed37c6ea160a5a9046ed5526d555");
+        let signature = taproot_sign(&sig_hash, &sec_key, None, &[0u8; 32]).unwrap();
+        assert_eq!(
+            signature.as_bytes(),
+            hex("ed7c1647cb97379e76892be0cacff57ec4a7102aa24296ca39af7541246d8ff14d38958d4cc1e2e478e4d4a764bbfd835b16d4e314b72937b29833060b87276c")
+        );
+    }
+
+    #[test]
+    fn test_taproot_sign() {
+        let mut rng = thread_rng();
+        for _ in 0..8 {
+            let sec_key = rng.gen::<ScalarN>();
+            let internal_key = NormalizedPoint::from(Point::from(generator_mul(&sec_key).unwrap()));
+            let merkle_root = rng.gen::<[u8; 32]>();
+            let msg = rng.gen::<[u8; 32]>();
+            for merkle_root in [None, Some(&merkle_root)].iter() {
+                let (output_key, _) = taproot_output_key(&internal_key, *merkle_root).unwrap();
+                let signature = taproot_sign(&msg, &sec_key, *merkle_root, &rng.gen()).unwrap();
+                assert!(schnorr_verify(&msg, &output_key, &signature));
+                assert!(!schnorr_verify(&msg, &internal_key, &signature));
+            }
+        }
+    }
+}
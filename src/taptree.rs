@@ -0,0 +1,355 @@
+use backend::native::NormalizedPoint;
+use error::{check_length, Error};
+use scalar::tagged_hash;
+use taproot::{taproot_output_key, verify_taproot_commitment};
+
+// BIP341 script trees https://github.com/bitcoin/bips/blob/master/bip-0341.mediawiki
+// Leaves hash as hashTapLeaf(leaf_version || compact_size(script) || script), branches as
+// hashTapBranch of the two children sorted lexicographically, so a path is just the list of
+// sibling hashes from the leaf up to the root.
+
+/// Leaf version of BIP342 tapscript
+pub const TAPSCRIPT_LEAF_VERSION: u8 = 0xc0;
+
+/// The control block can't have more than 128 hashes in the path
+pub const MAX_PATH_LEN: usize = 128;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TapLeaf {
+    pub leaf_version: u8,
+    pub script: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TapTree {
+    Leaf(TapLeaf),
+    Branch(Box<TapTree>, Box<TapTree>),
+}
+
+/// What a script path spend reveals besides the script, serialized as
+/// (leaf_version | parity) || internal key || path
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ControlBlock {
+    pub leaf_version: u8,
+    /// `true` if the output key has odd y
+    pub parity: bool,
+    pub internal_key: NormalizedPoint,
+    pub path: Vec<[u8; 32]>,
+}
+
+fn compact_size(len: usize) -> Vec<u8> {
+    let len = len as u64;
+    if len < 0xfd {
+        vec![len as u8]
+    } else if len <= 0xffff {
+        let mut vec = vec![0xfd];
+        vec.extend(&(len as u16).to_le_bytes()[..]);
+        vec
+    } else if len <= 0xffff_ffff {
+        let mut vec = vec![0xfe];
+        vec.extend(&(len as u32).to_le_bytes()[..]);
+        vec
+    } else {
+        let mut vec = vec![0xff];
+        vec.extend(&len.to_le_bytes()[..]);
+        vec
+    }
+}
+
+pub fn tap_branch_hash(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut input = first.to_vec();
+    input.extend(&second[..]);
+    tagged_hash(b"TapBranch", &input)
+}
+
+impl TapLeaf {
+    pub fn new(leaf_version: u8, script: Vec<u8>) -> Result<Self, Error> {
+        if leaf_version & 1 == 1 {
+            return Err(Error::InvalidLeafVersion { found: leaf_version });
+        }
+        Ok(TapLeaf { leaf_version, script })
+    }
+
+    pub fn hash(&self) -> [u8; 32] {
+        let mut input = vec![self.leaf_version];
+        input.extend(compact_size(self.script.len()));
+        input.extend(&self.script);
+        tagged_hash(b"TapLeaf", &input)
+    }
+}
+
+impl TapTree {
+    /// Builds a balanced tree, the first half of the leaves goes on the left
+    pub fn from_leaves(leaves: &[(u8, Vec<u8>)]) -> Result<Self, Error> {
+        match leaves.len() {
+            0 => Err(Error::EmptyTree),
+            1 => {
+                let (leaf_version, script) = leaves[0].clone();
+                Ok(TapTree::Leaf(TapLeaf::new(leaf_version, script)?))
+            }
+            len => {
+                let (left, right) = leaves.split_at(len / 2);
+                Ok(TapTree::Branch(
+                    Box::new(TapTree::from_leaves(left)?),
+                    Box::new(TapTree::from_leaves(right)?),
+                ))
+            }
+        }
+    }
+
+    pub fn merkle_root(&self) -> [u8; 32] {
+        match self {
+            TapTree::Leaf(leaf) => leaf.hash(),
+            TapTree::Branch(left, right) => tap_branch_hash(&left.merkle_root(), &right.merkle_root()),
+        }
+    }
+
+    /// Leaves from left to right
+    pub fn leaves(&self) -> Vec<&TapLeaf> {
+        match self {
+            TapTree::Leaf(leaf) => vec![leaf],
+            TapTree::Branch(left, right) => {
+                let mut leaves = left.leaves();
+                leaves.extend(right.leaves());
+                leaves
+            }
+        }
+    }
+
+    // paths from every leaf to the root, in the order of `leaves`
+    fn paths(&self) -> Vec<Vec<[u8; 32]>> {
+        match self {
+            TapTree::Leaf(_) => vec![vec![]],
+            TapTree::Branch(left, right) => {
+                let left_hash = left.merkle_root();
+                let right_hash = right.merkle_root();
+                let mut paths = left.paths();
+                for path in paths.iter_mut() {
+                    path.push(right_hash);
+                }
+                for mut path in right.paths() {
+                    path.push(left_hash);
+                    paths.push(path);
+                }
+                paths
+            }
+        }
+    }
+
+    /// Every leaf with the control block spending it, in the order of `leaves`
+    pub fn control_blocks(&self, internal_key: &NormalizedPoint) -> Result<Vec<(TapLeaf, ControlBlock)>, Error> {
+        let (_, parity) = taproot_output_key(internal_key, Some(&self.merkle_root()))?;
+        Ok(self
+            .leaves()
+            .into_iter()
+            .zip(self.paths())
+            .map(|(leaf, path)| {
+                let control_block = ControlBlock {
+                    leaf_version: leaf.leaf_version,
+                    parity,
+                    internal_key: internal_key.clone(),
+                    path,
+                };
+                (leaf.clone(), control_block)
+            })
+            .collect())
+    }
+}
+
+impl ControlBlock {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let path_len = bytes.len().saturating_sub(33) / 32;
+        check_length(bytes, 33 + 32 * path_len.min(MAX_PATH_LEN))?;
+        let path = bytes[33..]
+            .chunks(32)
+            .map(|chunk| {
+                let mut hash = [0u8; 32];
+                hash.copy_from_slice(chunk);
+                hash
+            })
+            .collect();
+        Ok(ControlBlock {
+            leaf_version: bytes[0] & 0xfe,
+            parity: bytes[0] & 1 == 1,
+            internal_key: NormalizedPoint::from_bytes(&bytes[1..33])?,
+            path,
+        })
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut vec = Vec::with_capacity(33 + 32 * self.path.len());
+        vec.push(self.leaf_version | self.parity as u8);
+        vec.extend(&self.internal_key.as_bytes()[..]);
+        for hash in self.path.iter() {
+            vec.extend(&hash[..]);
+        }
+        vec
+    }
+
+    /// The merkle root obtained hashing the leaf of `script` up along the path
+    pub fn merkle_root(&self, script: &[u8]) -> Result<[u8; 32], Error> {
+        let leaf = TapLeaf::new(self.leaf_version, script.to_vec())?;
+        Ok(self.path.iter().fold(leaf.hash(), |acc, hash| tap_branch_hash(&acc, hash)))
+    }
+
+    /// Checks that `script` is committed in `output_key`, as done when spending via script path
+    pub fn verify(&self, output_key: &NormalizedPoint, script: &[u8]) -> bool {
+        if self.path.len() > MAX_PATH_LEN {
+            return false;
+        }
+        let merkle_root = match self.merkle_root(script) {
+            Ok(merkle_root) => merkle_root,
+            Err(_) => return false,
+        };
+        verify_taproot_commitment(output_key, self.parity, &self.internal_key, Some(&merkle_root))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_encoding::HEXLOWER;
+
+    fn hex(string: &str) -> Vec<u8> {
+        HEXLOWER.decode(string.as_bytes()).unwrap()
+    }
+
+    fn leaf(leaf_version: u8, script: &str) -> TapTree {
+        TapTree::Leaf(TapLeaf::new(leaf_version, hex(script)).unwrap())
+    }
+
+    fn check(
+        internal_key: &str,
+        tree: &TapTree,
+        leaf_hashes: &[&str],
+        merkle_root: &str,
+        output_key: &str,
+        control_blocks: &[&str],
+    ) {
+        let internal_key = NormalizedPoint::from_bytes(&hex(internal_key)).unwrap();
+        let output_key = NormalizedPoint::from_bytes(&hex(output_key)).unwrap();
+        let leaves = tree.leaves();
+        assert_eq!(leaves.len(), leaf_hashes.len());
+        for (leaf, leaf_hash) in leaves.iter().zip(leaf_hashes.iter()) {
+            assert_eq!(leaf.hash().to_vec(), hex(leaf_hash));
+        }
+        assert_eq!(tree.merkle_root().to_vec(), hex(merkle_root));
+
+        let spends = tree.control_blocks(&internal_key).unwrap();
+        for ((leaf, control_block), expected) in spends.iter().zip(control_blocks.iter()) {
+            assert_eq!(control_block.as_bytes(), hex(expected));
+            assert_eq!(&ControlBlock::from_bytes(&hex(expected)).unwrap(), control_block);
+            assert!(control_block.verify(&output_key, &leaf.script));
+            assert!(!control_block.verify(&output_key, &[0x51]));
+            let mut wrong_parity = control_block.clone();
+            wrong_parity.parity = !wrong_parity.parity;
+            assert!(!wrong_parity.verify(&output_key, &leaf.script));
+        }
+    }
+
+    // scriptPubKey section of https://github.com/bitcoin/bips/blob/master/bip-0341/wallet-test-vectors.json
+    #[test]
+    fn test_single_leaf() {
+        check(
+            "187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27",
+            &leaf(192, "20d85a959b0290bf19bb89ed43c916be835475d013da4b362117393e25a48229b8ac"),
+            &["5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21"],
+            "5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21",
+            "147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3",
+            &["c1187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27"],
+        );
+    }
+
+    #[test]
+    fn test_two_leaves() {
+        let tree = TapTree::from_leaves(&[
+            (192, hex("20387671353e273264c495656e27e39ba899ea8fee3bb69fb2a680e22093447d48ac")),
+            (250, hex("06424950333431")),
+        ])
+        .unwrap();
+        check(
+            "ee4fe085983462a184015d1f782d6a5f8b9c2b60130aff050ce221ecf3786592",
+            &tree,
+            &[
+                "8ad69ec7cf41c2a4001fd1f738bf1e505ce2277acdcaa63fe4765192497f47a7",
+                "f224a923cd0021ab202ab139cc56802ddb92dcfc172b9212261a539df79a112a",
+            ],
+            "6c2dc106ab816b73f9d07e3cd1ef2c8c1256f519748e0813e4edd2405d277bef",
+            "712447206d7a5238acc7ff53fbe94a3b64539ad291c7cdbc490b7577e4b17df5",
+            &[
+                "c0ee4fe085983462a184015d1f782d6a5f8b9c2b60130aff050ce221ecf3786592f224a923cd0021ab202ab139cc56802ddb92dcfc172b9212261a539df79a112a",
+                "faee4fe085983462a184015d1f782d6a5f8b9c2b60130aff050ce221ecf37865928ad69ec7cf41c2a4001fd1f738bf1e505ce2277acdcaa63fe4765192497f47a7",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_three_leaves() {
+        let tree = TapTree::from_leaves(&[
+            (192, hex("2072ea6adcf1d371dea8fba1035a09f3d24ed5a059799bae114084130ee5898e69ac")),
+            (192, hex("202352d137f2f3ab38d1eaa976758873377fa5ebb817372c71e2c542313d4abda8ac")),
+            (192, hex("207337c0dd4253cb86f2c43a2351aadd82cccb12a172cd120452b9bb8324f2186aac")),
+        ])
+        .unwrap();
+        let explicit = TapTree::Branch(
+            Box::new(leaf(192, "2072ea6adcf1d371dea8fba1035a09f3d24ed5a059799bae114084130ee5898e69ac")),
+            Box::new(TapTree::Branch(
+                Box::new(leaf(192, "202352d137f2f3ab38d1eaa976758873377fa5ebb817372c71e2c542313d4abda8ac")),
+                Box::new(leaf(192, "207337c0dd4253cb86f2c43a2351aadd82cccb12a172cd120452b9bb8324f2186aac")),
+            )),
+        );
+        assert_eq!(tree, explicit);
+        check(
+            "e0dfe2300b0dd746a3f8674dfd4525623639042569d829c7f0eed9602d263e6f",
+            &tree,
+            &[
+                "2645a02e0aac1fe69d69755733a9b7621b694bb5b5cde2bbfc94066ed62b9817",
+                "ba982a91d4fc552163cb1c0da03676102d5b7a014304c01f0c77b2b8e888de1c",
+                "9e31407bffa15fefbf5090b149d53959ecdf3f62b1246780238c24501d5ceaf6",
+            ],
+            "ccbd66c6f7e8fdab47b3a486f59d28262be857f30d4773f2d5ea47f7761ce0e2",
+            "91b64d5324723a985170e4dc5a0f84c041804f2cd12660fa5dec09fc21783605",
+            &[
+                "c0e0dfe2300b0dd746a3f8674dfd4525623639042569d829c7f0eed9602d263e6fffe578e9ea769027e4f5a3de40732f75a88a6353a09d767ddeb66accef85e553",
+                "c0e0dfe2300b0dd746a3f8674dfd4525623639042569d829c7f0eed9602d263e6f9e31407bffa15fefbf5090b149d53959ecdf3f62b1246780238c24501d5ceaf62645a02e0aac1fe69d69755733a9b7621b694bb5b5cde2bbfc94066ed62b9817",
+                "c0e0dfe2300b0dd746a3f8674dfd4525623639042569d829c7f0eed9602d263e6fba982a91d4fc552163cb1c0da03676102d5b7a014304c01f0c77b2b8e888de1c2645a02e0aac1fe69d69755733a9b7621b694bb5b5cde2bbfc94066ed62b9817",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_invalid_leaves() {
+        assert_eq!(Err(Error::InvalidLeafVersion { found: 0xc1 }), TapLeaf::new(0xc1, vec![0x51]));
+        assert_eq!(Err(Error::EmptyTree), TapTree::from_leaves(&[]));
+        assert_eq!(
+            Err(Error::InvalidLeafVersion { found: 0xc1 }),
+            TapTree::from_leaves(&[(0xc0, vec![0x51]), (0xc1, vec![0x52])])
+        );
+    }
+
+    #[test]
+    fn test_control_block_bytes() {
+        let bytes = hex("c1187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27");
+        assert_eq!(
+            Err(Error::InvalidLength { expected: 33, found: 32 }),
+            ControlBlock::from_bytes(&bytes[..32])
+        );
+        let mut long = bytes.clone();
+        long.extend(&[0u8; 40][..]);
+        assert_eq!(
+            Err(Error::InvalidLength { expected: 65, found: 73 }),
+            ControlBlock::from_bytes(&long)
+        );
+        let mut too_deep = bytes.clone();
+        too_deep.extend(vec![0u8; 32 * (MAX_PATH_LEN + 1)]);
+        assert!(ControlBlock::from_bytes(&too_deep).is_err());
+
+        let mut odd = ControlBlock::from_bytes(&bytes).unwrap();
+        assert_eq!(odd.leaf_version, 0xc0);
+        odd.leaf_version = 0xc1;
+        assert_eq!(Err(Error::InvalidLeafVersion { found: 0xc1 }), odd.merkle_root(&[0x51]));
+        assert!(!odd.verify(&odd.internal_key, &[0x51]));
+        assert_eq!(compact_size(300), vec![0xfd, 0x2c, 0x01]);
+    }
+}
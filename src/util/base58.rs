@@ -0,0 +1,100 @@
+use error::Error;
+use scalar::sha256;
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+pub fn encode(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|el| **el == 0).count();
+    // little endian base 58 digits
+    let mut digits: Vec<u8> = Vec::with_capacity(bytes.len() * 138 / 100 + 1);
+    for byte in bytes[zeros..].iter() {
+        let mut carry = u32::from(*byte);
+        for digit in digits.iter_mut() {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let mut string = String::with_capacity(zeros + digits.len());
+    for _ in 0..zeros {
+        string.push('1');
+    }
+    for digit in digits.iter().rev() {
+        string.push(ALPHABET[*digit as usize] as char);
+    }
+    string
+}
+
+pub fn decode(string: &str) -> Result<Vec<u8>, Error> {
+    let zeros = string.bytes().take_while(|el| *el == b'1').count();
+    // little endian bytes
+    let mut bytes: Vec<u8> = Vec::with_capacity(string.len());
+    for c in string.bytes().skip(zeros) {
+        let mut carry = ALPHABET
+            .iter()
+            .position(|el| *el == c)
+            .ok_or(Error::InvalidBase58)? as u32;
+        for byte in bytes.iter_mut() {
+            carry += u32::from(*byte) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    let mut result = vec![0u8; zeros];
+    result.extend(bytes.iter().rev());
+    Ok(result)
+}
+
+fn checksum(bytes: &[u8]) -> [u8; 4] {
+    let hash = sha256(&sha256(bytes));
+    let mut result = [0u8; 4];
+    result.copy_from_slice(&hash[..4]);
+    result
+}
+
+/// Base58 of the bytes followed by the first 4 bytes of their double SHA256
+pub fn encode_check(bytes: &[u8]) -> String {
+    let mut vec = bytes.to_vec();
+    vec.extend(&checksum(bytes)[..]);
+    encode(&vec)
+}
+
+pub fn decode_check(string: &str) -> Result<Vec<u8>, Error> {
+    let mut bytes = decode(string)?;
+    if bytes.len() < 4 {
+        return Err(Error::InvalidChecksum);
+    }
+    let payload_len = bytes.len() - 4;
+    if checksum(&bytes[..payload_len])[..] != bytes[payload_len..] {
+        return Err(Error::InvalidChecksum);
+    }
+    bytes.truncate(payload_len);
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base58() {
+        assert_eq!(encode(b""), "");
+        assert_eq!(encode(b"hello world"), "StV1DL6CwTryKyV");
+        assert_eq!(encode(&[0, 0, 0x28, 0x7f, 0xb4, 0xcd]), "11233QC4");
+        assert_eq!(decode("11233QC4").unwrap(), vec![0, 0, 0x28, 0x7f, 0xb4, 0xcd]);
+        assert_eq!(decode("StV1DL6CwTryKyV").unwrap(), b"hello world".to_vec());
+        assert_eq!(Err(Error::InvalidBase58), decode("0OIl"));
+
+        let encoded = encode_check(b"schnorr");
+        assert_eq!(decode_check(&encoded).unwrap(), b"schnorr".to_vec());
+        assert_eq!(Err(Error::InvalidChecksum), decode_check(&encoded[1..]));
+    }
+}
@@ -1,5 +1,5 @@
-pub mod apint;
+pub mod base58;
+pub mod multi_scalar_mul;
 pub mod rug;
 pub mod shamir;
 pub mod signature;
-pub mod term;
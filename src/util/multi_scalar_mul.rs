@@ -0,0 +1,161 @@
+use backend::native::JacobianPoint;
+use point::jacobian_point::wnaf_table;
+use point::jacobian_point_add;
+use scalar::ScalarN;
+
+// Both algorithms first split every scalar with the GLV endomorphism, so they work on twice the
+// terms with scalars lower than 2^128 and do half the doublings.
+// Strauss walks the wNAF of every term together, sharing the doublings but keeping a table per
+// term. Pippenger sorts the points in buckets by their digit in every window of c bits, so it adds
+// each point once per window whatever its digit, and wins once the terms are many.
+
+/// Bits of the scalars after the GLV split
+const HALF_BITS: usize = 128;
+/// Window of the Strauss wNAF
+const STRAUSS_W: i8 = 5;
+/// Above this number of terms `multi_scalar_mul` switches from Strauss to Pippenger, the
+/// "multi scalar mul" bench has them even between 32 and 64 terms
+pub const PIPPENGER_THRESHOLD: usize = 48;
+
+/// Sum of coeff * point, `None` if it is the point at infinity
+pub fn multi_scalar_mul(terms: &[(ScalarN, JacobianPoint)]) -> Option<JacobianPoint> {
+    if terms.len() > PIPPENGER_THRESHOLD {
+        pippenger(terms)
+    } else {
+        strauss(terms)
+    }
+}
+
+// k * P = k1 * P + k2 * lambda(P), with the negative halves moved to the points
+fn split(terms: &[(ScalarN, JacobianPoint)]) -> Vec<(ScalarN, JacobianPoint)> {
+    let mut halves = Vec::with_capacity(terms.len() * 2);
+    for (k, point) in terms.iter() {
+        let (k1, k2) = k.split_lambda();
+        for (half, point) in [(k1, point.clone()), (k2, point.endomorphism())] {
+            if half.is_zero() {
+                continue;
+            }
+            if half.is_high() {
+                halves.push((half.negate(), point.negate()));
+            } else {
+                halves.push((half, point));
+            }
+        }
+    }
+    halves
+}
+
+pub fn strauss(terms: &[(ScalarN, JacobianPoint)]) -> Option<JacobianPoint> {
+    let halves = split(terms);
+    let nafs: Vec<Vec<i8>> = halves.iter().map(|(k, _)| k.clone().to_wnaf(STRAUSS_W)).collect();
+    let tables: Vec<Vec<JacobianPoint>> = halves.iter().map(|(_, point)| wnaf_table(point, STRAUSS_W)).collect();
+    let max = 2i8.pow(STRAUSS_W as u32 - 1) - 1;
+    let len = nafs.iter().map(Vec::len).max().unwrap_or(0);
+
+    let mut acc: Option<JacobianPoint> = None;
+    for i in 0..len {
+        if acc.is_some() {
+            acc = acc.unwrap().double();
+        }
+        // the wNAF are aligned on the least significant digit
+        for (naf, table) in nafs.iter().zip(tables.iter()) {
+            if i + naf.len() < len {
+                continue;
+            }
+            let el = naf[i + naf.len() - len];
+            if el != 0i8 {
+                let index = (el + max) / 2;
+                acc = jacobian_point_add(acc.as_ref(), table.get(index as usize));
+            }
+        }
+    }
+    acc
+}
+
+// the window minimizing the additions, (bits / c) * (terms + 2^(c+1))
+fn pippenger_window(terms: usize) -> usize {
+    (1..16)
+        .min_by_key(|c| HALF_BITS.div_ceil(*c) * (terms + (1 << (c + 1))))
+        .unwrap()
+}
+
+pub fn pippenger(terms: &[(ScalarN, JacobianPoint)]) -> Option<JacobianPoint> {
+    let halves = split(terms);
+    let c = pippenger_window(halves.len());
+    let windows = HALF_BITS.div_ceil(c);
+
+    let mut acc: Option<JacobianPoint> = None;
+    for window in (0..windows).rev() {
+        for _ in 0..c {
+            if acc.is_some() {
+                acc = acc.unwrap().double();
+            }
+        }
+        // the bucket d - 1 collects the points whose digit is d
+        let mut buckets: Vec<Option<JacobianPoint>> = vec![None; (1 << c) - 1];
+        for (k, point) in halves.iter() {
+            let mut digit = 0usize;
+            for bit in (window * c..(window * c + c).min(HALF_BITS)).rev() {
+                digit = (digit << 1) | k.bit(bit) as usize;
+            }
+            if digit != 0 {
+                buckets[digit - 1] = jacobian_point_add(buckets[digit - 1].as_ref(), Some(point));
+            }
+        }
+        // sum of d * bucket(d) with running sums, the highest bucket is added d times
+        let mut running: Option<JacobianPoint> = None;
+        let mut sum: Option<JacobianPoint> = None;
+        for bucket in buckets.iter().rev() {
+            running = jacobian_point_add(running.as_ref(), bucket.as_ref());
+            sum = jacobian_point_add(sum.as_ref(), running.as_ref());
+        }
+        acc = jacobian_point_add(acc.as_ref(), sum.as_ref());
+    }
+    acc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use context::CONTEXT;
+    use point::jacobian_point_mul;
+    use rand::prelude::*;
+
+    fn naive(terms: &[(ScalarN, JacobianPoint)]) -> Option<JacobianPoint> {
+        terms.iter().fold(None, |acc, (k, point)| {
+            jacobian_point_add(acc.as_ref(), jacobian_point_mul(point, k).as_ref())
+        })
+    }
+
+    fn random_terms(size: usize) -> Vec<(ScalarN, JacobianPoint)> {
+        let mut rng = thread_rng();
+        (0..size)
+            .map(|_| {
+                let point = jacobian_point_mul(&CONTEXT.G_jacobian, &rng.gen()).unwrap();
+                (rng.gen(), point)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_against_naive() {
+        for size in [0usize, 1, 2, 7, 30].iter() {
+            let terms = random_terms(*size);
+            let expected = naive(&terms);
+            assert_eq!(expected, strauss(&terms));
+            assert_eq!(expected, pippenger(&terms));
+            assert_eq!(expected, multi_scalar_mul(&terms));
+        }
+    }
+
+    #[test]
+    fn test_infinity() {
+        // every k * P is cancelled by (n - k) * P, and a zero coefficient adds nothing
+        let mut terms = random_terms(3);
+        let negated: Vec<(ScalarN, JacobianPoint)> = terms.iter().map(|(k, point)| (k.negate(), point.clone())).collect();
+        terms.extend(negated);
+        terms.push((ScalarN::from_u64(0), CONTEXT.G_jacobian.clone()));
+        assert_eq!(None, strauss(&terms));
+        assert_eq!(None, pippenger(&terms));
+    }
+}
@@ -9,7 +9,7 @@ pub fn integer_from_bytes(val: &[u8]) -> Integer {
 
 pub fn mul_and_rem(mut a512: Integer, b256: &Integer) -> Integer {
     a512 *= b256;
-    a512.rem(&CONTEXT.p.0)
+    a512.rem(&CONTEXT.p)
 }
 
 #[cfg(test)]
@@ -35,7 +35,7 @@ mod tests {
         buffer.assign(&a);
 
         let val1 = mul_and_rem(buffer, &b);
-        let val2 = (a * &b) % &CONTEXT.p.0;
+        let val2 = (a * &b) % &CONTEXT.p;
         assert_eq!(val1, val2);
     }
 
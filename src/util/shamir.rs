@@ -1,7 +1,6 @@
+use backend::native::JacobianPoint;
 use point::jacobian_point::jacobian_point_double;
 use point::jacobian_point_add;
-use point::JacobianPoint;
-use rug::Integer;
 use scalar::ScalarN;
 
 #[allow(non_snake_case)]
@@ -14,13 +13,10 @@ pub fn shamirs_trick(k: ScalarN, P: JacobianPoint, l: ScalarN, Q: JacobianPoint)
     precomputed.push(jacobian_point_add(Some(&Q), Some(&P)));
 
     let mut acc: Option<JacobianPoint> = None;
-    let mut exponent: Integer = Integer::from(1) << 255;
 
-    loop {
-        let a1: Integer = (&k.0 & &exponent).into();
-        let a = (a1 != 0) as usize;
-        let b1: Integer = (&l.0 & &exponent).into();
-        let b = (b1 != 0) as usize;
+    for i in (0..256).rev() {
+        let a = k.bit(i) as usize;
+        let b = l.bit(i) as usize;
         let index = a * 2 + b;
         let current = precomputed[index].to_owned();
 
@@ -30,10 +26,6 @@ pub fn shamirs_trick(k: ScalarN, P: JacobianPoint, l: ScalarN, Q: JacobianPoint)
         if current.is_some() {
             acc = jacobian_point_add(acc.as_ref(), current.as_ref());
         }
-        exponent = exponent >> 1;
-        if exponent == 0 {
-            break;
-        }
     }
 
     acc.unwrap()
@@ -43,15 +35,14 @@ pub fn shamirs_trick(k: ScalarN, P: JacobianPoint, l: ScalarN, Q: JacobianPoint)
 mod tests {
 
     use super::shamirs_trick;
+    use backend::native::Point;
     use context::CONTEXT;
     use point::generator_mul;
     use point::jacobian_point_add;
     use point::point_add;
     use point::point_mul;
-    use point::Point;
     use rand::thread_rng;
     use rand::Rng;
-    use rug::Integer;
     use scalar::ScalarN;
 
     #[test]
@@ -75,24 +66,18 @@ mod tests {
         let mut rng = thread_rng();
         let P = CONTEXT.G.clone();
         let k = rng.gen::<ScalarN>();
-        let two_scalar_n = ScalarN(Integer::from(2));
+        let two_scalar_n = ScalarN::from_u64(2);
 
-        let mut exponent: Integer = Integer::from(1) << 255;
         let mut acc: Option<Point> = None;
 
-        loop {
-            let val: Integer = (&k.0 & &exponent).into();
-            let a: bool = val != 0;
+        for i in (0..256).rev() {
+            let a = k.bit(i);
             if acc.is_some() {
                 acc = point_mul(acc.unwrap(), two_scalar_n.clone());
             }
             if a {
                 acc = point_add(acc, Some(P.clone()));
             }
-            exponent = exponent >> 1;
-            if exponent == 0 {
-                break;
-            }
         }
 
         assert_eq!(P.mul(&k), acc.unwrap());
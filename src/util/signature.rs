@@ -1,41 +1,27 @@
-use context::CONTEXT;
+use backend::{DefaultField, DefaultScalar, FieldOps, ScalarOps};
+use error::{check_length, Error};
 use scalar::ScalarN;
 use scalar::ScalarP;
 use std::fmt;
-use util::rug::integer_from_bytes;
 
 #[allow(non_snake_case)]
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
-pub struct Signature {
-    pub Rx: ScalarP,
-    pub s: ScalarN,
+pub struct Signature<F = DefaultField, S = DefaultScalar> {
+    pub Rx: F,
+    pub s: S,
 }
 
-impl fmt::Display for Signature {
+impl<F: FieldOps, S: ScalarOps> fmt::Display for Signature<F, S> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "(Rx {:?}, s {:?})", self.Rx, self.s)
     }
 }
 
 #[allow(non_snake_case)]
-impl Signature {
-    pub fn new(Rx: ScalarP, s: ScalarN) -> Self {
+impl<F: FieldOps, S: ScalarOps> Signature<F, S> {
+    pub fn new(Rx: F, s: S) -> Self {
         Signature { Rx, s }
     }
-    pub fn from_bytes(bytes: &[u8]) -> Result<Self,()> {
-        assert_eq!(bytes.len(), 64);
-        let Rx = integer_from_bytes(&bytes[..32]);
-        if Rx >= CONTEXT.p.0 {
-            return Err(());
-        }
-        let Rx = ScalarP(Rx);
-        let s = integer_from_bytes(&bytes[32..]);
-        if s >= CONTEXT.n.0 {
-            return Err(());
-        }
-        let s = ScalarN(s);
-        Ok(Signature { Rx, s })
-    }
 
     pub fn as_bytes(&self) -> Vec<u8> {
         let mut vec = Vec::with_capacity(64);
@@ -44,3 +30,13 @@ impl Signature {
         vec
     }
 }
+
+#[allow(non_snake_case)]
+impl Signature<ScalarP, ScalarN> {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        check_length(bytes, 64)?;
+        let Rx = ScalarP::try_from_bytes(&bytes[..32])?;
+        let s = ScalarN::try_from_bytes(&bytes[32..])?;
+        Ok(Signature { Rx, s })
+    }
+}